@@ -0,0 +1,12 @@
+use criterion::Criterion;
+
+// shared by every benchmark, changes smaller than 2% are treated as noise and the rest are only
+// reported as a regression or improvement when significant at the 5% level
+//
+// criterion itself never fails on a regression, scripts/bench.sh compares against a saved
+// baseline and fails when one is reported
+pub fn criterion() -> Criterion {
+    Criterion::default()
+        .significance_level(0.05)
+        .noise_threshold(0.02)
+}
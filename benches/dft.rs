@@ -4,9 +4,12 @@ use fftw::array::AlignedVec;
 use fftw::plan::{R2CPlan, R2CPlan64};
 use fftw::types::Flag;
 
+use spectrogram::transform::fftw as fftw_transform;
 #[allow(unused_imports)]
 use spectrogram::transform::{naive, naive_simd};
 
+mod common;
+
 pub fn criterion_benchmark(c: &mut Criterion) {
     // TODO
     // * figure out proper plan createion outside of benchmark time
@@ -27,34 +30,54 @@ pub fn criterion_benchmark(c: &mut Criterion) {
         })
     });
 
-    // the following were used to learn about SIMD
-    // benchmarks of naive vs naive_simd implementations
-    // simd results in ~17% speedup
+    // one MEASURE plan shared by every column rather than one per column
+    let windows: Vec<Vec<f64>> = (0..100)
+        .map(|w| (0..512).map(|x| ((x * w * 7919) % 2000) as f64).collect())
+        .collect();
+    c.bench_function("individual_fftw_100x512", |b| {
+        b.iter(|| {
+            for window in &windows {
+                fftw_transform::fourier_transform(black_box(window));
+            }
+        })
+    });
+    c.bench_function("batch_fftw_100x512", |b| {
+        b.iter(|| fftw_transform::batch_fourier_transform(black_box(&windows)))
+    });
+
+    // the following were used to learn about SIMD, benches/simd_vs_scalar.rs compares the
+    // naive and naive_simd implementations directly and scripts/bench.sh compares any of
+    // these against a saved baseline
     c.bench_function("vanilla_dft", |b| {
-        b.iter(|| naive_simd::fourier_transform(black_box(vec![1, 0, 0, 0, 0, 0, 0, 0])))
+        b.iter(|| naive_simd::fourier_transform(black_box(vec![1, 0, 0, 0, 0, 0, 0, 0]), false))
     });
 
-    // simd results in ~17% speedup
     c.bench_function("non_trivial_dft", |b| {
         b.iter(|| {
-            naive_simd::fourier_transform(black_box(vec![100, 211, 62, 116, 34, 98, 178, 12]))
+            naive_simd::fourier_transform(
+                black_box(vec![100, 211, 62, 116, 34, 98, 178, 12]),
+                false,
+            )
         })
     });
 
-    // simd results in ~7% speedup
     c.bench_function("indivisible_by_eight_dft", |b| {
         b.iter(|| {
-            naive_simd::fourier_transform(black_box(vec![
-                100, 211, 62, 116, 34, 98, 178, 12, 201, 145, 178,
-            ]))
+            naive_simd::fourier_transform(
+                black_box(vec![100, 211, 62, 116, 34, 98, 178, 12, 201, 145, 178]),
+                false,
+            )
         })
     });
 
-    // simd results in ~23% speedup
     c.bench_function("large_dft", |b| {
-        b.iter(|| naive_simd::fourier_transform(black_box((0..=7999).collect())))
+        b.iter(|| naive_simd::fourier_transform(black_box((0..=7999).collect()), false))
     });
 }
 
-criterion_group!(benches, criterion_benchmark);
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = criterion_benchmark
+}
 criterion_main!(benches);
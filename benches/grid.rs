@@ -0,0 +1,43 @@
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion};
+
+use spectrogram::transform::Backend;
+use spectrogram::units::Scale;
+use spectrogram::widgets::grid::Grid;
+
+mod common;
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let mut group = c.benchmark_group("calculate_frequencies");
+    // (n_columns, n_rows) where n_rows is both the column span and the fft window size
+    let resolutions: [(usize, usize); 5] =
+        [(10, 512), (50, 512), (100, 512), (100, 1024), (100, 2048)];
+    for (n_columns, n_rows) in resolutions {
+        // deterministic noise spanning the full i16 range
+        let samples: Vec<i16> = (0..n_columns * n_rows)
+            .map(|x| (x * 7919 % 65536) as u16 as i16)
+            .collect();
+        let resolution = (n_columns as u32, n_rows as u32 / 2);
+        let mut grid = Grid::new(
+            resolution.0,
+            44100,
+            20,
+            Scale::default(),
+            Scale::default(),
+            Backend::default(),
+        );
+        grid.window_size = n_rows;
+        group.bench_with_input(
+            BenchmarkId::from_parameter(format!("{}x{}", n_columns, n_rows)),
+            &samples,
+            |b, samples| b.iter(|| grid.update_frequencies(resolution, black_box(samples))),
+        );
+    }
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
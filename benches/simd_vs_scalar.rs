@@ -0,0 +1,73 @@
+#![feature(portable_simd)]
+
+use std::simd::f64x8;
+
+use criterion::{black_box, criterion_group, criterion_main, BenchmarkId, Criterion, Throughput};
+
+use spectrogram::transform::naive_simd::{calculate_kth_nth, simd_calculate_kth_x8};
+
+mod common;
+
+// calls per iteration, each covering eight samples
+const N_CALLS: usize = 10_000;
+const N_SAMPLES: usize = 1024;
+const K: usize = 37;
+
+// what std::simd lowers f64x8 to in this build, so results from different machines can be
+// told apart when comparing against a scalar only implementation
+#[cfg(target_arch = "aarch64")]
+const INSTRUCTION_SET: &str = "neon";
+#[cfg(all(target_arch = "x86_64", target_feature = "avx2"))]
+const INSTRUCTION_SET: &str = "avx2";
+#[cfg(all(target_arch = "x86_64", not(target_feature = "avx2")))]
+const INSTRUCTION_SET: &str = "sse2";
+#[cfg(not(any(target_arch = "aarch64", target_arch = "x86_64")))]
+const INSTRUCTION_SET: &str = "generic";
+
+pub fn criterion_benchmark(c: &mut Criterion) {
+    let samples = [100.0, 211.0, 62.0, 116.0, 34.0, 98.0, 178.0, 12.0];
+    let mut group = c.benchmark_group("calculate_kth");
+    group.throughput(Throughput::Elements((N_CALLS * samples.len()) as u64));
+
+    group.bench_function(
+        BenchmarkId::new("simd_calculate_kth_x8", INSTRUCTION_SET),
+        |b| {
+            let x_n = f64x8::from_array(samples);
+            let n = f64x8::from_array([0.0, 1.0, 2.0, 3.0, 4.0, 5.0, 6.0, 7.0]);
+            let n_samples = f64x8::splat(N_SAMPLES as f64);
+            let k = f64x8::splat(K as f64);
+            b.iter(|| {
+                for _ in 0..N_CALLS {
+                    black_box(simd_calculate_kth_x8(
+                        black_box(x_n),
+                        black_box(n),
+                        n_samples,
+                        k,
+                    ));
+                }
+            })
+        },
+    );
+
+    // the same eight samples one at a time
+    group.bench_function(
+        BenchmarkId::new("calculate_kth_nth", INSTRUCTION_SET),
+        |b| {
+            b.iter(|| {
+                for _ in 0..N_CALLS {
+                    for (n, x_n) in samples.iter().enumerate() {
+                        black_box(calculate_kth_nth(black_box(x_n), n, N_SAMPLES, K));
+                    }
+                }
+            })
+        },
+    );
+    group.finish();
+}
+
+criterion_group! {
+    name = benches;
+    config = common::criterion();
+    targets = criterion_benchmark
+}
+criterion_main!(benches);
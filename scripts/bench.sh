@@ -0,0 +1,42 @@
+#!/bin/bash
+# records and compares against criterion baselines of the benchmarks, eg.
+#
+#   git checkout master && scripts/bench.sh save
+#   git checkout my-branch && scripts/bench.sh compare
+#
+# the baseline name defaults to master, extra cargo arguments can be given in
+# CARGO_BENCH_ARGS, eg. CARGO_BENCH_ARGS="--features nightly_simd"
+#
+# no baseline is committed as timings only mean something on the machine that recorded them,
+# run save first on the commit to compare against. baselines are kept in target/criterion,
+# the html report there shows each comparison
+set -euo pipefail
+
+mode=${1:-}
+baseline=${2:-master}
+read -r -a cargo_args <<< "${CARGO_BENCH_ARGS:-}"
+
+case $mode in
+    save)
+        cargo bench ${cargo_args[@]+"${cargo_args[@]}"} -- --save-baseline "$baseline"
+        ;;
+    compare)
+        criterion_dir="${CARGO_TARGET_DIR:-target}/criterion"
+        if ! find "$criterion_dir" -mindepth 2 -maxdepth 4 -type d -name "$baseline" 2>/dev/null | grep -q .; then
+            echo "no $baseline baseline in $criterion_dir, record one first with: $0 save $baseline" >&2
+            exit 2
+        fi
+        output=$(mktemp)
+        trap 'rm -f "$output"' EXIT
+        cargo bench ${cargo_args[@]+"${cargo_args[@]}"} -- --baseline "$baseline" | tee "$output"
+        # thresholds are set in benches/common/mod.rs, the pattern allows for colored output
+        if grep -qE "Performance has .*regressed" "$output"; then
+            echo "benchmarks regressed against the $baseline baseline" >&2
+            exit 1
+        fi
+        ;;
+    *)
+        echo "usage: $0 save|compare [baseline]" >&2
+        exit 2
+        ;;
+esac
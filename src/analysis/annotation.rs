@@ -0,0 +1,132 @@
+use iced::Color;
+use serde::{Deserialize, Serialize};
+
+use crate::error::SpectrogramError;
+
+// given to regions as they are selected, before the user has picked anything else
+pub const DEFAULT_COLOR: Color = Color::from_rgb(1.0, 0.8, 0.0);
+
+// iced's Color has no serde support of its own
+#[derive(Serialize, Deserialize)]
+#[serde(remote = "Color")]
+struct ColorDef {
+    r: f32,
+    g: f32,
+    b: f32,
+    a: f32,
+}
+
+// a labelled time frequency region, eg. an event noted while reviewing a recording
+// times are in seconds and frequencies in Hz
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct Annotation {
+    pub time_start: f32,
+    pub time_end: f32,
+    pub freq_min: f32,
+    pub freq_max: f32,
+    pub label: String,
+    #[serde(with = "ColorDef")]
+    pub color: Color,
+}
+
+// free text placed at a point on the grid, eg. a timestamp or recording note
+// time is in seconds and frequency in Hz
+#[derive(Clone, Debug, PartialEq, Serialize, Deserialize)]
+pub struct TextLabel {
+    pub time: f32,
+    pub frequency: f32,
+    pub text: String,
+}
+
+#[derive(Serialize)]
+struct AnnotationFile<'a> {
+    annotations: &'a [Annotation],
+    labels: &'a [TextLabel],
+}
+
+// files saved before text labels were added hold only the array of annotations
+#[derive(Deserialize)]
+#[serde(untagged)]
+enum AnnotationJson {
+    File {
+        annotations: Vec<Annotation>,
+        #[serde(default)]
+        labels: Vec<TextLabel>,
+    },
+    Annotations(Vec<Annotation>),
+}
+
+pub fn to_json(
+    annotations: &[Annotation],
+    labels: &[TextLabel],
+) -> Result<String, SpectrogramError> {
+    let file = AnnotationFile {
+        annotations,
+        labels,
+    };
+    Ok(serde_json::to_string_pretty(&file)?)
+}
+
+pub fn from_json(json: &str) -> Result<(Vec<Annotation>, Vec<TextLabel>), SpectrogramError> {
+    Ok(match serde_json::from_str(json)? {
+        AnnotationJson::File {
+            annotations,
+            labels,
+        } => (annotations, labels),
+        AnnotationJson::Annotations(annotations) => (annotations, vec![]),
+    })
+}
+
+#[cfg(test)]
+mod test_json {
+    use super::{from_json, to_json, Annotation, TextLabel};
+    use iced::Color;
+
+    fn annotation() -> Annotation {
+        Annotation {
+            time_start: 1.5,
+            time_end: 2.0,
+            freq_min: 50.0,
+            freq_max: 70.0,
+            label: "hum".to_string(),
+            color: Color::from_rgba(1.0, 0.0, 0.0, 0.5),
+        }
+    }
+
+    fn label() -> TextLabel {
+        TextLabel {
+            time: 3.25,
+            frequency: 440.0,
+            text: "door slams".to_string(),
+        }
+    }
+
+    #[test]
+    fn there_and_back_again() {
+        let annotations = vec![annotation(), annotation()];
+        let labels = vec![label()];
+        assert_eq!(
+            (annotations.clone(), labels.clone()),
+            from_json(&to_json(&annotations, &labels).unwrap()).unwrap()
+        );
+    }
+
+    #[test]
+    fn fields() {
+        let json = to_json(&[annotation()], &[label()]).unwrap();
+        assert!(json.contains("\"label\": \"hum\""));
+        assert!(json.contains("\"a\": 0.5"));
+        assert!(json.contains("\"text\": \"door slams\""));
+    }
+
+    #[test]
+    fn annotations_only() {
+        let json = serde_json::to_string(&[annotation()]).unwrap();
+        assert_eq!((vec![annotation()], vec![]), from_json(&json).unwrap());
+    }
+
+    #[test]
+    fn invalid() {
+        assert!(from_json("[{\"label\": \"hum\"}]").is_err());
+    }
+}
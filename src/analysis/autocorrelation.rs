@@ -0,0 +1,161 @@
+// cumulative mean normalized differences below this mark a period, 0.1 to 0.15 in the paper
+const YIN_THRESHOLD: f64 = 0.1;
+
+// autocorrelation at lags 0..=max_lag divided by the autocorrelation at lag 0,
+// so lag 0 is 1.0 and a periodic signal peaks again at multiples of its period
+pub fn normalized_autocorrelation(samples: &[f64], max_lag: usize) -> Vec<f64> {
+    if samples.is_empty() {
+        return vec![];
+    }
+    let max_lag = max_lag.min(samples.len() - 1);
+    let correlations: Vec<f64> = (0..=max_lag)
+        .map(|lag| {
+            samples
+                .iter()
+                .zip(samples[lag..].iter())
+                .map(|(a, b)| a * b)
+                .sum()
+        })
+        .collect();
+    let energy = correlations[0];
+    if energy == 0.0 {
+        return vec![0.0; correlations.len()];
+    }
+    correlations.iter().map(|x| x / energy).collect()
+}
+
+// de Cheveigne and Kawahara's YIN, the fundamental between f_min and f_max or None when
+// no lag is periodic enough, samples should span at least two periods of f_min
+pub fn yin_pitch(samples: &[f64], sample_rate: u32, f_min: f32, f_max: f32) -> Option<f32> {
+    if f_min <= 0.0 || f_max <= f_min {
+        return None;
+    }
+    let min_lag = ((sample_rate as f32 / f_max).floor() as usize).max(2);
+    let max_lag = (sample_rate as f32 / f_min).ceil() as usize;
+    // the difference function compares a window of this many samples against a shifted copy
+    let window = samples.len().checked_sub(max_lag + 1)?;
+    if window < max_lag {
+        return None;
+    }
+
+    // squared difference between the window and itself shifted by each lag
+    let difference: Vec<f64> = (0..=max_lag + 1)
+        .map(|lag| {
+            samples[..window]
+                .iter()
+                .zip(samples[lag..lag + window].iter())
+                .map(|(a, b)| (a - b) * (a - b))
+                .sum()
+        })
+        .collect();
+
+    // each difference divided by the mean of those at smaller lags, removing the dip at lag 0
+    let mut normalized = vec![1.0; difference.len()];
+    let mut running_sum = 0.0;
+    for lag in 1..difference.len() {
+        running_sum += difference[lag];
+        normalized[lag] = if running_sum == 0.0 {
+            1.0
+        } else {
+            difference[lag] * lag as f64 / running_sum
+        };
+    }
+
+    // first dip below the threshold, followed down to the bottom of that dip
+    let mut lag = (min_lag..=max_lag).find(|lag| normalized[*lag] < YIN_THRESHOLD)?;
+    while lag < max_lag && normalized[lag + 1] < normalized[lag] {
+        lag += 1;
+    }
+
+    // parabola through the dip and its neighbours, for a period between whole samples
+    let (before, at, after) = (normalized[lag - 1], normalized[lag], normalized[lag + 1]);
+    let denominator = before - 2.0 * at + after;
+    let offset = if denominator.abs() > f64::EPSILON {
+        (0.5 * (before - after) / denominator).clamp(-0.5, 0.5)
+    } else {
+        0.0
+    };
+    Some(sample_rate as f32 / (lag as f64 + offset) as f32)
+}
+
+#[cfg(test)]
+mod test_normalized_autocorrelation {
+    use super::normalized_autocorrelation;
+    use crate::test_signals::unit_sine;
+
+    #[test]
+    fn lag_zero() {
+        let autocorrelation = normalized_autocorrelation(&[1.0, -2.0, 3.0], 5);
+        assert_eq!(3, autocorrelation.len());
+        assert_eq!(1.0, autocorrelation[0]);
+    }
+
+    #[test]
+    fn period() {
+        // a period of 20 samples
+        let samples = unit_sine(1.0, 20, 400, 0.0);
+        let autocorrelation = normalized_autocorrelation(&samples, 30);
+        let peak = (5..=30)
+            .max_by(|a, b| {
+                autocorrelation[*a]
+                    .partial_cmp(&autocorrelation[*b])
+                    .unwrap()
+            })
+            .unwrap();
+        assert_eq!(20, peak)
+    }
+
+    #[test]
+    fn silence() {
+        assert_eq!(vec![0.0; 3], normalized_autocorrelation(&[0.0; 10], 2))
+    }
+}
+
+#[cfg(test)]
+mod test_yin_pitch {
+    use super::yin_pitch;
+    use crate::test_signals::unit_sine;
+    use std::f64::consts::TAU;
+
+    // box muller transform of linear congruential noise, with a standard deviation of 1.0
+    fn gaussian_noise(n: usize) -> Vec<f64> {
+        let mut seed: u32 = 1;
+        let mut uniform = || {
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            ((seed >> 8) as f64 + 1.0) / (1u32 << 24) as f64
+        };
+        (0..n)
+            .map(|_| (-2.0 * uniform().ln()).sqrt() * (TAU * uniform()).cos())
+            .collect()
+    }
+
+    #[test]
+    fn noisy_sine() {
+        let samples: Vec<f64> = unit_sine(440.0, 44100, 4096, 0.0)
+            .iter()
+            .zip(gaussian_noise(4096))
+            .map(|(sine, noise)| sine + 0.01 * noise)
+            .collect();
+        let pitch = yin_pitch(&samples, 44100, 50.0, 2000.0).unwrap();
+        assert!((pitch - 440.0).abs() < 1.0, "{}", pitch)
+    }
+
+    #[test]
+    fn low_pitch() {
+        let pitch = yin_pitch(&unit_sine(82.41, 8000, 2048, 0.0), 8000, 50.0, 1000.0).unwrap();
+        assert!((pitch - 82.41).abs() < 0.5, "{}", pitch)
+    }
+
+    #[test]
+    fn noise_has_no_pitch() {
+        assert_eq!(None, yin_pitch(&gaussian_noise(4096), 44100, 50.0, 2000.0))
+    }
+
+    #[test]
+    fn too_short() {
+        assert_eq!(
+            None,
+            yin_pitch(&unit_sine(440.0, 44100, 1000, 0.0), 44100, 50.0, 2000.0)
+        )
+    }
+}
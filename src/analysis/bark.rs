@@ -0,0 +1,130 @@
+// frequencies spanned by the filterbank, roughly the range of human hearing
+pub const MIN_HZ: f32 = 20.0;
+pub const MAX_HZ: f32 = 20000.0;
+// fraction of a band over which neighbouring filters cross fade, half on each side of an edge
+const SKIRT: f32 = 0.5;
+
+// Zwicker and Terhardt's approximation of the critical band rate
+pub fn hz_to_bark(hz: f32) -> f32 {
+    13.0 * (0.00076 * hz).atan() + 3.5 * (hz / 7500.0).powi(2).atan()
+}
+
+// hz_to_bark has no closed form inverse, but it is monotonic so it can be bisected
+pub fn bark_to_hz(bark: f32) -> f32 {
+    let (mut low, mut high) = (0.0, 100_000.0);
+    for _ in 0..64 {
+        let mid = (low + high) / 2.0;
+        if hz_to_bark(mid) < bark {
+            low = mid;
+        } else {
+            high = mid;
+        }
+    }
+    (low + high) / 2.0
+}
+
+// n_filters bands of equal width in Bark spanning MIN_HZ to MAX_HZ, each a weight for
+// every bin of an n_fft point transform
+//
+// filters are trapezoids, flat inside their band with linear skirts which cross fade into
+// the neighbouring bands so the weights of all filters sum to 1.0 anywhere in the range
+pub fn bark_filterbank(n_filters: usize, n_fft: usize, sample_rate: u32) -> Vec<Vec<f32>> {
+    let n_bins = n_fft / 2 + 1;
+    let (low, high) = (hz_to_bark(MIN_HZ), hz_to_bark(MAX_HZ));
+    let width = (high - low) / n_filters as f32;
+    let skirt = width * SKIRT / 2.0;
+    let bin_barks: Vec<f32> = (0..n_bins)
+        .map(|bin| hz_to_bark(bin as f32 * sample_rate as f32 / n_fft as f32))
+        .collect();
+
+    (0..n_filters)
+        .map(|filter| {
+            let start = low + filter as f32 * width;
+            let end = start + width;
+            bin_barks
+                .iter()
+                .map(|bark| {
+                    if *bark < low || *bark > high {
+                        return 0.0;
+                    }
+                    // the outermost edges of the range have no neighbour to fade into
+                    let rising = if filter == 0 {
+                        1.0
+                    } else {
+                        (bark - (start - skirt)) / (2.0 * skirt)
+                    };
+                    let falling = if filter + 1 == n_filters {
+                        1.0
+                    } else {
+                        ((end + skirt) - bark) / (2.0 * skirt)
+                    };
+                    rising.min(falling).clamp(0.0, 1.0)
+                })
+                .collect()
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_hz_to_bark {
+    use super::{bark_to_hz, hz_to_bark};
+
+    #[test]
+    fn one_khz() {
+        assert!((hz_to_bark(1000.0) - 8.51).abs() < 0.01)
+    }
+
+    #[test]
+    fn inverse() {
+        for hz in [20.0, 440.0, 5000.0, 18000.0].iter() {
+            assert!((bark_to_hz(hz_to_bark(*hz)) - hz).abs() < 0.01 * hz)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_bark_filterbank {
+    use super::{bark_filterbank, hz_to_bark, MAX_HZ, MIN_HZ};
+
+    const SAMPLE_RATE: u32 = 48000;
+    const N_FFT: usize = 4096;
+
+    fn bin_hz(bin: usize) -> f32 {
+        bin as f32 * SAMPLE_RATE as f32 / N_FFT as f32
+    }
+
+    #[test]
+    fn shape() {
+        let filterbank = bark_filterbank(24, N_FFT, SAMPLE_RATE);
+        assert_eq!(24, filterbank.len());
+        assert!(filterbank
+            .iter()
+            .all(|filter| filter.len() == N_FFT / 2 + 1))
+    }
+
+    #[test]
+    fn covers_hearing() {
+        // 24 bands of about one Bark each, together covering 20 Hz to 20 kHz
+        let filterbank = bark_filterbank(24, N_FFT, SAMPLE_RATE);
+        assert!((hz_to_bark(MAX_HZ) - hz_to_bark(MIN_HZ) - 24.0).abs() < 1.0);
+        for bin in 0..=N_FFT / 2 {
+            let total: f32 = filterbank.iter().map(|filter| filter[bin]).sum();
+            if (MIN_HZ..=MAX_HZ).contains(&bin_hz(bin)) {
+                assert!((total - 1.0).abs() < 1e-4, "{} Hz", bin_hz(bin))
+            } else {
+                assert_eq!(0.0, total, "{} Hz", bin_hz(bin))
+            }
+        }
+    }
+
+    #[test]
+    fn bands_ascend() {
+        // the loudest weight of each filter is above that of the one below it
+        let filterbank = bark_filterbank(24, N_FFT, SAMPLE_RATE);
+        let peaks: Vec<usize> = filterbank
+            .iter()
+            .map(|filter| filter.iter().position(|weight| *weight == 1.0).unwrap())
+            .collect();
+        assert!(peaks.windows(2).all(|pair| pair[0] < pair[1]))
+    }
+}
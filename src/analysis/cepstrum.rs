@@ -0,0 +1,58 @@
+use num::Complex;
+
+use crate::transform::{inverse_fourier_transform, Backend};
+
+// keeps silent bins from taking the log of zero
+const MAGNITUDE_FLOOR: f64 = 1e-10;
+
+// inverse transform of the log magnitude spectrum, spectrum being the n / 2 + 1 bins of
+// a real valued frame of even length n
+// index i of the result is a quefrency of i / sample_rate seconds
+pub fn real_cepstrum(spectrum: &[Complex<f64>]) -> Vec<f64> {
+    if spectrum.len() < 2 {
+        return vec![];
+    }
+    let n_samples = (spectrum.len() - 1) * 2;
+    let log_magnitudes: Vec<Complex<f64>> = spectrum
+        .iter()
+        .map(|x| Complex::new(x.norm().max(MAGNITUDE_FLOOR).ln(), 0.0))
+        .collect();
+    inverse_fourier_transform(&log_magnitudes, n_samples, Backend::default())
+}
+
+#[cfg(test)]
+mod test_real_cepstrum {
+    use super::real_cepstrum;
+    use crate::transform::{fourier_transform, Backend};
+
+    #[test]
+    fn impulse() {
+        // a flat spectrum has a log magnitude of zero everywhere
+        let mut samples = vec![0; 16];
+        samples[0] = 1;
+        let cepstrum = real_cepstrum(&fourier_transform(&samples, Backend::default()));
+        assert_eq!(16, cepstrum.len());
+        assert!(cepstrum.iter().all(|x| x.abs() < 1e-9))
+    }
+
+    #[test]
+    fn echo() {
+        // an echo 100 samples later shows up as a peak at a quefrency of 100 samples
+        let mut samples = vec![0; 1024];
+        let mut seed: u32 = 1;
+        for sample in samples.iter_mut().take(400) {
+            // linear congruential noise, without any periodicity of its own
+            seed = seed.wrapping_mul(1_103_515_245).wrapping_add(12345);
+            *sample = (seed >> 16) as i16 / 32;
+        }
+        let original = samples.clone();
+        for i in 100..1024 {
+            samples[i] += original[i - 100] / 2;
+        }
+        let cepstrum = real_cepstrum(&fourier_transform(&samples, Backend::default()));
+        let peak = (20..512)
+            .max_by(|a, b| cepstrum[*a].partial_cmp(&cepstrum[*b]).unwrap())
+            .unwrap();
+        assert_eq!(100, peak)
+    }
+}
@@ -0,0 +1,106 @@
+// peak level of the samples in sample units, rising over attack_ms up to each peak and falling
+// over release_ms after it
+//
+// the whole signal is known up front, so the attack is followed backwards from each peak rather
+// than lagging behind it, which keeps the envelope at or above every sample
+pub fn envelope_follow(
+    samples: &[i16],
+    attack_ms: f32,
+    release_ms: f32,
+    sample_rate: u32,
+) -> Vec<f32> {
+    let magnitudes: Vec<f32> = samples
+        .iter()
+        .map(|sample| (*sample as f32).abs())
+        .collect();
+    let release = follow(magnitudes.iter(), coefficient(release_ms, sample_rate));
+    let mut attack = follow(magnitudes.iter().rev(), coefficient(attack_ms, sample_rate));
+    attack.reverse();
+    release
+        .iter()
+        .zip(attack.iter())
+        .map(|(release, attack)| release.max(*attack))
+        .collect()
+}
+
+// jumps up to louder magnitudes and decays towards quieter ones with a one pole filter
+fn follow<'a, I: Iterator<Item = &'a f32>>(magnitudes: I, coefficient: f32) -> Vec<f32> {
+    let mut level = 0.0;
+    magnitudes
+        .map(|magnitude| {
+            level = if *magnitude >= level {
+                *magnitude
+            } else {
+                magnitude + coefficient * (level - magnitude)
+            };
+            level
+        })
+        .collect()
+}
+
+// per sample decay which closes all but 1/e of the gap to the signal in time_ms,
+// zero or negative times follow the signal exactly
+fn coefficient(time_ms: f32, sample_rate: u32) -> f32 {
+    let n_samples = time_ms / 1000.0 * sample_rate as f32;
+    if n_samples <= 0.0 {
+        return 0.0;
+    }
+    (-1.0 / n_samples).exp()
+}
+
+#[cfg(test)]
+mod test_envelope_follow {
+    use super::envelope_follow;
+    use std::f32::consts::E;
+    use std::f64::consts::TAU;
+
+    fn impulse() -> Vec<i16> {
+        let mut samples = vec![0; 2000];
+        samples[1000] = 10000;
+        samples
+    }
+
+    #[test]
+    fn above_every_sample() {
+        let samples: Vec<i16> = (0..8000)
+            .map(|t| {
+                let tone = 8000.0 * (TAU * 440.0 * t as f64 / 8000.0).sin();
+                let noise = ((t * 7919) % 2001) as f64 - 1000.0;
+                let burst = if (3000..3100).contains(&t) { 3.0 } else { 1.0 };
+                (burst * (tone + noise)).clamp(i16::MIN as f64, i16::MAX as f64) as i16
+            })
+            .collect();
+        let envelope = envelope_follow(&samples, 5.0, 50.0, 8000);
+        assert_eq!(samples.len(), envelope.len());
+        for (sample, level) in samples.iter().zip(envelope.iter()) {
+            assert!(*level >= (*sample as f32).abs(), "{} {}", sample, level)
+        }
+    }
+
+    #[test]
+    fn release() {
+        // 10 ms at 8 kHz is 80 samples
+        let envelope = envelope_follow(&impulse(), 0.0, 10.0, 8000);
+        assert_eq!(10000.0, envelope[1000]);
+        assert!((envelope[1080] - 10000.0 / E).abs() < 1.0);
+        assert_eq!(0.0, envelope[999]);
+    }
+
+    #[test]
+    fn attack() {
+        let envelope = envelope_follow(&impulse(), 10.0, 0.0, 8000);
+        assert!((envelope[920] - 10000.0 / E).abs() < 1.0);
+        assert!(envelope[999] < envelope[1000]);
+        assert_eq!(0.0, envelope[1001]);
+    }
+
+    #[test]
+    fn instant() {
+        let samples = vec![3, -7, i16::MIN, 0];
+        assert_eq!(
+            vec![3.0, 7.0, 32768.0, 0.0],
+            envelope_follow(&samples, 0.0, 0.0, 8000)
+        );
+        assert!(envelope_follow(&[], 5.0, 50.0, 8000).is_empty());
+    }
+}
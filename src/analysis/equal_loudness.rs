@@ -0,0 +1,199 @@
+// ISO 226:2003 equal-loudness contours, sound pressure level in dB SPL at which each
+// frequency is perceived as loud as a 1 kHz tone at the given phon level
+// the standard only validates levels up to 90 phon, 100 is extrapolated from its formula
+
+pub const PHON_LEVELS: [u32; 3] = [40, 70, 100];
+
+pub const PHON_40: [(f32, f32); 29] = [
+    (20.0, 99.9),
+    (25.0, 93.9),
+    (31.5, 88.2),
+    (40.0, 82.6),
+    (50.0, 77.8),
+    (63.0, 73.1),
+    (80.0, 68.5),
+    (100.0, 64.4),
+    (125.0, 60.6),
+    (160.0, 56.7),
+    (200.0, 53.4),
+    (250.0, 50.4),
+    (315.0, 47.6),
+    (400.0, 45.0),
+    (500.0, 43.1),
+    (630.0, 41.3),
+    (800.0, 40.1),
+    (1000.0, 40.0),
+    (1250.0, 41.8),
+    (1600.0, 42.5),
+    (2000.0, 39.2),
+    (2500.0, 36.5),
+    (3150.0, 35.6),
+    (4000.0, 36.6),
+    (5000.0, 40.0),
+    (6300.0, 45.8),
+    (8000.0, 51.8),
+    (10000.0, 54.3),
+    (12500.0, 51.5),
+];
+
+pub const PHON_70: [(f32, f32); 29] = [
+    (20.0, 114.3),
+    (25.0, 109.2),
+    (31.5, 104.4),
+    (40.0, 99.8),
+    (50.0, 95.9),
+    (63.0, 92.2),
+    (80.0, 88.6),
+    (100.0, 85.6),
+    (125.0, 82.9),
+    (160.0, 80.2),
+    (200.0, 77.9),
+    (250.0, 75.9),
+    (315.0, 74.2),
+    (400.0, 72.6),
+    (500.0, 71.5),
+    (630.0, 70.5),
+    (800.0, 69.8),
+    (1000.0, 70.0),
+    (1250.0, 72.3),
+    (1600.0, 73.5),
+    (2000.0, 70.3),
+    (2500.0, 67.6),
+    (3150.0, 66.8),
+    (4000.0, 68.0),
+    (5000.0, 71.3),
+    (6300.0, 76.6),
+    (8000.0, 81.5),
+    (10000.0, 82.5),
+    (12500.0, 77.0),
+];
+
+pub const PHON_100: [(f32, f32); 29] = [
+    (20.0, 128.4),
+    (25.0, 124.2),
+    (31.5, 120.1),
+    (40.0, 116.4),
+    (50.0, 113.4),
+    (63.0, 110.6),
+    (80.0, 108.2),
+    (100.0, 106.2),
+    (125.0, 104.5),
+    (160.0, 103.0),
+    (200.0, 101.8),
+    (250.0, 101.0),
+    (315.0, 100.3),
+    (400.0, 99.8),
+    (500.0, 99.6),
+    (630.0, 99.5),
+    (800.0, 99.4),
+    (1000.0, 100.0),
+    (1250.0, 102.8),
+    (1600.0, 104.3),
+    (2000.0, 101.2),
+    (2500.0, 98.5),
+    (3150.0, 97.7),
+    (4000.0, 99.0),
+    (5000.0, 102.3),
+    (6300.0, 107.2),
+    (8000.0, 111.1),
+    (10000.0, 110.2),
+    (12500.0, 102.1),
+];
+
+pub fn contour(phon: u32) -> Option<&'static [(f32, f32)]> {
+    match phon {
+        40 => Some(&PHON_40),
+        70 => Some(&PHON_70),
+        100 => Some(&PHON_100),
+        _ => None,
+    }
+}
+
+// sound pressure level of the contour for any phon level, interpolated linearly between the
+// tabulated levels (clamped to 40..=100 phon) and between tabulated frequencies on a log
+// scale, frequencies outside of 20 Hz to 12.5 kHz take the level at the nearest end
+pub fn spl_at(phon: f32, hz: f32) -> f32 {
+    let phon = phon.clamp(PHON_LEVELS[0] as f32, PHON_LEVELS[2] as f32);
+    let upper = PHON_LEVELS
+        .iter()
+        .position(|level| *level as f32 >= phon)
+        .unwrap_or(PHON_LEVELS.len() - 1)
+        .max(1);
+    let (low, high) = (PHON_LEVELS[upper - 1], PHON_LEVELS[upper]);
+    let t = (phon - low as f32) / (high - low) as f32;
+    let (low_spl, high_spl) = (
+        interpolate_contour(contour(low).unwrap(), hz),
+        interpolate_contour(contour(high).unwrap(), hz),
+    );
+    low_spl + (high_spl - low_spl) * t
+}
+
+fn interpolate_contour(contour: &[(f32, f32)], hz: f32) -> f32 {
+    let (first, last) = (contour[0], contour[contour.len() - 1]);
+    if hz <= first.0 {
+        return first.1;
+    }
+    if hz >= last.0 {
+        return last.1;
+    }
+    let upper = contour.iter().position(|(f, _)| *f >= hz).unwrap();
+    let ((f0, db0), (f1, db1)) = (contour[upper - 1], contour[upper]);
+    let t = (hz.log10() - f0.log10()) / (f1.log10() - f0.log10());
+    db0 + (db1 - db0) * t
+}
+
+#[cfg(test)]
+mod test_spl_at {
+    use super::{spl_at, PHON_70};
+
+    #[test]
+    fn tabulated() {
+        assert_eq!(PHON_70[7].1, spl_at(70.0, 100.0))
+    }
+
+    #[test]
+    fn between_levels() {
+        assert!((spl_at(55.0, 1000.0) - 55.0).abs() < 1e-4)
+    }
+
+    #[test]
+    fn between_frequencies() {
+        let spl = spl_at(40.0, 110.0);
+        assert!(spl < 64.4 && spl > 60.6)
+    }
+
+    #[test]
+    fn outside_of_the_table() {
+        assert_eq!(spl_at(40.0, 20.0), spl_at(40.0, 0.0));
+        assert_eq!(spl_at(100.0, 1000.0), spl_at(120.0, 1000.0));
+    }
+}
+
+#[cfg(test)]
+mod test_contour {
+    use super::{contour, PHON_LEVELS};
+
+    #[test]
+    fn phon_is_db_spl_at_1khz() {
+        for phon in PHON_LEVELS.iter() {
+            let (_, db) = contour(*phon)
+                .unwrap()
+                .iter()
+                .find(|(hz, _)| *hz == 1000.0)
+                .cloned()
+                .unwrap();
+            assert_eq!(*phon as f32, db)
+        }
+    }
+
+    #[test]
+    fn bass_needs_more_level() {
+        let contour = contour(40).unwrap();
+        assert!(contour[0].1 > contour[17].1 + 50.0)
+    }
+
+    #[test]
+    fn unknown_level() {
+        assert_eq!(None, contour(50))
+    }
+}
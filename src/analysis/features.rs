@@ -0,0 +1,118 @@
+// frequency of the largest magnitude bin, refined to sub-bin accuracy by fitting
+// a parabola through the peak bin and its two neighbours
+pub fn peak_frequency(magnitudes: &[f64], sample_rate: u32, n_fft: usize) -> f32 {
+    let mut peak = 0;
+    for (bin, magnitude) in magnitudes.iter().enumerate() {
+        if *magnitude > magnitudes[peak] {
+            peak = bin;
+        }
+    }
+
+    let mut offset = 0.0;
+    if peak > 0 && peak + 1 < magnitudes.len() {
+        let left = magnitudes[peak - 1];
+        let center = magnitudes[peak];
+        let right = magnitudes[peak + 1];
+        let denominator = left - 2.0 * center + right;
+        if denominator != 0.0 {
+            offset = 0.5 * (left - right) / denominator;
+        }
+    }
+
+    ((peak as f64 + offset) * sample_rate as f64 / n_fft as f64) as f32
+}
+
+// sum of the half wave rectified change in magnitude of each bin between two frames
+pub fn spectral_flux(prev: &[f64], curr: &[f64]) -> f64 {
+    prev.iter()
+        .zip(curr.iter())
+        .map(|(prev, curr)| (curr - prev).max(0.0))
+        .sum()
+}
+
+// indices of local maxima in the onset function which exceed threshold
+pub fn pick_onsets(onset_function: &[f64], threshold: f64) -> Vec<usize> {
+    let mut onsets = vec![];
+    for (index, value) in onset_function.iter().enumerate() {
+        let previous = if index > 0 {
+            onset_function[index - 1]
+        } else {
+            f64::MIN
+        };
+        let next = onset_function.get(index + 1).cloned().unwrap_or(f64::MIN);
+        if *value > threshold && *value > previous && *value >= next {
+            onsets.push(index);
+        }
+    }
+    onsets
+}
+
+#[cfg(test)]
+mod test_peak_frequency {
+    use super::peak_frequency;
+    use crate::test_signals::sine;
+    use crate::transform::{fourier_transform, Backend};
+
+    fn sine_magnitudes(freq_hz: f64, sample_rate: u32, n_fft: usize) -> Vec<f64> {
+        fourier_transform(&sine(freq_hz, sample_rate, n_fft), Backend::default())
+            .iter()
+            .map(|x| x.norm())
+            .collect()
+    }
+
+    #[test]
+    fn exact_parabola() {
+        // y = -(x - 3.25)^2 sampled at bins 0..8
+        let magnitudes: Vec<f64> = (0..8).map(|x| 100.0 - (x as f64 - 3.25).powi(2)).collect();
+        assert_eq!(3.25, peak_frequency(&magnitudes, 8, 8))
+    }
+
+    #[test]
+    fn peak_at_edge() {
+        let magnitudes = vec![5.0, 3.0, 1.0, 0.0];
+        assert_eq!(0.0, peak_frequency(&magnitudes, 8, 8))
+    }
+
+    #[test]
+    fn on_bin_sine() {
+        // 1000 Hz is exactly bin 128 when 8000 Hz is split into 1024 bins
+        let magnitudes = sine_magnitudes(1000.0, 8000, 1024);
+        assert!((peak_frequency(&magnitudes, 8000, 1024) - 1000.0).abs() < 0.01)
+    }
+
+    #[test]
+    fn between_bin_sine() {
+        // bins are 7.8125 Hz wide, 440 Hz falls at bin 56.32
+        let magnitudes = sine_magnitudes(440.0, 8000, 1024);
+        let nearest_bin_error = 440.0 - 56.0 * 8000.0 / 1024.0;
+        let error = (peak_frequency(&magnitudes, 8000, 1024) - 440.0).abs();
+        assert!(error < nearest_bin_error, "error of {} Hz", error)
+    }
+}
+
+#[cfg(test)]
+mod test_spectral_flux {
+    use super::{pick_onsets, spectral_flux};
+
+    #[test]
+    fn identical_frames() {
+        assert_eq!(0.0, spectral_flux(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0]))
+    }
+
+    #[test]
+    fn only_increases_count() {
+        assert_eq!(3.0, spectral_flux(&[1.0, 2.0, 3.0], &[2.0, 0.0, 5.0]))
+    }
+
+    #[test]
+    fn onsets_above_threshold() {
+        let onset_function = vec![0.0, 5.0, 1.0, 0.5, 2.0, 0.0, 9.0];
+        assert_eq!(vec![1, 6], pick_onsets(&onset_function, 3.0))
+    }
+
+    #[test]
+    fn plateau_counts_once() {
+        let onset_function = vec![0.0, 4.0, 4.0, 0.0];
+        assert_eq!(vec![1], pick_onsets(&onset_function, 3.0))
+    }
+}
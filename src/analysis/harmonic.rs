@@ -0,0 +1,180 @@
+use crate::analysis::features::peak_frequency;
+
+// stiff strings, eg. a piano's, have partials at n * f0 * sqrt(1 + B * n^2) rather than n * f0,
+// B is estimated from the partials in order starting at the fundamental
+//
+// (f_n / n)^2 = f0^2 + f0^2 * B * n^2 is a straight line in n^2, so a least squares fit of it
+// gives B as slope / intercept. partials which were not found (<= 0.0) are skipped and fewer
+// than two leave nothing to fit, which is reported as 0.0
+pub fn inharmonicity_coefficient(frequencies: &[f32]) -> f32 {
+    let points: Vec<(f64, f64)> = frequencies
+        .iter()
+        .enumerate()
+        .filter(|(_, frequency)| **frequency > 0.0)
+        .map(|(i, frequency)| {
+            let n = (i + 1) as f64;
+            (n * n, (*frequency as f64 / n).powi(2))
+        })
+        .collect();
+    if points.len() < 2 {
+        return 0.0;
+    }
+
+    let count = points.len() as f64;
+    let mean_x = points.iter().map(|(x, _)| x).sum::<f64>() / count;
+    let mean_y = points.iter().map(|(_, y)| y).sum::<f64>() / count;
+    let covariance: f64 = points
+        .iter()
+        .map(|(x, y)| (x - mean_x) * (y - mean_y))
+        .sum();
+    let variance: f64 = points.iter().map(|(x, _)| (x - mean_x).powi(2)).sum();
+    let slope = covariance / variance;
+    let intercept = mean_y - slope * mean_x;
+    if intercept <= 0.0 {
+        return 0.0;
+    }
+    (slope / intercept) as f32
+}
+
+// frequency of the loudest bin within half a fundamental of each k * fundamental_hz for
+// k = 1..=n_harmonics, stopping at the nyquist frequency
+//
+// spectrum holds the magnitudes of the n_fft / 2 + 1 bins from 0 Hz up to the nyquist frequency
+pub fn detect_harmonics(
+    spectrum: &[f64],
+    fundamental_hz: f32,
+    sample_rate: u32,
+    n_harmonics: usize,
+) -> Vec<f32> {
+    if fundamental_hz <= 0.0 || spectrum.len() < 2 {
+        return vec![];
+    }
+    let n_fft = 2 * (spectrum.len() - 1);
+    let bin_hz = sample_rate as f32 / n_fft as f32;
+    let last_bin = spectrum.len() - 1;
+
+    let mut harmonics = vec![];
+    for k in 1..=n_harmonics {
+        let center = k as f32 * fundamental_hz;
+        if center / bin_hz > last_bin as f32 {
+            break;
+        }
+        let start = (((center - fundamental_hz / 2.0) / bin_hz).ceil().max(0.0)) as usize;
+        let end = (((center + fundamental_hz / 2.0) / bin_hz).floor() as usize).min(last_bin);
+        if start > end {
+            // a fundamental below the bin width leaves no bins to search, reported as not found
+            harmonics.push(0.0);
+            continue;
+        }
+        let window = &spectrum[start..=end];
+        harmonics.push(peak_frequency(window, sample_rate, n_fft) + start as f32 * bin_hz);
+    }
+    harmonics
+}
+
+#[cfg(test)]
+mod test_inharmonicity_coefficient {
+    use super::inharmonicity_coefficient;
+
+    fn stiff_string(fundamental: f32, b: f32, n_partials: usize) -> Vec<f32> {
+        (1..=n_partials)
+            .map(|n| {
+                let n = n as f32;
+                n * fundamental * (1.0 + b * n * n).sqrt()
+            })
+            .collect()
+    }
+
+    #[test]
+    fn integer_ratios() {
+        let partials: Vec<f32> = (1..=8).map(|n| n as f32 * 110.0).collect();
+        assert!(inharmonicity_coefficient(&partials).abs() < 1e-6)
+    }
+
+    #[test]
+    fn known_coefficient() {
+        let b = inharmonicity_coefficient(&stiff_string(55.0, 4e-4, 12));
+        assert!((b - 4e-4).abs() < 1e-6, "{}", b)
+    }
+
+    #[test]
+    fn missing_partials() {
+        let mut partials = stiff_string(55.0, 4e-4, 12);
+        partials[2] = 0.0;
+        partials[7] = 0.0;
+        let b = inharmonicity_coefficient(&partials);
+        assert!((b - 4e-4).abs() < 1e-6, "{}", b)
+    }
+
+    #[test]
+    fn too_few_partials() {
+        assert_eq!(0.0, inharmonicity_coefficient(&[]));
+        assert_eq!(0.0, inharmonicity_coefficient(&[440.0]));
+        assert_eq!(0.0, inharmonicity_coefficient(&[440.0, 0.0]));
+    }
+}
+
+#[cfg(test)]
+mod test_detect_harmonics {
+    use super::{detect_harmonics, inharmonicity_coefficient};
+    use crate::transform::window::WindowFunction;
+    use crate::transform::{fourier_transform, Backend};
+    use std::f64::consts::TAU;
+
+    const SAMPLE_RATE: u32 = 16000;
+    const N_FFT: usize = 8192;
+
+    // one sided magnitudes of a hann windowed sum of equal amplitude sines
+    fn spectrum(partials: &[f64]) -> Vec<f64> {
+        let coefficients = WindowFunction::Hann.coefficients(N_FFT);
+        let samples: Vec<f64> = (0..N_FFT)
+            .map(|t| {
+                let sum: f64 = partials
+                    .iter()
+                    .map(|f| (TAU * f * t as f64 / SAMPLE_RATE as f64).sin())
+                    .sum();
+                1000.0 * sum * coefficients[t]
+            })
+            .collect();
+        fourier_transform(&samples, Backend::default())[..=N_FFT / 2]
+            .iter()
+            .map(|x| x.norm())
+            .collect()
+    }
+
+    #[test]
+    fn integer_ratios() {
+        let partials: Vec<f64> = (1..=6).map(|n| n as f64 * 220.0).collect();
+        let harmonics = detect_harmonics(&spectrum(&partials), 220.0, SAMPLE_RATE, 6);
+        assert_eq!(6, harmonics.len());
+        for (expected, found) in partials.iter().zip(harmonics.iter()) {
+            assert!(
+                (*expected as f32 - found).abs() < 0.5,
+                "{} {}",
+                expected,
+                found
+            )
+        }
+        assert!(inharmonicity_coefficient(&harmonics).abs() < 1e-5)
+    }
+
+    #[test]
+    fn inharmonic() {
+        let b = 1e-3;
+        let partials: Vec<f64> = (1..=8)
+            .map(|n| {
+                let n = n as f64;
+                n * 220.0 * (1.0 + b * n * n).sqrt()
+            })
+            .collect();
+        let harmonics = detect_harmonics(&spectrum(&partials), 220.0, SAMPLE_RATE, 8);
+        let found = inharmonicity_coefficient(&harmonics) as f64;
+        assert!((found - b).abs() < 1e-4, "{}", found)
+    }
+
+    #[test]
+    fn stops_at_nyquist() {
+        let harmonics = detect_harmonics(&spectrum(&[3000.0]), 3000.0, SAMPLE_RATE, 8);
+        assert_eq!(2, harmonics.len())
+    }
+}
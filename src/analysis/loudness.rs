@@ -0,0 +1,181 @@
+use std::f64::consts::PI;
+
+// integrated loudness following ITU-R BS.1770-4
+
+const BLOCK_SECONDS: f64 = 0.4;
+const STEP_SECONDS: f64 = 0.1;
+const ABSOLUTE_GATE_LUFS: f64 = -70.0;
+const RELATIVE_GATE_LU: f64 = -10.0;
+
+struct Biquad {
+    b: [f64; 3],
+    a: [f64; 2],
+    x: [f64; 2],
+    y: [f64; 2],
+}
+
+impl Biquad {
+    fn process(&mut self, x0: f64) -> f64 {
+        let y0 = self.b[0] * x0 + self.b[1] * self.x[0] + self.b[2] * self.x[1]
+            - self.a[0] * self.y[0]
+            - self.a[1] * self.y[1];
+        self.x = [x0, self.x[0]];
+        self.y = [y0, self.y[0]];
+        y0
+    }
+}
+
+// stage 1, models the acoustic effect of the head as a high shelf
+fn pre_filter(sample_rate: u32) -> Biquad {
+    let f0 = 1681.974450955533;
+    let gain_db = 3.999843853973347;
+    let q = 0.7071752369554196;
+
+    let k = (PI * f0 / sample_rate as f64).tan();
+    let vh = 10.0_f64.powf(gain_db / 20.0);
+    let vb = vh.powf(0.4996667741545416);
+    let a0 = 1.0 + k / q + k * k;
+    Biquad {
+        b: [
+            (vh + vb * k / q + k * k) / a0,
+            2.0 * (k * k - vh) / a0,
+            (vh - vb * k / q + k * k) / a0,
+        ],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        x: [0.0; 2],
+        y: [0.0; 2],
+    }
+}
+
+// stage 2, the revised low frequency B weighting high pass
+fn rlb_filter(sample_rate: u32) -> Biquad {
+    let f0 = 38.13547087602444;
+    let q = 0.5003270373238773;
+
+    let k = (PI * f0 / sample_rate as f64).tan();
+    let a0 = 1.0 + k / q + k * k;
+    Biquad {
+        b: [1.0, -2.0, 1.0],
+        a: [2.0 * (k * k - 1.0) / a0, (1.0 - k / q + k * k) / a0],
+        x: [0.0; 2],
+        y: [0.0; 2],
+    }
+}
+
+fn k_weighted(samples: &[i16], sample_rate: u32) -> Vec<f64> {
+    let mut pre = pre_filter(sample_rate);
+    let mut rlb = rlb_filter(sample_rate);
+    samples
+        .iter()
+        .map(|x| *x as f64 / 32768.0)
+        .map(|x| rlb.process(pre.process(x)))
+        .collect()
+}
+
+// assumes the standard L, R, C, LFE, Ls, Rs ordering for 5.1 audio
+fn channel_weight(channel: usize, n_channels: usize) -> f64 {
+    match (n_channels, channel) {
+        (6, 3) => 0.0,
+        (6, 4) | (6, 5) => 1.41,
+        _ => 1.0,
+    }
+}
+
+fn loudness(weighted_mean_square: f64) -> f64 {
+    -0.691 + 10.0 * weighted_mean_square.log10()
+}
+
+pub fn integrated_lufs(channels: &[Vec<i16>], sample_rate: u32) -> f64 {
+    let filtered: Vec<Vec<f64>> = channels
+        .iter()
+        .map(|channel| k_weighted(channel, sample_rate))
+        .collect();
+
+    let n_samples = channels.iter().map(|c| c.len()).min().unwrap_or(0);
+    let block_size = (BLOCK_SECONDS * sample_rate as f64).round() as usize;
+    // rounds to zero below 5 Hz, where the blocks would never move on
+    let step_size = ((STEP_SECONDS * sample_rate as f64).round() as usize).max(1);
+
+    // weighted sum of each channel's mean square energy for each gating block
+    let mut blocks = vec![];
+    let mut start = 0;
+    while block_size > 0 && start + block_size <= n_samples {
+        let mut energy = 0.0;
+        for (channel, samples) in filtered.iter().enumerate() {
+            let mean_square = samples[start..start + block_size]
+                .iter()
+                .map(|x| x * x)
+                .sum::<f64>()
+                / block_size as f64;
+            energy += channel_weight(channel, filtered.len()) * mean_square;
+        }
+        blocks.push(energy);
+        start += step_size;
+    }
+
+    let absolute_gated: Vec<f64> = blocks
+        .into_iter()
+        .filter(|energy| loudness(*energy) > ABSOLUTE_GATE_LUFS)
+        .collect();
+    if absolute_gated.is_empty() {
+        return f64::NEG_INFINITY;
+    }
+
+    let relative_gate = loudness(mean(&absolute_gated)) + RELATIVE_GATE_LU;
+    let relative_gated: Vec<f64> = absolute_gated
+        .into_iter()
+        .filter(|energy| loudness(*energy) > relative_gate)
+        .collect();
+    loudness(mean(&relative_gated))
+}
+
+fn mean(values: &[f64]) -> f64 {
+    values.iter().sum::<f64>() / values.len() as f64
+}
+
+#[cfg(test)]
+mod test_integrated_lufs {
+    use super::integrated_lufs;
+    use crate::test_signals::scaled_sine;
+
+    // a full scale peak is clipped to i16::MAX
+    fn sine(freq_hz: f64, amplitude_dbfs: f64, seconds: f64, sample_rate: u32) -> Vec<i16> {
+        let amplitude = 32768.0 * 10.0_f64.powf(amplitude_dbfs / 20.0);
+        let n = (seconds * sample_rate as f64) as usize;
+        scaled_sine(freq_hz, sample_rate, n, amplitude, 0.0)
+    }
+
+    #[test]
+    fn full_scale_sine() {
+        // BS.1770: a 0 dBFS 1 kHz sine in a single front channel reads -3.01 LKFS
+        let channels = vec![sine(1000.0, 0.0, 5.0, 48000)];
+        let lufs = integrated_lufs(&channels, 48000);
+        assert!((lufs + 3.01).abs() < 0.1, "{} LUFS", lufs)
+    }
+
+    #[test]
+    fn ebu_3341_case_1() {
+        // stereo 1 kHz sine at -23 dBFS for 20 seconds reads -23 LUFS
+        let channel = sine(1000.0, -23.0, 20.0, 48000);
+        let lufs = integrated_lufs(&[channel.clone(), channel], 48000);
+        assert!((lufs + 23.0).abs() < 0.1, "{} LUFS", lufs)
+    }
+
+    #[test]
+    fn sample_rate_independent() {
+        let channel = sine(1000.0, -23.0, 5.0, 44100);
+        let lufs = integrated_lufs(&[channel.clone(), channel], 44100);
+        assert!((lufs + 23.0).abs() < 0.1, "{} LUFS", lufs)
+    }
+
+    #[test]
+    fn tiny_sample_rate() {
+        assert_eq!(f64::NEG_INFINITY, integrated_lufs(&[vec![0; 40]], 4))
+    }
+
+    #[test]
+    fn silence_is_gated() {
+        let channels = vec![vec![0; 48000]];
+        assert_eq!(f64::NEG_INFINITY, integrated_lufs(&channels, 48000))
+    }
+}
@@ -0,0 +1,13 @@
+pub mod annotation;
+pub mod autocorrelation;
+pub mod bark;
+pub mod cepstrum;
+pub mod envelope;
+pub mod equal_loudness;
+pub mod features;
+pub mod harmonic;
+pub mod loudness;
+pub mod pitch;
+pub mod stereo;
+pub mod tempo;
+pub mod weighting;
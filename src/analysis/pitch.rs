@@ -0,0 +1,111 @@
+use crate::analysis::features::peak_frequency;
+
+const A4_FREQ: f32 = 440.0;
+const A4_MIDI: f32 = 69.0;
+// further than this from the nearest note is treated as no note at all
+pub const CENTS_TOLERANCE: f32 = 25.0;
+
+// nearest midi note number and the deviation from it in cents, None outside of 0..=127
+pub fn freq_to_midi(freq_hz: f32) -> Option<(u8, f32)> {
+    if freq_hz <= 0.0 {
+        return None;
+    }
+    let semitones = A4_MIDI + 12.0 * (freq_hz / A4_FREQ).log2();
+    let note = semitones.round();
+    if !(0.0..=127.0).contains(&note) {
+        return None;
+    }
+    Some((note as u8, (semitones - note) * 100.0))
+}
+
+// note of the loudest bin of a single frame, provided it is louder than min_amplitude
+// (in sample units) and within CENTS_TOLERANCE of a note
+pub fn detect_note(
+    magnitudes: &[f64],
+    sample_rate: u32,
+    n_fft: usize,
+    min_amplitude: f64,
+) -> Option<(u8, f32)> {
+    let max = magnitudes.iter().cloned().fold(0.0, f64::max);
+    // a sine of amplitude a has a peak magnitude of a * n / 2
+    if max * 2.0 / (n_fft as f64) < min_amplitude {
+        return None;
+    }
+    let (note, cents) = freq_to_midi(peak_frequency(magnitudes, sample_rate, n_fft))?;
+    if cents.abs() > CENTS_TOLERANCE {
+        return None;
+    }
+    Some((note, cents))
+}
+
+#[cfg(test)]
+mod test_freq_to_midi {
+    use super::freq_to_midi;
+
+    #[test]
+    fn a4() {
+        assert_eq!(Some((69, 0.0)), freq_to_midi(440.0))
+    }
+
+    #[test]
+    fn middle_c() {
+        let (note, cents) = freq_to_midi(261.63).unwrap();
+        assert_eq!(60, note);
+        assert!(cents.abs() < 0.1)
+    }
+
+    #[test]
+    fn sharp() {
+        // a quarter tone above A4
+        let (note, cents) = freq_to_midi(440.0 * 2f32.powf(0.4 / 12.0)).unwrap();
+        assert_eq!(69, note);
+        assert!((cents - 40.0).abs() < 0.01)
+    }
+
+    #[test]
+    fn out_of_range() {
+        assert_eq!(None, freq_to_midi(0.0));
+        assert_eq!(None, freq_to_midi(20000.0))
+    }
+}
+
+#[cfg(test)]
+mod test_detect_note {
+    use super::detect_note;
+    use crate::test_signals::scaled_sine;
+    use crate::transform::{fourier_transform, Backend};
+
+    fn sine_magnitudes(freq_hz: f64, amplitude: f64) -> Vec<f64> {
+        let samples = scaled_sine(freq_hz, 44100, 4096, amplitude, 0.0);
+        fourier_transform(&samples, Backend::default())
+            .iter()
+            .map(|x| x.norm())
+            .collect()
+    }
+
+    #[test]
+    fn a4() {
+        // parabolic interpolation over an unwindowed spectrum is only accurate to a few cents
+        let (note, cents) =
+            detect_note(&sine_magnitudes(440.0, 10000.0), 44100, 4096, 100.0).unwrap();
+        assert_eq!(69, note);
+        assert!(cents.abs() < 10.0)
+    }
+
+    #[test]
+    fn too_quiet() {
+        assert_eq!(
+            None,
+            detect_note(&sine_magnitudes(440.0, 50.0), 44100, 4096, 100.0)
+        )
+    }
+
+    #[test]
+    fn between_notes() {
+        let quarter_tone = 440.0 * 2f64.powf(0.5 / 12.0);
+        assert_eq!(
+            None,
+            detect_note(&sine_magnitudes(quarter_tone, 10000.0), 44100, 4096, 100.0)
+        )
+    }
+}
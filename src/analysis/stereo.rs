@@ -0,0 +1,108 @@
+use crate::io::wav::WAV;
+
+// pearson correlation of the left and right samples, 1.0 for mono, around 0.0 for a wide
+// stereo image and -1.0 when one channel is the other phase inverted
+//
+// only the overlap of the two is used, silence or any other constant channel has nothing to
+// correlate and is reported as 0.0
+pub fn stereo_correlation(left: &[i16], right: &[i16]) -> f32 {
+    let n = left.len().min(right.len());
+    if n == 0 {
+        return 0.0;
+    }
+    let (left, right) = (&left[..n], &right[..n]);
+    let mean = |samples: &[i16]| samples.iter().map(|x| *x as f64).sum::<f64>() / n as f64;
+    let (mean_left, mean_right) = (mean(left), mean(right));
+
+    let mut covariance = 0.0;
+    let mut variance_left = 0.0;
+    let mut variance_right = 0.0;
+    for (l, r) in left.iter().zip(right.iter()) {
+        let l = *l as f64 - mean_left;
+        let r = *r as f64 - mean_right;
+        covariance += l * r;
+        variance_left += l * l;
+        variance_right += r * r;
+    }
+    if variance_left == 0.0 || variance_right == 0.0 {
+        return 0.0;
+    }
+    (covariance / (variance_left * variance_right).sqrt()).clamp(-1.0, 1.0) as f32
+}
+
+// stereo_correlation of the first two channels over consecutive frames of frame_size samples,
+// the last frame may be shorter. a mono file is compared with itself
+pub fn stereo_width_over_time(wav: &WAV, frame_size: usize) -> Vec<f32> {
+    let left = match wav.channel(0) {
+        Ok(left) => left,
+        Err(_) => return vec![],
+    };
+    let right = wav.channel(1).unwrap_or(left);
+    if frame_size == 0 {
+        return vec![];
+    }
+    left.chunks(frame_size)
+        .zip(right.chunks(frame_size))
+        .map(|(left, right)| stereo_correlation(left, right))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_stereo_correlation {
+    use super::stereo_correlation;
+    use crate::test_signals::{scaled_sine, AMPLITUDE};
+    use std::f64::consts::TAU;
+
+    fn sine(frequency: f64, phase: f64) -> Vec<i16> {
+        scaled_sine(frequency, 8000, 8000, AMPLITUDE, phase)
+    }
+
+    #[test]
+    fn mono() {
+        let samples = sine(440.0, 0.0);
+        assert!((stereo_correlation(&samples, &samples) - 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn phase_inverted() {
+        let left = sine(440.0, 0.0);
+        let right: Vec<i16> = left.iter().map(|x| -x).collect();
+        assert!((stereo_correlation(&left, &right) + 1.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn decorrelated() {
+        // a quarter cycle apart over a whole number of cycles
+        let correlation = stereo_correlation(&sine(440.0, 0.0), &sine(440.0, TAU / 4.0));
+        assert!(correlation.abs() < 1e-3, "{}", correlation);
+    }
+
+    #[test]
+    fn nothing_to_correlate() {
+        assert_eq!(0.0, stereo_correlation(&[], &[]));
+        assert_eq!(0.0, stereo_correlation(&[0, 0, 0], &[1, 2, 3]));
+    }
+}
+
+#[cfg(test)]
+mod test_stereo_width_over_time {
+    use super::stereo_width_over_time;
+    use crate::io::wav::WAV;
+
+    #[test]
+    fn frames() {
+        let left = vec![1, 2, 3, 4, 1, 2, 3, 4, 1, 2];
+        let right = vec![1, 2, 3, 4, 4, 3, 2, 1, 5, 5];
+        let wav = WAV::from_samples(vec![left, right], 8000).unwrap();
+        assert_eq!(vec![1.0, -1.0, 0.0], stereo_width_over_time(&wav, 4));
+    }
+
+    #[test]
+    fn mono() {
+        let wav = WAV::from_samples(vec![vec![1, 5, 2, 8, 3, 9]], 8000).unwrap();
+        let correlations = stereo_width_over_time(&wav, 3);
+        assert_eq!(2, correlations.len());
+        assert!(correlations.iter().all(|c| (c - 1.0).abs() < 1e-6));
+        assert!(stereo_width_over_time(&wav, 0).is_empty());
+    }
+}
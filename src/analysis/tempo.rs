@@ -0,0 +1,138 @@
+// beats faster than 200 BPM or slower than 40 BPM are not considered
+const MIN_PERIOD_SECONDS: f32 = 0.3;
+const MAX_PERIOD_SECONDS: f32 = 1.5;
+// the first peak reaching this fraction of the largest one wins, so a beat is not
+// mistaken for a multiple of its own period
+const SIGNIFICANT_PEAK: f64 = 0.8;
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct TempoEstimate {
+    pub bpm: f32,
+    // autocorrelation at the chosen lag relative to lag zero, between 0.0 and 1.0
+    pub confidence: f32,
+}
+
+impl TempoEstimate {
+    const NONE: TempoEstimate = TempoEstimate {
+        bpm: 0.0,
+        confidence: 0.0,
+    };
+}
+
+fn autocorrelation(signal: &[f64], lag: usize) -> f64 {
+    signal
+        .iter()
+        .zip(signal[lag..].iter())
+        .map(|(a, b)| a * b)
+        .sum()
+}
+
+// hop_rate is the number of onset function values per second
+pub fn estimate_tempo(onset_function: &[f64], hop_rate: f32) -> TempoEstimate {
+    let mean = onset_function.iter().sum::<f64>() / onset_function.len().max(1) as f64;
+    let centered: Vec<f64> = onset_function.iter().map(|x| x - mean).collect();
+
+    let min_lag = ((MIN_PERIOD_SECONDS * hop_rate).floor() as usize).max(1);
+    let max_lag =
+        ((MAX_PERIOD_SECONDS * hop_rate).ceil() as usize).min(centered.len().saturating_sub(1));
+    let energy = autocorrelation(&centered, 0);
+    if min_lag + 2 > max_lag || energy <= 0.0 {
+        return TempoEstimate::NONE;
+    }
+
+    // one lag either side of the search range so peaks at its edges can be recognised
+    let correlations: Vec<f64> = (min_lag - 1..=max_lag + 1)
+        .map(|lag| {
+            if lag < centered.len() {
+                autocorrelation(&centered, lag)
+            } else {
+                f64::MIN
+            }
+        })
+        .collect();
+    let largest = correlations.iter().cloned().fold(f64::MIN, f64::max);
+    if largest <= 0.0 {
+        return TempoEstimate::NONE;
+    }
+
+    let peak = (1..correlations.len() - 1).find(|i| {
+        correlations[*i] >= SIGNIFICANT_PEAK * largest
+            && correlations[*i] > correlations[i - 1]
+            && correlations[*i] >= correlations[i + 1]
+    });
+    let peak = match peak {
+        Some(peak) => peak,
+        None => return TempoEstimate::NONE,
+    };
+
+    // parabolic interpolation between neighbouring lags, as in peak_frequency
+    let left = correlations[peak - 1].max(0.0);
+    let center = correlations[peak];
+    let right = correlations[peak + 1].max(0.0);
+    let denominator = left - 2.0 * center + right;
+    let offset = if denominator != 0.0 {
+        0.5 * (left - right) / denominator
+    } else {
+        0.0
+    };
+    let lag = (min_lag - 1 + peak) as f64 + offset;
+
+    TempoEstimate {
+        bpm: (60.0 * hop_rate as f64 / lag) as f32,
+        confidence: (center / energy).min(1.0) as f32,
+    }
+}
+
+#[cfg(test)]
+mod test_estimate_tempo {
+    use super::estimate_tempo;
+
+    // an impulse every `period` values, as the onset function of a metronome
+    fn pulses(period: usize, length: usize) -> Vec<f64> {
+        (0..length)
+            .map(|i| if i % period == 0 { 1.0 } else { 0.0 })
+            .collect()
+    }
+
+    #[test]
+    fn one_twenty_bpm() {
+        // 100 values per second, a pulse every half second
+        let tempo = estimate_tempo(&pulses(50, 1000), 100.0);
+        assert!((tempo.bpm - 120.0).abs() < 2.0, "{:?}", tempo);
+        assert!(tempo.confidence > 0.5, "{:?}", tempo);
+    }
+
+    #[test]
+    fn ninety_bpm_at_a_fractional_lag() {
+        // a pulse every 2/3 of a second is 28.7 values at this hop rate
+        let hop_rate = 43.066;
+        let onsets: Vec<f64> = (0..1000)
+            .map(|i| {
+                let t = i as f32 / hop_rate;
+                if (t * 1.5).fract() < 1.0 / hop_rate * 1.5 {
+                    1.0
+                } else {
+                    0.0
+                }
+            })
+            .collect();
+        let tempo = estimate_tempo(&onsets, hop_rate);
+        assert!((tempo.bpm - 90.0).abs() < 2.0, "{:?}", tempo);
+    }
+
+    #[test]
+    fn not_fooled_by_double_period() {
+        let tempo = estimate_tempo(&pulses(30, 1000), 100.0);
+        assert!((tempo.bpm - 200.0).abs() < 2.0, "{:?}", tempo);
+    }
+
+    #[test]
+    fn silence() {
+        assert_eq!(0.0, estimate_tempo(&vec![0.0; 1000], 100.0).confidence)
+    }
+
+    #[test]
+    fn too_short() {
+        assert_eq!(0.0, estimate_tempo(&pulses(50, 20), 100.0).bpm)
+    }
+}
@@ -0,0 +1,90 @@
+use crate::analysis::equal_loudness;
+
+// frequency weightings applied to magnitudes so they follow perceived rather than
+// physical loudness, all of them leave 1 kHz unchanged
+#[derive(Clone, Copy, Debug, Default, PartialEq)]
+pub enum Weighting {
+    #[default]
+    None,
+    // IEC 61672, approximates the 40 phon contour
+    AWeighting,
+    // the inverse of the ISO 226 contour at this loudness level
+    EqualLoudnessWeighting {
+        phon: f32,
+    },
+}
+
+impl Weighting {
+    // correction in dB added to a component at this frequency
+    pub fn gain_db(&self, hz: f32) -> f32 {
+        match self {
+            Weighting::None => 0.0,
+            Weighting::AWeighting => a_weighting_db(hz),
+            // relative to 1 kHz rather than phon itself, as levels outside of the table are clamped
+            Weighting::EqualLoudnessWeighting { phon } => {
+                equal_loudness::spl_at(*phon, 1000.0) - equal_loudness::spl_at(*phon, hz)
+            }
+        }
+    }
+
+    // amplitude multiplier for each of the n_bins bins of a transform spanning 0 Hz
+    // to the nyquist frequency
+    pub fn gains(&self, n_bins: usize, sample_rate: u32) -> Vec<f64> {
+        let bin_width = sample_rate as f32 / 2.0 / (n_bins.max(2) - 1) as f32;
+        (0..n_bins)
+            .map(|bin| 10f64.powf(self.gain_db(bin as f32 * bin_width) as f64 / 20.0))
+            .collect()
+    }
+}
+
+fn a_weighting_db(hz: f32) -> f32 {
+    if hz <= 0.0 {
+        return f32::NEG_INFINITY;
+    }
+    let f2 = (hz as f64).powi(2);
+    let response = 12194f64.powi(2) * f2 * f2
+        / ((f2 + 20.6f64.powi(2))
+            * ((f2 + 107.7f64.powi(2)) * (f2 + 737.9f64.powi(2))).sqrt()
+            * (f2 + 12194f64.powi(2)));
+    // the offset normalizes the response to 0 dB at 1 kHz
+    (20.0 * response.log10() + 2.0) as f32
+}
+
+#[cfg(test)]
+mod test_weighting {
+    use super::Weighting;
+
+    #[test]
+    fn unweighted_at_1khz() {
+        for phon in [20.0, 40.0, 55.0, 70.0, 85.0, 100.0].iter() {
+            let weighting = Weighting::EqualLoudnessWeighting { phon: *phon };
+            assert!(weighting.gain_db(1000.0).abs() < 1e-4, "{} phon", phon)
+        }
+        assert!(Weighting::AWeighting.gain_db(1000.0).abs() < 0.01);
+        assert_eq!(0.0, Weighting::None.gain_db(1000.0));
+    }
+
+    #[test]
+    fn a_weighting() {
+        // tabulated values from IEC 61672-1
+        assert!((Weighting::AWeighting.gain_db(100.0) + 19.1).abs() < 0.1);
+        assert!((Weighting::AWeighting.gain_db(10000.0) + 2.5).abs() < 0.1);
+    }
+
+    #[test]
+    fn equal_loudness_cuts_bass() {
+        let weighting = Weighting::EqualLoudnessWeighting { phon: 40.0 };
+        assert!((weighting.gain_db(100.0) + 24.4).abs() < 1e-4);
+        // the ear is most sensitive around 3 to 4 kHz
+        assert!(weighting.gain_db(3150.0) > 0.0);
+    }
+
+    #[test]
+    fn gains() {
+        let gains = Weighting::AWeighting.gains(5, 4000);
+        assert_eq!(5, gains.len());
+        assert_eq!(0.0, gains[0]);
+        assert!((gains[2] - 1.0).abs() < 1e-3);
+        assert_eq!(vec![1.0; 3], Weighting::None.gains(3, 4000));
+    }
+}
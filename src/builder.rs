@@ -0,0 +1,362 @@
+use crate::error::SpectrogramError;
+use crate::io::png;
+use crate::io::wav::WAV;
+use crate::transform::stft::{mean_magnitudes, ColumnLayout, HopSize, WindowSize};
+use crate::transform::window::WindowFunction;
+use crate::transform::{batch_fourier_transform, Backend};
+use crate::units::{Scale, ScaleRange, Unit};
+use crate::widgets::colormap::Colormap;
+
+// calculates a spectrogram without any of the gui, eg.
+// SpectrogramBuilder::new().wav(wav).width(100).window(WindowFunction::Hann).build()
+pub struct SpectrogramBuilder {
+    wav: Option<WAV>,
+    channel: usize,
+    // number of time columns before overlap is taken into account
+    width: u32,
+    // fft size, shorter columns are zero padded and longer ones averaged over several windows
+    window_size: usize,
+    window: WindowFunction,
+    // 0.0 = no overlap, 0.75 = 75% overlap between consecutive fft windows
+    overlap: f32,
+    colormap: Colormap,
+    backend: Backend,
+}
+
+impl Default for SpectrogramBuilder {
+    fn default() -> Self {
+        SpectrogramBuilder {
+            wav: None,
+            channel: 0,
+            width: 100,
+            window_size: 1024,
+            window: WindowFunction::Hann,
+            overlap: 0.0,
+            colormap: Colormap::default(),
+            backend: Backend::default(),
+        }
+    }
+}
+
+impl SpectrogramBuilder {
+    pub fn new() -> Self {
+        SpectrogramBuilder::default()
+    }
+
+    pub fn wav(mut self, wav: WAV) -> Self {
+        self.wav = Some(wav);
+        self
+    }
+
+    pub fn channel(mut self, channel: usize) -> Self {
+        self.channel = channel;
+        self
+    }
+
+    pub fn width(mut self, width: u32) -> Self {
+        self.width = width;
+        self
+    }
+
+    pub fn window_size(mut self, window_size: usize) -> Self {
+        self.window_size = window_size;
+        self
+    }
+
+    pub fn window(mut self, window: WindowFunction) -> Self {
+        self.window = window;
+        self
+    }
+
+    pub fn overlap(mut self, overlap: f32) -> Self {
+        self.overlap = overlap;
+        self
+    }
+
+    pub fn colormap(mut self, colormap: Colormap) -> Self {
+        self.colormap = colormap;
+        self
+    }
+
+    pub fn backend(mut self, backend: Backend) -> Self {
+        self.backend = backend;
+        self
+    }
+
+    pub fn build(self) -> Result<SpectrogramData, SpectrogramError> {
+        let wav = self.wav.ok_or(SpectrogramError::MissingInput("wav"))?;
+        let samples = wav.channel(self.channel)?;
+        let width = self.width as usize;
+        // every column needs at least two samples
+        if width == 0 || samples.len() < 2 * width {
+            return Err(SpectrogramError::ShapeMismatch {
+                expected: 2 * width.max(1),
+                found: samples.len(),
+            });
+        }
+        if !(0.0..1.0).contains(&self.overlap) {
+            return Err(SpectrogramError::InvalidOverlap(self.overlap));
+        }
+        if self.window_size < 2 {
+            return Err(SpectrogramError::ShapeMismatch {
+                expected: 2,
+                found: self.window_size,
+            });
+        }
+
+        // the same columns as Grid at this width, so the matrix can be loaded with
+        // Grid::from_matrix. Grid's span is twice its height, so always an even number of samples
+        let span = 2 * (samples.len() / width / 2);
+        let layout = ColumnLayout::new(
+            width,
+            span,
+            HopSize::from_overlap(WindowSize(span), self.overlap),
+            WindowSize(self.window_size),
+            self.window,
+        );
+        let n_columns = layout.starts.len();
+        let n_segments = layout.segments.len();
+        let frames: Vec<Vec<f64>> = (0..n_columns)
+            .flat_map(|column| (0..n_segments).map(move |segment| (column, segment)))
+            .map(|(column, segment)| layout.frame(samples, column, segment))
+            .collect();
+        let matrix: Vec<Vec<f64>> = batch_fourier_transform(&frames, self.backend)
+            .chunks(n_segments)
+            .map(|windows| normalized(mean_magnitudes(windows)))
+            .collect();
+
+        let sample_rate = wav.fmt_header.sample_rate;
+        let duration = samples.len() as f32 / sample_rate as f32;
+        let x = Scale::default()
+            .with_unit(Unit::Second)
+            .with_range(ScaleRange::new(0.0, duration)?);
+        let y = Scale::default().with_range(ScaleRange::new(0.0, sample_rate as f32 / 2.0)?);
+        Ok(SpectrogramData {
+            resolution: (n_columns, self.window_size / 2 + 1),
+            matrix,
+            sample_rate,
+            x,
+            y,
+            colormap: self.colormap,
+        })
+    }
+}
+
+// scaled so the loudest bin of each column is 1.0, silent columns stay at 0.0
+fn normalized(magnitudes: Vec<f64>) -> Vec<f64> {
+    let max = magnitudes.iter().cloned().fold(0.0, f64::max);
+    if max == 0.0 {
+        return magnitudes;
+    }
+    magnitudes.iter().map(|x| x / max).collect()
+}
+
+pub struct SpectrogramData {
+    // matrix[column][bin] is an intensity between 0.0 and 1.0
+    matrix: Vec<Vec<f64>>,
+    // (number of columns, number of frequency bins)
+    pub resolution: (usize, usize),
+    pub sample_rate: u32,
+    // time in seconds and frequency in Hz covered by the matrix
+    pub x: Scale,
+    pub y: Scale,
+    pub colormap: Colormap,
+}
+
+impl SpectrogramData {
+    // mono samples straight to a spectrogram with the default settings, eg. from a synthesizer,
+    // without writing them out as a WAV file first
+    pub fn from_samples(
+        samples: Vec<i16>,
+        sample_rate: u32,
+        width: u32,
+    ) -> Result<SpectrogramData, SpectrogramError> {
+        SpectrogramBuilder::new()
+            .wav(WAV::from_samples(vec![samples], sample_rate)?)
+            .width(width)
+            .build()
+    }
+
+    pub fn to_matrix(&self) -> Vec<Vec<f64>> {
+        self.matrix.clone()
+    }
+
+    // a pixel per cell, time to the right and the highest frequency at the top
+    pub fn to_png_bytes(&self) -> Vec<u8> {
+        let (n_columns, n_bins) = self.resolution;
+        let mut pixels = Vec::with_capacity(n_columns * n_bins * 3);
+        for bin in (0..n_bins).rev() {
+            for column in &self.matrix {
+                let color = self.colormap.color(column[bin] as f32);
+                pixels.extend_from_slice(&[
+                    (color.r * 255.0).round() as u8,
+                    (color.g * 255.0).round() as u8,
+                    (color.b * 255.0).round() as u8,
+                ]);
+            }
+        }
+        png::encode_rgb(&pixels, n_columns as u32, n_bins as u32)
+            .expect("one pixel per cell of the matrix")
+    }
+}
+
+#[cfg(test)]
+mod test_spectrogram_builder {
+    use super::{SpectrogramBuilder, SpectrogramData};
+    use crate::error::SpectrogramError;
+    use crate::io::wav::WAV;
+    use crate::test_signals::sine;
+    use crate::transform::window::WindowFunction;
+    use crate::transform::Backend;
+    use crate::units::Scale;
+    use crate::widgets::colormap::Colormap;
+    use crate::widgets::grid::Grid;
+
+    fn sine_wav(frequency: f64, sample_rate: u32, n: usize) -> WAV {
+        WAV::from_samples(vec![sine(frequency, sample_rate, n)], sample_rate).unwrap()
+    }
+
+    #[test]
+    fn resolution() {
+        let data = SpectrogramBuilder::new()
+            .wav(sine_wav(440.0, 8000, 8000))
+            .width(10)
+            .window_size(256)
+            .overlap(0.5)
+            .build()
+            .unwrap();
+        // half overlapping columns fit one between each pair of the ten non overlapping ones
+        assert_eq!((19, 129), data.resolution);
+        assert_eq!(19, data.to_matrix().len());
+        assert_eq!((0.0, 1.0), (data.x.range.min(), data.x.range.max()));
+        assert_eq!(4000.0, data.y.range.max());
+    }
+
+    #[test]
+    fn peak_bin() {
+        let data = SpectrogramBuilder::new()
+            .wav(sine_wav(1000.0, 8000, 8000))
+            .width(4)
+            .window_size(256)
+            .window(WindowFunction::Hann)
+            .build()
+            .unwrap();
+        // 8000 / 256 Hz per bin
+        for column in data.to_matrix() {
+            assert_eq!(1.0, column[32])
+        }
+    }
+
+    #[test]
+    fn longer_than_window() {
+        // each 4000 sample column is silent for its first 256 sample window
+        let mut wav = sine_wav(1000.0, 8000, 8000);
+        for start in [0, 4000].iter() {
+            for sample in wav.channels[0][*start..start + 1000].iter_mut() {
+                *sample = 0;
+            }
+        }
+        let data = SpectrogramBuilder::new()
+            .wav(wav)
+            .width(2)
+            .window_size(256)
+            .build()
+            .unwrap();
+        for column in data.to_matrix() {
+            assert_eq!(1.0, column[32])
+        }
+    }
+
+    #[test]
+    fn same_as_grid() {
+        // 803 samples per column, of which Grid and the builder use the first 802
+        let wav = sine_wav(440.0, 8000, 8030);
+        let mut grid = Grid::new(
+            10,
+            8000,
+            1,
+            Scale::default(),
+            Scale::default(),
+            Backend::default(),
+        );
+        grid.window_size = 256;
+        grid.overlap = 0.5;
+        grid.set_resolution((10, 401), &wav.channels[0]);
+        let data = SpectrogramBuilder::new()
+            .wav(wav)
+            .width(10)
+            .window_size(256)
+            .overlap(0.5)
+            .build()
+            .unwrap();
+        let expected = grid.to_matrix();
+        assert_eq!(expected.len(), data.resolution.0);
+        for (e, a) in expected
+            .iter()
+            .flatten()
+            .zip(data.to_matrix().iter().flatten())
+        {
+            assert!((e - a).abs() < 1e-9, "{} != {}", e, a)
+        }
+    }
+
+    #[test]
+    fn channel_out_of_range() {
+        let result = SpectrogramBuilder::new()
+            .wav(sine_wav(440.0, 8000, 8000))
+            .channel(1)
+            .build();
+        assert!(matches!(
+            result,
+            Err(SpectrogramError::ChannelOutOfRange {
+                requested: 1,
+                available: 1
+            })
+        ));
+    }
+
+    #[test]
+    fn png() {
+        let png = SpectrogramBuilder::new()
+            .wav(sine_wav(440.0, 8000, 8000))
+            .width(8)
+            .window_size(64)
+            .colormap(Colormap::Viridis)
+            .build()
+            .unwrap()
+            .to_png_bytes();
+        assert_eq!(b"\x89PNG", &png[..4]);
+        // width and height in the header
+        assert_eq!(&[0, 0, 0, 8, 0, 0, 0, 33], &png[16..24]);
+    }
+
+    #[test]
+    fn from_samples() {
+        let samples = sine_wav(440.0, 8000, 8000).channels.remove(0);
+        let data = SpectrogramData::from_samples(samples, 8000, 10).unwrap();
+        assert_eq!((10, 513), data.resolution);
+        assert!(SpectrogramData::from_samples(vec![], 8000, 10).is_err());
+    }
+
+    #[test]
+    fn missing_wav() {
+        assert!(SpectrogramBuilder::new().build().is_err())
+    }
+
+    #[test]
+    fn invalid_overlap() {
+        for overlap in [1.0, 1.5, -0.25, f32::NAN].iter() {
+            let result = SpectrogramBuilder::new()
+                .wav(sine_wav(440.0, 8000, 8000))
+                .overlap(*overlap)
+                .build();
+            assert!(matches!(result, Err(SpectrogramError::InvalidOverlap(_))));
+        }
+    }
+
+    #[test]
+    fn too_few_samples() {
+        let wav = sine_wav(440.0, 8000, 10);
+        assert!(SpectrogramBuilder::new().wav(wav).width(6).build().is_err())
+    }
+}
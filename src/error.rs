@@ -0,0 +1,119 @@
+use std::error::Error;
+use std::fmt;
+use std::io;
+
+use crate::units::RangeError;
+
+#[derive(Debug)]
+pub enum SpectrogramError {
+    InvalidSampleCount {
+        data_size: u32,
+        nchannels: u16,
+    },
+    InvalidRange(RangeError),
+    Io(io::Error),
+    InvalidHeader(String),
+    MissingChunk(&'static str),
+    SampleCountMismatch {
+        expected: u64,
+        found: u64,
+    },
+    Microphone(String),
+    ShapeMismatch {
+        expected: usize,
+        found: usize,
+    },
+    ReferenceMismatch {
+        property: &'static str,
+        expected: u64,
+        found: u64,
+    },
+    // a required SpectrogramBuilder setting which was never given
+    MissingInput(&'static str),
+    ChannelOutOfRange {
+        requested: usize,
+        available: usize,
+    },
+    Json(serde_json::Error),
+    // fraction of each window shared with the next, which must be in 0.0..1.0
+    InvalidOverlap(f32),
+    // samples wider than an i32 or no bits wide
+    UnsupportedBitDepth(u16),
+}
+
+impl fmt::Display for SpectrogramError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            SpectrogramError::InvalidSampleCount {
+                data_size,
+                nchannels,
+            } => write!(
+                f,
+                "unable to calculate samples per channel from a data size of {} bytes and {} channels",
+                data_size, nchannels
+            ),
+            SpectrogramError::InvalidRange(e) => write!(f, "{}", e),
+            SpectrogramError::Io(e) => write!(f, "{}", e),
+            SpectrogramError::InvalidHeader(msg) => write!(f, "{}", msg),
+            SpectrogramError::MissingChunk(id) => write!(f, "no \"{}\" chunk found", id),
+            SpectrogramError::SampleCountMismatch { expected, found } => write!(
+                f,
+                "error reading samples. expected {}, found {}",
+                expected, found
+            ),
+            SpectrogramError::Microphone(msg) => write!(f, "microphone error: {}", msg),
+            SpectrogramError::ShapeMismatch { expected, found } => write!(
+                f,
+                "expected {} values to fill the shape, found {}",
+                expected, found
+            ),
+            SpectrogramError::ReferenceMismatch {
+                property,
+                expected,
+                found,
+            } => write!(
+                f,
+                "reference {} of {} does not match {}",
+                property, found, expected
+            ),
+            SpectrogramError::MissingInput(name) => write!(f, "no {} was given", name),
+            SpectrogramError::ChannelOutOfRange {
+                requested,
+                available,
+            } => write!(
+                f,
+                "channel {} requested, but there are only {} channels",
+                requested, available
+            ),
+            SpectrogramError::Json(e) => write!(f, "{}", e),
+            SpectrogramError::InvalidOverlap(overlap) => write!(
+                f,
+                "overlap of {} is outside of 0.0..1.0, windows can not share every sample",
+                overlap
+            ),
+            SpectrogramError::UnsupportedBitDepth(bits) => {
+                write!(f, "{} bit samples can not be converted to 16 bits", bits)
+            }
+        }
+    }
+}
+
+impl Error for SpectrogramError {}
+
+impl From<RangeError> for SpectrogramError {
+    fn from(e: RangeError) -> Self {
+        SpectrogramError::InvalidRange(e)
+    }
+}
+
+impl From<io::Error> for SpectrogramError {
+    fn from(e: io::Error) -> Self {
+        SpectrogramError::Io(e)
+    }
+}
+
+impl From<serde_json::Error> for SpectrogramError {
+    fn from(e: serde_json::Error) -> Self {
+        SpectrogramError::Json(e)
+    }
+}
@@ -0,0 +1,87 @@
+// text fields from a RIFF LIST chunk of type INFO, the usual home of WAV tags
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct WavMetadata {
+    pub title: Option<String>,
+    pub artist: Option<String>,
+    pub comment: Option<String>,
+}
+
+impl WavMetadata {
+    // body is the contents of the LIST chunk, after its id and size
+    // lists of any other type, eg. adtl, carry no tags and leave everything as None
+    pub fn from_list_chunk(body: &[u8]) -> WavMetadata {
+        let mut metadata = WavMetadata::default();
+        if body.len() < 4 || &body[0..4] != b"INFO" {
+            return metadata;
+        }
+        let mut offset = 4;
+        while offset + 8 <= body.len() {
+            let id = &body[offset..offset + 4];
+            let size = u32::from_le_bytes([
+                body[offset + 4],
+                body[offset + 5],
+                body[offset + 6],
+                body[offset + 7],
+            ]) as usize;
+            let start = offset + 8;
+            let end = start.saturating_add(size).min(body.len());
+            let value = info_string(&body[start..end]);
+            match id {
+                b"INAM" => metadata.title = value,
+                b"IART" => metadata.artist = value,
+                b"ICMT" => metadata.comment = value,
+                _ => (),
+            }
+            // sub-chunks are padded to an even number of bytes, like top level chunks
+            offset = start.saturating_add(size + size % 2);
+        }
+        metadata
+    }
+}
+
+// null terminated, and occasionally null padded, text
+fn info_string(bytes: &[u8]) -> Option<String> {
+    let text = String::from_utf8_lossy(bytes);
+    let text = text.trim_end_matches('\0');
+    if text.is_empty() {
+        None
+    } else {
+        Some(text.to_string())
+    }
+}
+
+#[cfg(test)]
+mod test_from_list_chunk {
+    use super::WavMetadata;
+
+    #[test]
+    fn info() {
+        let mut body = b"INFO".to_vec();
+        body.extend_from_slice(b"INAM\x06\x00\x00\x00Title\x00");
+        // odd sized, followed by a pad byte
+        body.extend_from_slice(b"IART\x03\x00\x00\x00Me\x00\x00");
+        body.extend_from_slice(b"ISFT\x04\x00\x00\x00abc\x00");
+        assert_eq!(
+            WavMetadata {
+                title: Some("Title".to_string()),
+                artist: Some("Me".to_string()),
+                comment: None,
+            },
+            WavMetadata::from_list_chunk(&body)
+        )
+    }
+
+    #[test]
+    fn other_list_type() {
+        assert_eq!(
+            WavMetadata::default(),
+            WavMetadata::from_list_chunk(b"adtlINAM\x02\x00\x00\x00a\x00")
+        )
+    }
+
+    #[test]
+    fn truncated() {
+        let metadata = WavMetadata::from_list_chunk(b"INFOICMT\x10\x00\x00\x00short");
+        assert_eq!(Some("short".to_string()), metadata.comment)
+    }
+}
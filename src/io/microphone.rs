@@ -0,0 +1,128 @@
+#[cfg(feature = "microphone")]
+use std::fmt::Display;
+#[cfg(feature = "microphone")]
+use std::sync::{Arc, Mutex};
+
+#[cfg(feature = "microphone")]
+use cpal::traits::{DeviceTrait, HostTrait, StreamTrait};
+#[cfg(feature = "microphone")]
+use cpal::{Device, Sample, SampleFormat, Stream, StreamConfig};
+
+#[cfg(feature = "microphone")]
+use crate::error::SpectrogramError;
+#[cfg(feature = "microphone")]
+use crate::io::ring_buffer::RingBuffer;
+
+// index of the first rising edge through threshold, oscilloscope style
+// the signal must first fall below threshold - hysteresis so noise around the
+// threshold does not trigger repeatedly
+pub fn find_trigger(samples: &[i16], threshold: i16, hysteresis: i16) -> Option<usize> {
+    let armed_below = threshold.saturating_sub(hysteresis);
+    let mut armed = false;
+    for (index, sample) in samples.iter().enumerate() {
+        if *sample < armed_below {
+            armed = true;
+        } else if armed && *sample >= threshold {
+            return Some(index);
+        }
+    }
+    None
+}
+
+#[cfg(feature = "microphone")]
+// samples from the first channel of the default input device, filled from cpal's
+// audio thread and read from iced's update
+pub struct MicrophoneSource {
+    pub sample_rate: u32,
+    buffer: Arc<Mutex<RingBuffer<i16>>>,
+    // samples stop arriving once the stream is dropped
+    _stream: Stream,
+}
+
+#[cfg(feature = "microphone")]
+impl MicrophoneSource {
+    // keeps the most recent `buffer_seconds` of audio
+    pub fn new(buffer_seconds: u32) -> Result<MicrophoneSource, SpectrogramError> {
+        let device = cpal::default_host()
+            .default_input_device()
+            .ok_or_else(|| SpectrogramError::Microphone("no input device found".to_string()))?;
+        let supported_config = device.default_input_config().map_err(microphone_error)?;
+        let sample_format = supported_config.sample_format();
+        let config: StreamConfig = supported_config.into();
+
+        let sample_rate = config.sample_rate.0;
+        let capacity = (sample_rate * buffer_seconds) as usize;
+        let buffer = Arc::new(Mutex::new(RingBuffer::new(capacity, 0)));
+        let stream = match sample_format {
+            SampleFormat::I16 => build_stream::<i16>(&device, &config, buffer.clone()),
+            SampleFormat::U16 => build_stream::<u16>(&device, &config, buffer.clone()),
+            SampleFormat::F32 => build_stream::<f32>(&device, &config, buffer.clone()),
+        }?;
+        stream.play().map_err(microphone_error)?;
+
+        Ok(MicrophoneSource {
+            sample_rate,
+            buffer,
+            _stream: stream,
+        })
+    }
+
+    // fewer than n samples are returned until the buffer has filled
+    pub fn latest_samples(&self, n: usize) -> Vec<i16> {
+        self.buffer
+            .lock()
+            .expect("microphone buffer to not be poisoned")
+            .latest_n(n)
+    }
+}
+
+#[cfg(feature = "microphone")]
+fn microphone_error<E: Display>(e: E) -> SpectrogramError {
+    SpectrogramError::Microphone(e.to_string())
+}
+
+#[cfg(feature = "microphone")]
+fn build_stream<T: Sample>(
+    device: &Device,
+    config: &StreamConfig,
+    buffer: Arc<Mutex<RingBuffer<i16>>>,
+) -> Result<Stream, SpectrogramError> {
+    let nchannels = config.channels as usize;
+    device
+        .build_input_stream(
+            config,
+            move |data: &[T], _: &cpal::InputCallbackInfo| {
+                let mut buffer = buffer.lock().expect("microphone buffer to not be poisoned");
+                for frame in data.chunks(nchannels) {
+                    buffer.push(frame[0].to_i16());
+                }
+            },
+            |e| log::error!("microphone stream error: {}", e),
+        )
+        .map_err(microphone_error)
+}
+
+#[cfg(test)]
+mod test_find_trigger {
+    use super::find_trigger;
+
+    #[test]
+    fn rising_zero_crossing() {
+        assert_eq!(Some(3), find_trigger(&[10, -5, -20, 4, 30], 0, 10))
+    }
+
+    #[test]
+    fn ignores_falling_edges() {
+        assert_eq!(Some(4), find_trigger(&[30, 10, -5, -20, 5], 0, 10))
+    }
+
+    #[test]
+    fn hysteresis_ignores_noise() {
+        assert_eq!(None, find_trigger(&[1, -1, 1, -1, 1], 0, 10))
+    }
+
+    #[test]
+    fn threshold() {
+        assert_eq!(Some(4), find_trigger(&[-100, 50, 80, -100, 150], 100, 50))
+    }
+}
@@ -1 +1,8 @@
+pub mod metadata;
+pub mod microphone;
+pub mod npy;
+pub mod png;
+pub mod raw;
+pub mod ring_buffer;
+pub mod validation;
 pub mod wav;
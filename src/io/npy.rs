@@ -0,0 +1,88 @@
+use std::fs::File;
+use std::io::{BufWriter, Write};
+use std::path::Path;
+
+use byteorder::{LittleEndian, WriteBytesExt};
+
+use crate::error::SpectrogramError;
+
+const MAGIC: &[u8] = b"\x93NUMPY";
+// the magic string, version and header length, which precede the header itself
+const PREAMBLE_LENGTH: usize = MAGIC.len() + 2 + 2;
+const ALIGNMENT: usize = 64;
+
+// header dict describing a C ordered float64 array, padded with spaces and a
+// trailing newline so the data starts on a 64 byte boundary
+fn header(shape: (usize, usize)) -> String {
+    let dict = format!(
+        "{{'descr': '<f8', 'fortran_order': False, 'shape': ({}, {}), }}",
+        shape.0, shape.1
+    );
+    let unpadded = PREAMBLE_LENGTH + dict.len() + 1;
+    let padding = (ALIGNMENT - unpadded % ALIGNMENT) % ALIGNMENT;
+    format!("{}{}\n", dict, " ".repeat(padding))
+}
+
+// writes a version 1.0 .npy file, shape is (n_columns, n_rows)
+pub fn to_npy(
+    frequencies: &[f64],
+    shape: (usize, usize),
+    path: &Path,
+) -> Result<(), SpectrogramError> {
+    let expected = shape.0 * shape.1;
+    if frequencies.len() != expected {
+        return Err(SpectrogramError::ShapeMismatch {
+            expected,
+            found: frequencies.len(),
+        });
+    }
+
+    let header = header(shape);
+    let mut writer = BufWriter::new(File::create(path)?);
+    writer.write_all(MAGIC)?;
+    writer.write_all(&[1, 0])?;
+    writer.write_u16::<LittleEndian>(header.len() as u16)?;
+    writer.write_all(header.as_bytes())?;
+    for value in frequencies {
+        writer.write_f64::<LittleEndian>(*value)?;
+    }
+    writer.flush()?;
+    Ok(())
+}
+
+#[cfg(test)]
+mod test_to_npy {
+    use super::{header, to_npy, PREAMBLE_LENGTH};
+    use std::fs::{read, remove_file};
+    use std::path::Path;
+
+    #[test]
+    fn header_is_aligned() {
+        for shape in &[(1, 1), (100, 513), (123456, 7)] {
+            let header = header(*shape);
+            assert_eq!(0, (PREAMBLE_LENGTH + header.len()) % 64);
+            assert!(header.ends_with('\n'));
+        }
+    }
+
+    #[test]
+    fn writes_file() {
+        let path = Path::new("src/tmp.npy");
+        to_npy(&[0.0, 0.5, 1.0, 0.25, 0.75, 1.0], (2, 3), path).unwrap();
+        let bytes = read(path).unwrap();
+        remove_file(path).unwrap();
+
+        assert_eq!(b"\x93NUMPY\x01\x00", &bytes[..8]);
+        let header_length = u16::from_le_bytes([bytes[8], bytes[9]]) as usize;
+        let header = std::str::from_utf8(&bytes[10..10 + header_length]).unwrap();
+        assert!(header.starts_with("{'descr': '<f8', 'fortran_order': False, 'shape': (2, 3), }"));
+        let data = &bytes[10 + header_length..];
+        assert_eq!(6 * 8, data.len());
+        assert_eq!(0.5f64.to_le_bytes(), data[8..16]);
+    }
+
+    #[test]
+    fn shape_mismatch() {
+        assert!(to_npy(&[0.0; 5], (2, 3), Path::new("src/tmp_mismatch.npy")).is_err())
+    }
+}
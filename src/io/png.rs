@@ -0,0 +1,130 @@
+use crate::error::SpectrogramError;
+
+const SIGNATURE: [u8; 8] = [0x89, b'P', b'N', b'G', 0x0d, 0x0a, 0x1a, 0x0a];
+// largest amount of data a single uncompressed deflate block can hold
+const MAX_STORED_BLOCK: usize = 65535;
+// 8 bit samples, truecolor without alpha
+const BIT_DEPTH: u8 = 8;
+const COLOR_TYPE_RGB: u8 = 2;
+
+fn crc32(bytes: &[u8]) -> u32 {
+    let mut crc = 0xffff_ffff;
+    for byte in bytes {
+        crc ^= *byte as u32;
+        for _ in 0..8 {
+            crc = if crc & 1 == 1 {
+                (crc >> 1) ^ 0xedb8_8320
+            } else {
+                crc >> 1
+            };
+        }
+    }
+    !crc
+}
+
+fn adler32(bytes: &[u8]) -> u32 {
+    let mut a: u32 = 1;
+    let mut b: u32 = 0;
+    for byte in bytes {
+        a = (a + *byte as u32) % 65521;
+        b = (b + a) % 65521;
+    }
+    (b << 16) | a
+}
+
+// zlib stream of uncompressed deflate blocks, larger than compressing but needs no encoder
+fn zlib_stored(data: &[u8]) -> Vec<u8> {
+    // deflate with a 32K window, no preset dictionary, header check bits making it divisible by 31
+    let mut stream = vec![0x78, 0x01];
+    let mut blocks = data.chunks(MAX_STORED_BLOCK).peekable();
+    if blocks.peek().is_none() {
+        stream.extend_from_slice(&[1, 0, 0, 0xff, 0xff]);
+    }
+    while let Some(block) = blocks.next() {
+        let is_final = blocks.peek().is_none();
+        let length = block.len() as u16;
+        stream.push(is_final as u8);
+        stream.extend_from_slice(&length.to_le_bytes());
+        stream.extend_from_slice(&(!length).to_le_bytes());
+        stream.extend_from_slice(block);
+    }
+    stream.extend_from_slice(&adler32(data).to_be_bytes());
+    stream
+}
+
+fn write_chunk(png: &mut Vec<u8>, kind: &[u8; 4], data: &[u8]) {
+    png.extend_from_slice(&(data.len() as u32).to_be_bytes());
+    let start = png.len();
+    png.extend_from_slice(kind);
+    png.extend_from_slice(data);
+    let crc = crc32(&png[start..]);
+    png.extend_from_slice(&crc.to_be_bytes());
+}
+
+// pixels are rgb triples, row by row from the top left
+pub fn encode_rgb(pixels: &[u8], width: u32, height: u32) -> Result<Vec<u8>, SpectrogramError> {
+    let row_length = width as usize * 3;
+    let expected = row_length * height as usize;
+    if pixels.len() != expected {
+        return Err(SpectrogramError::ShapeMismatch {
+            expected,
+            found: pixels.len(),
+        });
+    }
+
+    let mut header = Vec::with_capacity(13);
+    header.extend_from_slice(&width.to_be_bytes());
+    header.extend_from_slice(&height.to_be_bytes());
+    // no compression method, filter method or interlacing other than the defaults
+    header.extend_from_slice(&[BIT_DEPTH, COLOR_TYPE_RGB, 0, 0, 0]);
+
+    // every row starts with its filter type, 0 for none
+    let mut filtered = Vec::with_capacity(expected + height as usize);
+    for row in pixels.chunks(row_length.max(1)).take(height as usize) {
+        filtered.push(0);
+        filtered.extend_from_slice(row);
+    }
+
+    let mut png = SIGNATURE.to_vec();
+    write_chunk(&mut png, b"IHDR", &header);
+    write_chunk(&mut png, b"IDAT", &zlib_stored(&filtered));
+    write_chunk(&mut png, b"IEND", &[]);
+    Ok(png)
+}
+
+#[cfg(test)]
+mod test_encode_rgb {
+    use super::{adler32, crc32, encode_rgb, zlib_stored, SIGNATURE};
+
+    #[test]
+    fn checksums() {
+        assert_eq!(0xcbf4_3926, crc32(b"123456789"));
+        assert_eq!(0x11e6_0398, adler32(b"Wikipedia"));
+    }
+
+    #[test]
+    fn stored_blocks() {
+        // two blocks, each with a 5 byte header, between the zlib header and checksum
+        let stream = zlib_stored(&[7; 70000]);
+        assert_eq!(2 + 5 + 65535 + 5 + 4465 + 4, stream.len());
+        assert_eq!(&[0, 0xff, 0xff, 0, 0], &stream[2..7]);
+        assert_eq!(1, stream[2 + 5 + 65535]);
+    }
+
+    #[test]
+    fn one_pixel() {
+        let png = encode_rgb(&[255, 0, 0], 1, 1).unwrap();
+        assert_eq!(&SIGNATURE, &png[..8]);
+        assert_eq!(b"IHDR", &png[12..16]);
+        assert_eq!(&[0, 0, 0, 1, 0, 0, 0, 1, 8, 2], &png[16..26]);
+        assert_eq!(
+            &[0, 0, 0, 0, b'I', b'E', b'N', b'D', 0xae, 0x42, 0x60, 0x82],
+            &png[png.len() - 12..]
+        );
+    }
+
+    #[test]
+    fn wrong_pixel_count() {
+        assert!(encode_rgb(&[0; 5], 1, 2).is_err())
+    }
+}
@@ -0,0 +1,129 @@
+use crate::error::SpectrogramError;
+use crate::io::wav::{round_to_16bit, WAV};
+
+// byte order of each sample
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Endianness {
+    Little,
+    Big,
+}
+
+// integer sample widths read_raw understands
+pub const BIT_DEPTHS: [u16; 4] = [8, 16, 24, 32];
+
+// interleaved integer PCM without any header, eg. a dump from an ADC, as a 16 bit WAV
+//
+// 8 bit samples are unsigned as they are in WAV files and wider ones are signed, 24 and 32 bit
+// samples are rounded to the nearest 16 bit value as they are by WAV::from
+pub fn read_raw(
+    data: &[u8],
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+    endianness: Endianness,
+) -> Result<WAV, SpectrogramError> {
+    if sample_rate == 0 || channels == 0 {
+        return Err(SpectrogramError::InvalidHeader(
+            "raw PCM needs a nonzero sample rate and number of channels".to_string(),
+        ));
+    }
+    if !BIT_DEPTHS.contains(&bits_per_sample) {
+        let msg = format!("{} bit raw PCM is not supported", bits_per_sample);
+        return Err(SpectrogramError::InvalidHeader(msg));
+    }
+    let sample_bytes = bits_per_sample as usize / 8;
+    let frame_bytes = sample_bytes * channels as usize;
+    let n_frames = data.len() / frame_bytes;
+    if n_frames == 0 || n_frames * frame_bytes != data.len() {
+        return Err(SpectrogramError::InvalidSampleCount {
+            data_size: data.len() as u32,
+            nchannels: channels,
+        });
+    }
+
+    let mut samples: Vec<Vec<i16>> = vec![Vec::with_capacity(n_frames); channels as usize];
+    for (i, sample) in data.chunks_exact(sample_bytes).enumerate() {
+        samples[i % channels as usize].push(to_i16(sample, endianness));
+    }
+    WAV::from_samples(samples, sample_rate)
+}
+
+// an 8, 16, 24 or 32 bit sample at 16 bits
+pub fn to_i16(sample: &[u8], endianness: Endianness) -> i16 {
+    // most significant byte first, left aligned in 32 bits so every width shares the sign bit
+    let mut value: u32 = 0;
+    let mut push = |byte: &u8| value = (value << 8) | *byte as u32;
+    match endianness {
+        Endianness::Little => sample.iter().rev().for_each(&mut push),
+        Endianness::Big => sample.iter().for_each(&mut push),
+    }
+    let bits = 8 * sample.len() as u32;
+    value <<= 32 - bits;
+    if sample.len() == 1 {
+        // unsigned with silence at 128
+        value ^= 0x8000_0000;
+    }
+    round_to_16bit((value as i32) >> (32 - bits), bits as u16)
+}
+
+#[cfg(test)]
+mod test_read_raw {
+    use super::{read_raw, Endianness};
+    use crate::error::SpectrogramError;
+
+    #[test]
+    fn little_endian_16() {
+        let data = [0x01, 0x00, 0xff, 0x7f, 0x00, 0x80];
+        let wav = read_raw(&data, 8000, 1, 16, Endianness::Little).unwrap();
+        assert_eq!(vec![vec![1, i16::MAX, i16::MIN]], wav.channels);
+        assert_eq!(8000, wav.fmt_header.sample_rate);
+        assert_eq!(3, wav.n_samples().unwrap());
+    }
+
+    #[test]
+    fn big_endian_16() {
+        let data = [0x00, 0x01, 0x7f, 0xff];
+        let wav = read_raw(&data, 8000, 1, 16, Endianness::Big).unwrap();
+        assert_eq!(vec![vec![1, i16::MAX]], wav.channels);
+    }
+
+    #[test]
+    fn interleaved() {
+        let data = [0x01, 0x00, 0x02, 0x00, 0x03, 0x00, 0x04, 0x00];
+        let wav = read_raw(&data, 8000, 2, 16, Endianness::Little).unwrap();
+        assert_eq!(vec![vec![1, 3], vec![2, 4]], wav.channels);
+    }
+
+    #[test]
+    fn unsigned_8() {
+        let wav = read_raw(&[0, 128, 255], 8000, 1, 8, Endianness::Little).unwrap();
+        assert_eq!(vec![vec![i16::MIN, 0, 127 << 8]], wav.channels);
+    }
+
+    #[test]
+    fn rounds_wide_samples() {
+        // 0x123456 and -1 in 24 bits, then 0x7fffffff in 32
+        let data = [0x56, 0x34, 0x12, 0xff, 0xff, 0xff];
+        let wav = read_raw(&data, 8000, 1, 24, Endianness::Little).unwrap();
+        assert_eq!(vec![vec![0x1234, 0]], wav.channels);
+        let data = [0x7f, 0xff, 0xff, 0xff];
+        let wav = read_raw(&data, 8000, 1, 32, Endianness::Big).unwrap();
+        assert_eq!(vec![vec![i16::MAX]], wav.channels);
+    }
+
+    #[test]
+    fn partial_frame() {
+        let result = read_raw(&[0, 0, 0], 8000, 1, 16, Endianness::Little);
+        assert!(matches!(
+            result,
+            Err(SpectrogramError::InvalidSampleCount { data_size: 3, .. })
+        ));
+    }
+
+    #[test]
+    fn unsupported_format() {
+        assert!(read_raw(&[0, 0], 8000, 1, 12, Endianness::Little).is_err());
+        assert!(read_raw(&[0, 0], 8000, 0, 16, Endianness::Little).is_err());
+        assert!(read_raw(&[0, 0], 0, 1, 16, Endianness::Little).is_err());
+    }
+}
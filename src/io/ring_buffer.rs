@@ -0,0 +1,91 @@
+// fixed size buffer which overwrites its oldest values once full
+pub struct RingBuffer<T> {
+    buf: Vec<T>,
+    // index the next value is written to
+    head: usize,
+    len: usize,
+}
+
+impl<T: Clone> RingBuffer<T> {
+    // fill only occupies the storage, it is never returned from latest_n
+    pub fn new(capacity: usize, fill: T) -> RingBuffer<T> {
+        RingBuffer {
+            buf: vec![fill; capacity],
+            head: 0,
+            len: 0,
+        }
+    }
+
+    pub fn push(&mut self, val: T) {
+        let capacity = self.buf.len();
+        if capacity == 0 {
+            return;
+        }
+        self.buf[self.head] = val;
+        self.head = (self.head + 1) % capacity;
+        self.len = (self.len + 1).min(capacity);
+    }
+
+    // the most recent n values, oldest first
+    pub fn latest_n(&self, n: usize) -> Vec<T> {
+        let capacity = self.buf.len();
+        let n = n.min(self.len);
+        let start = (self.head + capacity - n) % capacity.max(1);
+        (0..n)
+            .map(|i| self.buf[(start + i) % capacity].clone())
+            .collect()
+    }
+
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len == 0
+    }
+
+    pub fn is_full(&self) -> bool {
+        self.len == self.buf.len()
+    }
+}
+
+#[cfg(test)]
+mod test_ring_buffer {
+    use super::RingBuffer;
+
+    fn assert_send<T: Send>() {}
+
+    #[test]
+    fn is_send() {
+        assert_send::<RingBuffer<i16>>();
+    }
+
+    #[test]
+    fn partially_filled() {
+        let mut buffer = RingBuffer::new(4, 0);
+        buffer.push(1);
+        buffer.push(2);
+        assert!(!buffer.is_full());
+        assert_eq!(vec![1, 2], buffer.latest_n(10));
+        assert_eq!(vec![2], buffer.latest_n(1));
+    }
+
+    #[test]
+    fn overwrites_oldest() {
+        let mut buffer = RingBuffer::new(3, 0);
+        for value in 1..=5 {
+            buffer.push(value);
+        }
+        assert!(buffer.is_full());
+        assert_eq!(vec![3, 4, 5], buffer.latest_n(3));
+        assert_eq!(vec![4, 5], buffer.latest_n(2));
+    }
+
+    #[test]
+    fn zero_capacity() {
+        let mut buffer = RingBuffer::new(0, 0);
+        buffer.push(1);
+        assert!(buffer.is_empty());
+        assert!(buffer.latest_n(1).is_empty());
+    }
+}
@@ -0,0 +1,165 @@
+use std::fmt;
+
+use crate::io::wav::{FMTHeader, RIFFHeader, WAV};
+
+// header fields which disagree with each other without stopping the samples being read,
+// often a sign of a file written by a buggy encoder or damaged afterwards
+#[derive(Debug, PartialEq)]
+pub enum ValidationWarning {
+    ByteRate { expected: u32, found: u32 },
+    BlockAlign { expected: u16, found: u16 },
+    // data which ends part way through a frame of one sample per channel
+    PartialFrame { data_size: u32, block_align: u16 },
+    // the RIFF chunk claims to end before the fmt and data chunks inside it do
+    RiffSize { expected: u32, found: u32 },
+}
+
+impl fmt::Display for ValidationWarning {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ValidationWarning::ByteRate { expected, found } => write!(
+                f,
+                "byte rate of {} does not match the {} implied by the sample rate and format",
+                found, expected
+            ),
+            ValidationWarning::BlockAlign { expected, found } => write!(
+                f,
+                "block align of {} does not match the {} implied by the format",
+                found, expected
+            ),
+            ValidationWarning::PartialFrame {
+                data_size,
+                block_align,
+            } => write!(
+                f,
+                "data size of {} bytes is not a whole number of {} byte frames",
+                data_size, block_align
+            ),
+            ValidationWarning::RiffSize { expected, found } => write!(
+                f,
+                "RIFF size of {} bytes is less than the {} needed by its fmt and data chunks",
+                found, expected
+            ),
+        }
+    }
+}
+
+// soft checks on a WAV which loaded, unlike the errors from WAV::from which stop it loading
+//
+// WAV::from never reads past the data chunk and rebuilds the headers of files which are not
+// 16 bit, WAV::from_file_checked gives the warnings for the headers as they are in the file
+pub struct WavValidation;
+
+impl WavValidation {
+    pub fn check(wav: &WAV) -> Vec<ValidationWarning> {
+        WavValidation::check_headers(&wav.riff_header, &wav.fmt_header, wav.data_header.size)
+    }
+
+    pub fn check_headers(
+        riff_header: &RIFFHeader,
+        fmt_header: &FMTHeader,
+        data_size: u32,
+    ) -> Vec<ValidationWarning> {
+        let mut warnings = vec![];
+
+        let block_align = fmt_header.nchannels as u32 * (fmt_header.bits_per_sample as u32 / 8);
+        if block_align != fmt_header.block_align as u32 {
+            warnings.push(ValidationWarning::BlockAlign {
+                expected: block_align as u16,
+                found: fmt_header.block_align,
+            });
+        }
+        let byte_rate = fmt_header.sample_rate as u64 * block_align as u64;
+        if byte_rate != fmt_header.byte_rate as u64 {
+            warnings.push(ValidationWarning::ByteRate {
+                expected: byte_rate as u32,
+                found: fmt_header.byte_rate,
+            });
+        }
+        if matches!(data_size.checked_rem(block_align), Some(partial) if partial > 0) {
+            warnings.push(ValidationWarning::PartialFrame {
+                data_size,
+                block_align: block_align as u16,
+            });
+        }
+
+        // WAVE, then each chunk's 8 byte header, body and padding to an even size
+        let riff_size =
+            4 + 8 + fmt_header.header_size as u64 + 8 + data_size as u64 + (data_size % 2) as u64;
+        if riff_size > riff_header.file_size as u64 {
+            warnings.push(ValidationWarning::RiffSize {
+                expected: riff_size as u32,
+                found: riff_header.file_size,
+            });
+        }
+        warnings
+    }
+}
+
+#[cfg(test)]
+mod test_wav_validation {
+    use super::{ValidationWarning, WavValidation};
+    use crate::io::wav::WAV;
+
+    const DEMO: &[u8] = include_bytes!("../demo.wav");
+
+    fn stereo() -> WAV {
+        WAV::from_samples(vec![vec![1, 2, 3], vec![4, 5, 6]], 8000).unwrap()
+    }
+
+    #[test]
+    fn consistent() {
+        assert!(WavValidation::check(&stereo()).is_empty());
+        assert!(WavValidation::check(&WAV::from(DEMO).unwrap()).is_empty());
+    }
+
+    #[test]
+    fn byte_rate_and_block_align() {
+        let mut wav = stereo();
+        wav.fmt_header.byte_rate = 16000;
+        wav.fmt_header.block_align = 2;
+        assert_eq!(
+            vec![
+                ValidationWarning::BlockAlign {
+                    expected: 4,
+                    found: 2
+                },
+                ValidationWarning::ByteRate {
+                    expected: 32000,
+                    found: 16000
+                },
+            ],
+            WavValidation::check(&wav)
+        );
+    }
+
+    #[test]
+    fn partial_frame() {
+        let mut wav = stereo();
+        wav.data_header.size = 14;
+        wav.riff_header.file_size = 50;
+        assert_eq!(
+            vec![ValidationWarning::PartialFrame {
+                data_size: 14,
+                block_align: 4
+            }],
+            WavValidation::check(&wav)
+        );
+    }
+
+    #[test]
+    fn riff_size() {
+        let mut wav = stereo();
+        wav.riff_header.file_size = 40;
+        assert_eq!(
+            vec![ValidationWarning::RiffSize {
+                expected: 48,
+                found: 40
+            }],
+            WavValidation::check(&wav)
+        );
+        // room for other chunks, eg. LIST, is not suspicious
+        wav.riff_header.file_size = 100;
+        assert!(WavValidation::check(&wav).is_empty());
+    }
+}
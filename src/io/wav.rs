@@ -1,10 +1,18 @@
+use std::convert::TryFrom;
 use std::error::Error;
+use std::fmt;
 use std::fs::File;
-use std::io::{BufWriter, Read, Write};
+use std::io::{self, BufWriter, ErrorKind, Read, Seek, SeekFrom, Write};
 use std::str;
+use std::time::Duration;
 
 use byteorder::{LittleEndian, WriteBytesExt};
 
+use crate::error::SpectrogramError;
+use crate::io::metadata::WavMetadata;
+use crate::io::validation::{ValidationWarning, WavValidation};
+use crate::transform::resample::resample;
+
 #[derive(Debug)]
 pub struct RIFFHeader {
     pub riff: String,
@@ -38,7 +46,7 @@ impl RIFFHeader {
         }
     }
 
-    fn write<W: Write>(self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+    fn write<W: Write>(self, writer: &mut W) -> io::Result<()> {
         writer.write(self.riff.as_bytes())?;
         writer.write_u32::<LittleEndian>(self.file_size)?;
         writer.write(self.four_cc.as_bytes())?;
@@ -46,6 +54,12 @@ impl RIFFHeader {
     }
 }
 
+// integer sample widths WAV::from understands, 32 bit samples are narrowed to 16 bits on load
+pub const BITS_PER_SAMPLE: [u16; 2] = [16, 32];
+// format codes of the fmt chunk, extensible files name their real format in a sub format
+const WAVE_FORMAT_PCM: u16 = 1;
+const WAVE_FORMAT_EXTENSIBLE: u16 = 0xFFFE;
+
 #[derive(Debug)]
 pub struct FMTHeader {
     pub fmt: String,
@@ -82,8 +96,14 @@ impl FMTHeader {
             block_align,
             bits_per_sample,
         };
-        if bits_per_sample != 16 {
-            let msg = format!("currently only 16 bit numbers are supported {:?}", header);
+        if format != WAVE_FORMAT_PCM && format != WAVE_FORMAT_EXTENSIBLE {
+            let msg = format!("only integer PCM samples are supported {:?}", header);
+            Err(msg)
+        } else if !BITS_PER_SAMPLE.contains(&bits_per_sample) {
+            let msg = format!(
+                "currently only 16 and 32 bit numbers are supported {:?}",
+                header
+            );
             Err(msg)
         } else if nchannels == 0 || sample_rate == 0 || byte_rate == 0 || bits_per_sample == 0 {
             let msg = format!("insufficent information in FMT header {:?}", header);
@@ -93,7 +113,7 @@ impl FMTHeader {
         }
     }
 
-    fn write<W: Write>(self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+    fn write<W: Write>(self, writer: &mut W) -> io::Result<()> {
         writer.write(self.fmt.as_bytes())?;
         writer.write_u32::<LittleEndian>(self.header_size)?;
         writer.write_u16::<LittleEndian>(self.format)?;
@@ -115,12 +135,6 @@ pub struct DataHeader {
 impl DataHeader {
     fn new(bytes: &[u8; 8]) -> Result<DataHeader, String> {
         let data = match str::from_utf8(&bytes[0..4]) {
-            Ok("smpl") => {
-                return Err("wav files containing a sampler chunk are not supported".to_string())
-            }
-            Ok("LIST") => {
-                return Err("wav files containing a LIST chunk are not supported".to_string())
-            }
             Ok(x) => x.to_string(),
             Err(e) => return Err(e.to_string()),
         };
@@ -129,85 +143,515 @@ impl DataHeader {
         Ok(header)
     }
 
-    fn write<W: Write>(self, writer: &mut W) -> Result<(), Box<dyn Error>> {
+    fn write<W: Write>(self, writer: &mut W) -> io::Result<()> {
         writer.write(self.data.as_bytes())?;
         writer.write_u32::<LittleEndian>(self.size)?;
         Ok(())
     }
 }
 
+// u64 arithmetic so large data chunks can not wrap on 32 bit targets
+fn samples_per_channel(
+    data_size: u32,
+    nchannels: u16,
+    bits_per_sample: u16,
+) -> Result<u64, SpectrogramError> {
+    (nchannels as u64)
+        .checked_mul(bits_per_sample as u64 / 8)
+        .and_then(|block_align| (data_size as u64).checked_div(block_align))
+        .ok_or(SpectrogramError::InvalidSampleCount {
+            data_size,
+            nchannels,
+        })
+}
+
+// length shared by every channel, channels are written interleaved so each needs a sample for
+// every frame
+fn samples_in_each(channels: &[Vec<i16>]) -> Result<usize, SpectrogramError> {
+    let n_samples = match channels.first() {
+        Some(channel) if !channel.is_empty() => channel.len(),
+        Some(_) => return Err(SpectrogramError::MissingInput("sample")),
+        None => return Err(SpectrogramError::MissingInput("channel")),
+    };
+    match channels.iter().find(|channel| channel.len() != n_samples) {
+        Some(channel) => Err(SpectrogramError::ShapeMismatch {
+            expected: n_samples,
+            found: channel.len(),
+        }),
+        None => Ok(n_samples),
+    }
+}
+
+// a little endian sample of 1 to 4 bytes, sign extended
+fn from_le_bytes(sample: &[u8]) -> i32 {
+    let mut word = [0; 4];
+    word[4 - sample.len()..].copy_from_slice(sample);
+    i32::from_le_bytes(word) >> (32 - 8 * sample.len() as u32)
+}
+
+// a sample source_bits wide, from 1 to 32, at 16 bits
+//
+// wider samples are rounded to the nearest value rather than truncated, so samples already on
+// the 16 bit grid come through unchanged, and narrower ones are scaled up to full scale
+pub(crate) fn round_to_16bit(sample: i32, source_bits: u16) -> i16 {
+    let sample = sample as i64;
+    let scaled = if source_bits > 16 {
+        let shift = source_bits - 16;
+        (sample + (1 << (shift - 1))) >> shift
+    } else {
+        sample << (16 - source_bits)
+    };
+    scaled.clamp(i16::MIN as i64, i16::MAX as i64) as i16
+}
+
+// narrows samples that are source_bits wide, eg. 24 or 32, to 16 bits with round_to_16bit
+pub fn convert_to_16bit(samples: &[i32], source_bits: u16) -> Result<Vec<i16>, SpectrogramError> {
+    if !(1..=32).contains(&source_bits) {
+        return Err(SpectrogramError::UnsupportedBitDepth(source_bits));
+    }
+    Ok(samples
+        .iter()
+        .map(|sample| round_to_16bit(*sample, source_bits))
+        .collect())
+}
+
+// headers for a canonical 16 bit PCM file where the data starts at byte 44
+fn pcm_headers(
+    nchannels: u16,
+    sample_rate: u32,
+    data_size: u32,
+) -> Result<(RIFFHeader, FMTHeader, DataHeader), SpectrogramError> {
+    let too_large = || {
+        let msg = format!(
+            "{} bytes in {} channels at {} Hz do not fit in 16 bit PCM headers",
+            data_size, nchannels, sample_rate
+        );
+        SpectrogramError::InvalidHeader(msg)
+    };
+    let block_align = nchannels.checked_mul(2).ok_or_else(too_large)?;
+    let byte_rate = sample_rate
+        .checked_mul(block_align as u32)
+        .ok_or_else(too_large)?;
+    let file_size = data_size.checked_add(36).ok_or_else(too_large)?;
+    Ok((
+        RIFFHeader {
+            riff: "RIFF".to_string(),
+            file_size,
+            four_cc: "WAVE".to_string(),
+        },
+        FMTHeader {
+            fmt: "fmt ".to_string(),
+            header_size: 16,
+            format: 1,
+            nchannels,
+            sample_rate,
+            byte_rate,
+            block_align,
+            bits_per_sample: 16,
+        },
+        DataHeader {
+            data: "data".to_string(),
+            size: data_size,
+        },
+    ))
+}
+
+pub struct DataChunk {
+    pub header: DataHeader,
+    // little endian samples, interleaved by channel
+    pub bytes: Vec<u8>,
+}
+
+pub enum WavChunk {
+    Riff(RIFFHeader),
+    Fmt(FMTHeader),
+    Data(DataChunk),
+    // eg. LIST, fact or smpl, without the padding byte of odd sized chunks
+    Unknown { id: [u8; 4], data: Vec<u8> },
+}
+
+// yields the RIFF header then every chunk in the order they appear in the file, reading
+// each one only when asked for it so callers can stop as soon as they have what they need
+//
+// iteration ends at the end of the file or after the first error
+pub struct WavChunkReader<R: Read> {
+    reader: R,
+    read_riff: bool,
+    finished: bool,
+    skip_unknown: bool,
+}
+
+impl<R: Read> WavChunkReader<R> {
+    pub fn new(reader: R) -> WavChunkReader<R> {
+        WavChunkReader {
+            reader,
+            read_riff: false,
+            finished: false,
+            skip_unknown: false,
+        }
+    }
+
+    // unknown chunks other than LIST, which holds the metadata, are skipped over without
+    // being read into memory or yielded
+    pub fn skip_unknown(mut self) -> WavChunkReader<R> {
+        self.skip_unknown = true;
+        self
+    }
+
+    // None once there are no more chunks
+    fn read_chunk(&mut self) -> Result<Option<WavChunk>, SpectrogramError> {
+        if !self.read_riff {
+            self.read_riff = true;
+            let mut buf = [0u8; 12];
+            self.reader.read_exact(&mut buf)?;
+            let header = RIFFHeader::new(&buf).map_err(SpectrogramError::InvalidHeader)?;
+            return Ok(Some(WavChunk::Riff(header)));
+        }
+
+        let mut chunk = [0u8; 8];
+        let (id, size) = loop {
+            if let Err(e) = self.reader.read_exact(&mut chunk) {
+                return match e.kind() {
+                    ErrorKind::UnexpectedEof => Ok(None),
+                    _ => Err(e.into()),
+                };
+            }
+            let id = [chunk[0], chunk[1], chunk[2], chunk[3]];
+            let size = u32::from_le_bytes([chunk[4], chunk[5], chunk[6], chunk[7]]);
+            match &id {
+                b"fmt " | b"data" | b"LIST" => break (id, size),
+                _ if self.skip_unknown => self.skip(size as u64 + (size % 2) as u64)?,
+                _ => break (id, size),
+            }
+        };
+        let wav_chunk = match &id {
+            b"fmt " => {
+                if size < 16 {
+                    let msg = format!("fmt chunk of {} bytes is too small for PCM", size);
+                    return Err(SpectrogramError::InvalidHeader(msg));
+                }
+                let mut buf = [0u8; 24];
+                buf[..8].copy_from_slice(&chunk);
+                self.reader.read_exact(&mut buf[8..])?;
+                let header = FMTHeader::new(&buf).map_err(SpectrogramError::InvalidHeader)?;
+                // any extension past the 16 bytes of PCM format information
+                let mut extension_size = size as u64 - 16;
+                if header.format == WAVE_FORMAT_EXTENSIBLE {
+                    // cbSize, valid bits and channel mask come before the sub format's guid
+                    let mut extension = [0u8; 24];
+                    if extension_size < extension.len() as u64 {
+                        let msg = format!("fmt chunk of {} bytes has no sub format", size);
+                        return Err(SpectrogramError::InvalidHeader(msg));
+                    }
+                    self.reader.read_exact(&mut extension)?;
+                    extension_size -= extension.len() as u64;
+                    let sub_format = u16::from_le_bytes([extension[8], extension[9]]);
+                    if sub_format != WAVE_FORMAT_PCM {
+                        let msg = format!(
+                            "only integer PCM samples are supported, found sub format {}",
+                            sub_format
+                        );
+                        return Err(SpectrogramError::InvalidHeader(msg));
+                    }
+                }
+                self.skip(extension_size)?;
+                WavChunk::Fmt(header)
+            }
+            b"data" => {
+                let header = DataHeader::new(&chunk).map_err(SpectrogramError::InvalidHeader)?;
+                WavChunk::Data(DataChunk {
+                    header,
+                    bytes: self.read_body(size)?,
+                })
+            }
+            _ => WavChunk::Unknown {
+                id,
+                data: self.read_body(size)?,
+            },
+        };
+        // chunks are padded to an even number of bytes
+        self.skip((size % 2) as u64)?;
+        Ok(Some(wav_chunk))
+    }
+
+    // up to n_bytes, fewer when the file is truncated
+    fn read_body(&mut self, n_bytes: u32) -> io::Result<Vec<u8>> {
+        let mut body = Vec::new();
+        (&mut self.reader)
+            .take(n_bytes as u64)
+            .read_to_end(&mut body)?;
+        Ok(body)
+    }
+
+    fn skip(&mut self, n_bytes: u64) -> io::Result<()> {
+        io::copy(&mut (&mut self.reader).take(n_bytes), &mut io::sink()).map(|_| ())
+    }
+}
+
+impl<R: Read> Iterator for WavChunkReader<R> {
+    type Item = Result<WavChunk, SpectrogramError>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        if self.finished {
+            return None;
+        }
+        let chunk = self.read_chunk().transpose();
+        if !matches!(chunk, Some(Ok(_))) {
+            self.finished = true;
+        }
+        chunk
+    }
+}
+
 pub struct WAV {
     pub riff_header: RIFFHeader,
     pub fmt_header: FMTHeader,
     pub data_header: DataHeader,
     pub channels: Vec<Vec<i16>>,
+    // every bit of files wider than the 16 bits channels are narrowed to, None for 16 bit
+    // files where channels already holds them all
+    pub wide_channels: Option<Vec<Vec<i32>>>,
+    pub metadata: WavMetadata,
 }
 
 impl WAV {
-    pub fn from<T: Read>(mut f: T) -> Result<WAV, Box<dyn Error>> {
-        let mut buf = [0u8; 12];
-        f.read(&mut buf)?;
-        let riff_header = RIFFHeader::new(&buf)?;
-
-        let mut buf = [0u8; 24];
-        f.read(&mut buf)?;
-        let fmt_header = FMTHeader::new(&buf)?;
+    pub fn from<T: Read>(f: T) -> Result<WAV, Box<dyn Error>> {
+        WAV::from_chunks(WavChunkReader::new(f))
+            .map(|(wav, _)| wav)
+            .map_err(|e| e.into())
+    }
 
-        let mut buf = [0u8; 8];
-        f.read(&mut buf)?;
-        let data_header = DataHeader::new(&buf)?;
+    pub fn from_reader<R: Read>(reader: R) -> Result<WAV, SpectrogramError> {
+        WAV::from_chunks(WavChunkReader::new(reader).skip_unknown()).map(|(wav, _)| wav)
+    }
 
-        // for debugging
-        // TODO implement as log
-        // println!("riff_header: {:?}", riff_header);
-        // println!("fmt_header: {:?}", fmt_header);
-        // println!("data_header: {:?}", data_header);
+    // fmt, data and LIST chunks are used, chunks after the data are never read. the headers
+    // are validated as they are in the file, before from_parts rebuilds those of files which
+    // are not 16 bit
+    fn from_chunks<R: Read>(
+        chunks: WavChunkReader<R>,
+    ) -> Result<(WAV, Vec<ValidationWarning>), SpectrogramError> {
+        let mut riff_header = None;
+        let mut fmt_header = None;
+        let mut metadata = WavMetadata::default();
+        for chunk in chunks {
+            match chunk? {
+                WavChunk::Riff(header) => riff_header = Some(header),
+                WavChunk::Fmt(header) => fmt_header = Some(header),
+                WavChunk::Data(DataChunk { header, bytes }) => {
+                    let riff_header = riff_header.ok_or(SpectrogramError::MissingChunk("RIFF"))?;
+                    let fmt_header = fmt_header.ok_or(SpectrogramError::MissingChunk("fmt "))?;
+                    let warnings =
+                        WavValidation::check_headers(&riff_header, &fmt_header, header.size);
+                    return WAV::from_parts(riff_header, fmt_header, header, metadata, &bytes)
+                        .map(|wav| (wav, warnings));
+                }
+                WavChunk::Unknown { id, data } if &id == b"LIST" => {
+                    metadata = WavMetadata::from_list_chunk(&data);
+                }
+                WavChunk::Unknown { .. } => {}
+            }
+        }
+        Err(SpectrogramError::MissingChunk("data"))
+    }
 
+    fn from_parts(
+        riff_header: RIFFHeader,
+        fmt_header: FMTHeader,
+        data_header: DataHeader,
+        metadata: WavMetadata,
+        bytes: &[u8],
+    ) -> Result<WAV, SpectrogramError> {
         let n_channels: usize = fmt_header.nchannels.into();
 
-        // TODO we can calculate the needed capacity given the header information
-        let mut channels: Vec<Vec<i16>> = vec![vec![]; n_channels];
+        let bits_per_sample = fmt_header.bits_per_sample;
 
-        let mut buf = Vec::new();
-        f.read_to_end(&mut buf)?;
-        for (i, sample) in buf.chunks(2).enumerate() {
+        // TODO we can calculate the needed capacity given the header information
+        let mut wide_channels: Vec<Vec<i32>> = vec![vec![]; n_channels];
+        for (i, sample) in bytes.chunks_exact(bits_per_sample as usize / 8).enumerate() {
             let channel = i % n_channels;
-            channels[channel].push(i16::from_le_bytes([sample[0], sample[1]]));
+            wide_channels[channel].push(from_le_bytes(sample));
         }
+        let channels = wide_channels
+            .iter()
+            .map(|channel| convert_to_16bit(channel, bits_per_sample))
+            .collect::<Result<Vec<Vec<i16>>, SpectrogramError>>()?;
 
-        let expected_n_samples = data_header.size / (fmt_header.nchannels as u32 * 2);
+        let expected_n_samples =
+            samples_per_channel(data_header.size, fmt_header.nchannels, bits_per_sample)?;
 
         let wav = WAV {
             riff_header,
             fmt_header,
             data_header,
             channels,
+            wide_channels: (bits_per_sample > 16).then_some(wide_channels),
+            metadata,
         };
+        log::debug!("{:?}", wav);
 
-        let n_samples = wav.channels[0].len() as u32;
+        let n_samples = wav.channels[0].len() as u64;
         if n_samples != expected_n_samples {
-            let msg = format!(
-                "error reading samples. expected {}, found {}",
-                expected_n_samples, n_samples
-            );
-            Err(msg.into())
+            Err(SpectrogramError::SampleCountMismatch {
+                expected: expected_n_samples,
+                found: n_samples,
+            })
+        } else if bits_per_sample != 16 {
+            wav.to_16bit()
         } else {
             Ok(wav)
         }
     }
 
-    pub fn from_file(filename: &str) -> Result<WAV, Box<dyn Error>> {
+    // builds a 16 bit PCM WAV with synthesized headers around existing samples, every channel
+    // needs the same, non zero, number of samples
+    pub fn from_samples(
+        channels: Vec<Vec<i16>>,
+        sample_rate: u32,
+    ) -> Result<WAV, SpectrogramError> {
+        let n_samples = samples_in_each(&channels)?;
+        let too_long = || {
+            let msg = format!(
+                "{} samples in each of {} channels do not fit in a WAV file",
+                n_samples,
+                channels.len()
+            );
+            SpectrogramError::InvalidHeader(msg)
+        };
+        let nchannels = u16::try_from(channels.len()).map_err(|_| too_long())?;
+        let data_size = u32::try_from(n_samples)
+            .ok()
+            .and_then(|n_samples| n_samples.checked_mul(nchannels as u32 * 2))
+            .ok_or_else(too_long)?;
+        let (riff_header, fmt_header, data_header) =
+            pcm_headers(nchannels, sample_rate, data_size)?;
+        Ok(WAV {
+            riff_header,
+            fmt_header,
+            data_header,
+            channels,
+            wide_channels: None,
+            metadata: WavMetadata::default(),
+        })
+    }
+
+    // headers of a canonical 16 bit PCM file around the same samples, channels are always held
+    // at 16 bits so this only matters once the headers describe something else, eg. 32 bits.
+    // wide_channels are kept, they are what was read rather than what the headers describe
+    pub fn to_16bit(self) -> Result<WAV, SpectrogramError> {
+        Ok(WAV {
+            wide_channels: self.wide_channels,
+            metadata: self.metadata,
+            ..WAV::from_samples(self.channels, self.fmt_header.sample_rate)?
+        })
+    }
+
+    // the synthesized headers follow the new sample rate and data size
+    pub fn resample_to(&self, target_rate: u32) -> Result<WAV, SpectrogramError> {
+        let sample_rate = self.fmt_header.sample_rate;
+        let channels = self
+            .channels
+            .iter()
+            .map(|channel| resample(channel, sample_rate, target_rate))
+            .collect();
+        WAV::from_samples(channels, target_rate)
+    }
+
+    pub fn channel(&self, n: usize) -> Result<&[i16], SpectrogramError> {
+        self.channels
+            .get(n)
+            .map(|channel| channel.as_slice())
+            .ok_or(SpectrogramError::ChannelOutOfRange {
+                requested: n,
+                available: self.channels.len(),
+            })
+    }
+
+    // each channel with its index, in file order
+    pub fn channels_iter(&self) -> impl Iterator<Item = (usize, &[i16])> {
+        self.channels
+            .iter()
+            .enumerate()
+            .map(|(i, channel)| (i, channel.as_slice()))
+    }
+
+    pub fn n_samples(&self) -> Result<usize, SpectrogramError> {
+        let n_samples = samples_per_channel(
+            self.data_header.size,
+            self.fmt_header.nchannels,
+            self.fmt_header.bits_per_sample,
+        )?;
+        usize::try_from(n_samples).map_err(|_| SpectrogramError::InvalidSampleCount {
+            data_size: self.data_header.size,
+            nchannels: self.fmt_header.nchannels,
+        })
+    }
+
+    // length of the samples, rather than of the data chunk the headers describe
+    pub fn duration(&self) -> Duration {
+        let n_samples = self.channels.first().map_or(0, |channel| channel.len());
+        Duration::from_secs_f64(n_samples as f64 / self.fmt_header.sample_rate as f64)
+    }
+
+    // linear ramp from silence up to full scale over the start of every channel,
+    // durations longer than the file fade all of it
+    pub fn fade_in(mut self, duration: Duration) -> WAV {
+        let sample_rate = self.fmt_header.sample_rate;
+        for channel in self.channels.iter_mut() {
+            let n_fade = fade_length(duration, sample_rate, channel.len());
+            for (i, sample) in channel[..n_fade].iter_mut().enumerate() {
+                *sample = (*sample as f64 * i as f64 / n_fade as f64) as i16;
+            }
+        }
+        self
+    }
+
+    // linear ramp from full scale down to silence over the end of every channel,
+    // durations longer than the file fade all of it
+    pub fn fade_out(mut self, duration: Duration) -> WAV {
+        let sample_rate = self.fmt_header.sample_rate;
+        for channel in self.channels.iter_mut() {
+            let n_fade = fade_length(duration, sample_rate, channel.len());
+            for (i, sample) in channel.iter_mut().rev().take(n_fade).enumerate() {
+                *sample = (*sample as f64 * i as f64 / n_fade as f64) as i16;
+            }
+        }
+        self
+    }
+
+    pub fn from_file(filename: &str) -> Result<WAV, SpectrogramError> {
+        let f = File::open(filename)?;
+        WAV::from_reader(f)
+    }
+
+    // with what WavValidation finds in the headers as they were before being rebuilt
+    pub fn from_file_checked(
+        filename: &str,
+    ) -> Result<(WAV, Vec<ValidationWarning>), SpectrogramError> {
         let f = File::open(filename)?;
-        WAV::from(f)
+        WAV::from_chunks(WavChunkReader::new(f).skip_unknown())
     }
 
-    pub fn write(self, filename: &str) -> Result<(), Box<dyn Error>> {
+    pub fn write(self, filename: &str) -> Result<(), SpectrogramError> {
         let f = File::create(filename)?;
         let mut writer = BufWriter::new(f);
-        self.riff_header.write(&mut writer)?;
-        self.fmt_header.write(&mut writer)?;
-        self.data_header.write(&mut writer)?;
-        let n_samples = self.channels[0].len();
+        self.write_to(&mut writer)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    // a mono 16 bit PCM file of one channel, eg. the left side of a stereo recording
+    pub fn write_channel(&self, channel: usize, filename: &str) -> Result<(), SpectrogramError> {
+        let samples = self.channel(channel)?.to_vec();
+        WAV::from_samples(vec![samples], self.fmt_header.sample_rate)?.write(filename)
+    }
+
+    pub fn write_to<W: Write>(self, writer: &mut W) -> Result<(), SpectrogramError> {
+        // checked before anything is written, so a bad WAV leaves the writer untouched
+        let n_samples = samples_in_each(&self.channels)?;
+        self.riff_header.write(writer)?;
+        self.fmt_header.write(writer)?;
+        self.data_header.write(writer)?;
         for sample in 0..n_samples {
             for channel in self.channels.iter() {
                 writer.write_i16::<LittleEndian>(channel[sample])?
@@ -218,6 +662,692 @@ impl WAV {
     }
 }
 
+// writes the headers up front with placeholder sizes, finalize patches them once the
+// total amount of data is known
+//
+// the file starts wherever writer is when it is given, eg. part way through an archive
+pub struct WavWriter<W: Write + Seek> {
+    writer: BufWriter<W>,
+    // position of the RIFF header in writer
+    start: u64,
+    data_size: u32,
+}
+
+impl<W: Write + Seek> WavWriter<W> {
+    pub fn new(
+        mut writer: W,
+        sample_rate: u32,
+        channels: u16,
+        bits_per_sample: u16,
+    ) -> Result<Self, SpectrogramError> {
+        if bits_per_sample != 16 {
+            let msg = format!(
+                "currently only 16 bit numbers are supported, not {}",
+                bits_per_sample
+            );
+            return Err(SpectrogramError::InvalidHeader(msg));
+        }
+        if channels == 0 || sample_rate == 0 {
+            let msg = format!(
+                "unable to write {} channels at a sample rate of {}",
+                channels, sample_rate
+            );
+            return Err(SpectrogramError::InvalidHeader(msg));
+        }
+        let (riff_header, fmt_header, data_header) = pcm_headers(channels, sample_rate, 0)?;
+        let start = writer.stream_position()?;
+        // one write_i16 per sample is too many system calls for a file
+        let mut writer = BufWriter::new(writer);
+        riff_header.write(&mut writer)?;
+        fmt_header.write(&mut writer)?;
+        data_header.write(&mut writer)?;
+        Ok(WavWriter {
+            writer,
+            start,
+            data_size: 0,
+        })
+    }
+
+    // samples are interleaved across channels
+    pub fn write_samples(&mut self, samples: &[i16]) -> Result<(), SpectrogramError> {
+        let data_size = u32::try_from(samples.len() * 2)
+            .ok()
+            .and_then(|size| self.data_size.checked_add(size))
+            .ok_or_else(|| {
+                SpectrogramError::InvalidHeader("data chunk can not exceed 4 GiB".to_string())
+            })?;
+        for sample in samples {
+            self.writer.write_i16::<LittleEndian>(*sample)?;
+        }
+        self.data_size = data_size;
+        Ok(())
+    }
+
+    pub fn finalize(mut self) -> Result<(), SpectrogramError> {
+        let file_size = self.data_size.checked_add(36).ok_or_else(|| {
+            SpectrogramError::InvalidHeader("file size can not exceed 4 GiB".to_string())
+        })?;
+        self.writer.seek(SeekFrom::Start(self.start + 4))?;
+        self.writer.write_u32::<LittleEndian>(file_size)?;
+        self.writer.seek(SeekFrom::Start(self.start + 40))?;
+        self.writer.write_u32::<LittleEndian>(self.data_size)?;
+        // left at the end of the file for anything written after it
+        self.writer
+            .seek(SeekFrom::Start(self.start + 8 + file_size as u64))?;
+        self.writer.flush()?;
+        Ok(())
+    }
+}
+
+// walks the chunks like WAV::from_reader, so extra chunks before the data are skipped
+impl TryFrom<&[u8]> for WAV {
+    type Error = SpectrogramError;
+
+    fn try_from(bytes: &[u8]) -> Result<WAV, SpectrogramError> {
+        WAV::from_reader(io::Cursor::new(bytes))
+    }
+}
+
+// summarizes the samples rather than printing every one of them
+impl fmt::Debug for WAV {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let samples_per_channel = self.channels.first().map_or(0, |c| c.len());
+        let duration = samples_per_channel as f64 / self.fmt_header.sample_rate as f64;
+        f.debug_struct("WAV")
+            .field("sample_rate", &self.fmt_header.sample_rate)
+            .field("channels", &self.fmt_header.nchannels)
+            .field("samples_per_channel", &samples_per_channel)
+            .field("duration", &format_args!("{:.3}s", duration))
+            .field("bits", &self.fmt_header.bits_per_sample)
+            .finish()
+    }
+}
+
+// samples covered by a fade of duration, at most the whole channel
+fn fade_length(duration: Duration, sample_rate: u32, n_samples: usize) -> usize {
+    ((duration.as_secs_f64() * sample_rate as f64).round() as usize).min(n_samples)
+}
+
+#[cfg(test)]
+mod test_debug {
+    use super::WAV;
+
+    #[test]
+    fn summary() {
+        let wav = WAV::from_samples(vec![vec![0; 132300], vec![0; 132300]], 44100).unwrap();
+        assert_eq!(
+            "WAV { sample_rate: 44100, channels: 2, samples_per_channel: 132300, duration: 3.000s, bits: 16 }",
+            format!("{:?}", wav)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_resample_to {
+    use super::WAV;
+
+    #[test]
+    fn updates_headers() {
+        let wav = WAV::from_samples(vec![vec![0; 44100], vec![0; 44100]], 44100).unwrap();
+        let resampled = wav.resample_to(22050).unwrap();
+        assert_eq!(22050, resampled.fmt_header.sample_rate);
+        assert_eq!(88200, resampled.fmt_header.byte_rate);
+        assert_eq!(88200, resampled.data_header.size);
+        assert_eq!(22050, resampled.n_samples().unwrap());
+    }
+}
+
+#[cfg(test)]
+mod test_channel {
+    use super::WAV;
+    use crate::error::SpectrogramError;
+
+    #[test]
+    fn in_range() {
+        let wav = WAV::from_samples(vec![vec![1, 2], vec![3, 4]], 8000).unwrap();
+        assert_eq!(&[3, 4], wav.channel(1).unwrap())
+    }
+
+    #[test]
+    fn channels_iter() {
+        let wav = WAV::from_samples(vec![vec![1, 2], vec![3, 4]], 8000).unwrap();
+        let channels: Vec<(usize, &[i16])> = wav.channels_iter().collect();
+        assert_eq!(vec![(0, &[1, 2][..]), (1, &[3, 4][..])], channels);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let wav = WAV::from_samples(vec![vec![1, 2]], 8000).unwrap();
+        match wav.channel(1) {
+            Err(SpectrogramError::ChannelOutOfRange {
+                requested,
+                available,
+            }) => assert_eq!((1, 1), (requested, available)),
+            _ => panic!("expected ChannelOutOfRange"),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_fade {
+    use super::WAV;
+    use std::time::Duration;
+
+    #[test]
+    fn fade_in() {
+        let wav = WAV::from_samples(vec![vec![1000; 8]], 4)
+            .unwrap()
+            .fade_in(Duration::from_millis(1000));
+        assert_eq!(
+            vec![0, 250, 500, 750, 1000, 1000, 1000, 1000],
+            wav.channels[0]
+        );
+    }
+
+    #[test]
+    fn fade_out() {
+        let wav = WAV::from_samples(vec![vec![1000; 8], vec![-1000; 8]], 4)
+            .unwrap()
+            .fade_out(Duration::from_millis(1000));
+        assert_eq!(
+            vec![1000, 1000, 1000, 1000, 750, 500, 250, 0],
+            wav.channels[0]
+        );
+        assert_eq!(
+            vec![-1000, -1000, -1000, -1000, -750, -500, -250, 0],
+            wav.channels[1]
+        );
+    }
+
+    #[test]
+    fn longer_than_file() {
+        let wav = WAV::from_samples(vec![vec![1000; 4]], 4).unwrap();
+        assert_eq!(Duration::from_secs(1), wav.duration());
+        let wav = wav
+            .fade_in(Duration::from_secs(10))
+            .fade_out(Duration::from_secs(10));
+        assert_eq!(vec![0, 125, 125, 0], wav.channels[0]);
+    }
+}
+
+#[cfg(test)]
+mod test_from_samples {
+    use super::WAV;
+    use crate::error::SpectrogramError;
+
+    #[test]
+    fn headers() {
+        let wav = WAV::from_samples(vec![vec![1, 2, 3], vec![4, 5, 6]], 8000).unwrap();
+        assert_eq!(2, wav.fmt_header.nchannels);
+        assert_eq!(12, wav.data_header.size);
+        assert_eq!(48, wav.riff_header.file_size);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(matches!(
+            WAV::from_samples(vec![], 8000),
+            Err(SpectrogramError::MissingInput("channel"))
+        ));
+        assert!(matches!(
+            WAV::from_samples(vec![vec![], vec![]], 8000),
+            Err(SpectrogramError::MissingInput("sample"))
+        ));
+    }
+
+    #[test]
+    fn mismatched_channels() {
+        assert!(matches!(
+            WAV::from_samples(vec![vec![1, 2, 3], vec![4, 5]], 8000),
+            Err(SpectrogramError::ShapeMismatch {
+                expected: 3,
+                found: 2
+            })
+        ));
+    }
+
+    #[test]
+    fn write_to_checks_channels() {
+        let mut wav = WAV::from_samples(vec![vec![1, 2], vec![3, 4]], 8000).unwrap();
+        wav.channels[1].pop();
+        let mut bytes = Vec::new();
+        assert!(wav.write_to(&mut bytes).is_err());
+        assert!(bytes.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_write_channel {
+    use super::WAV;
+    use crate::error::SpectrogramError;
+    use std::env;
+    use std::fs;
+
+    #[test]
+    fn mono_from_stereo() {
+        let original = WAV::from_samples(vec![vec![1, -2, 3], vec![4, 5, -6]], 8000).unwrap();
+        let path = env::temp_dir().join("spectrogram_test_write_channel.wav");
+        let filename = path.to_str().unwrap();
+        original.write_channel(0, filename).unwrap();
+        let mono = WAV::from_file(filename);
+        fs::remove_file(&path).unwrap();
+
+        let mono = mono.unwrap();
+        assert_eq!(vec![original.channels[0].clone()], mono.channels);
+        assert_eq!(1, mono.fmt_header.nchannels);
+        assert_eq!(2, mono.fmt_header.block_align);
+        assert_eq!(16000, mono.fmt_header.byte_rate);
+        assert_eq!(6, mono.data_header.size);
+        assert_eq!(42, mono.riff_header.file_size);
+    }
+
+    #[test]
+    fn out_of_range() {
+        let wav = WAV::from_samples(vec![vec![0; 4]], 8000).unwrap();
+        let path = env::temp_dir().join("spectrogram_test_write_channel_out_of_range.wav");
+        assert!(matches!(
+            wav.write_channel(1, path.to_str().unwrap()),
+            Err(SpectrogramError::ChannelOutOfRange { .. })
+        ));
+        assert!(!path.exists());
+    }
+}
+
+#[cfg(test)]
+mod test_samples_per_channel {
+    use super::samples_per_channel;
+
+    #[test]
+    fn stereo() {
+        assert_eq!(1000, samples_per_channel(4000, 2, 16).unwrap())
+    }
+
+    #[test]
+    fn thirty_two_bit() {
+        assert_eq!(500, samples_per_channel(4000, 2, 32).unwrap())
+    }
+
+    #[test]
+    fn max_data_size() {
+        assert_eq!(
+            u32::MAX as u64 / 2,
+            samples_per_channel(u32::MAX, 1, 16).unwrap()
+        )
+    }
+
+    #[test]
+    fn zero_channels() {
+        assert!(samples_per_channel(4000, 0, 16).is_err())
+    }
+}
+
+#[cfg(test)]
+mod test_from_reader {
+    use super::WAV;
+    use std::convert::TryFrom;
+    use std::io::Cursor;
+
+    const DEMO: &[u8] = include_bytes!("../demo.wav");
+
+    #[test]
+    fn matches_linear_read() {
+        let linear = WAV::from(DEMO).unwrap();
+        let skipping = WAV::from_reader(Cursor::new(DEMO)).unwrap();
+        assert_eq!(linear.channels, skipping.channels)
+    }
+
+    #[test]
+    fn skips_unknown_chunks() {
+        let mut bytes = DEMO[..36].to_vec();
+        // an odd sized chunk to exercise the padding byte
+        bytes.extend_from_slice(b"LIST\x03\x00\x00\x00abc\x00");
+        bytes.extend_from_slice(&DEMO[36..]);
+        let wav = WAV::from_reader(Cursor::new(bytes)).unwrap();
+        assert_eq!(WAV::from(DEMO).unwrap().channels, wav.channels)
+    }
+
+    #[test]
+    fn linear_read_skips_unknown_chunks() {
+        let mut bytes = DEMO[..36].to_vec();
+        bytes.extend_from_slice(b"fact\x04\x00\x00\x00\x00\x00\x00\x00");
+        bytes.extend_from_slice(b"smpl\x03\x00\x00\x00abc\x00");
+        bytes.extend_from_slice(&DEMO[36..]);
+        let wav = WAV::from(&bytes[..]).unwrap();
+        assert_eq!(WAV::from(DEMO).unwrap().channels, wav.channels)
+    }
+
+    #[test]
+    fn extended_fmt_chunk() {
+        let mut bytes = DEMO[..16].to_vec();
+        // cbSize of zero after the 16 bytes of PCM format information
+        bytes.extend_from_slice(b"\x12\x00\x00\x00");
+        bytes.extend_from_slice(&DEMO[20..36]);
+        bytes.extend_from_slice(b"\x00\x00");
+        bytes.extend_from_slice(&DEMO[36..]);
+        let wav = WAV::from(&bytes[..]).unwrap();
+        assert_eq!(WAV::from(DEMO).unwrap().channels, wav.channels)
+    }
+
+    // DEMO with the format code and, for extensible files, the fmt extension replaced
+    fn with_format(format: u16, sub_format: Option<u16>) -> Vec<u8> {
+        let mut bytes = DEMO[..16].to_vec();
+        let extension_size: u32 = if sub_format.is_some() { 24 } else { 0 };
+        bytes.extend_from_slice(&(16 + extension_size).to_le_bytes());
+        bytes.extend_from_slice(&format.to_le_bytes());
+        bytes.extend_from_slice(&DEMO[22..36]);
+        if let Some(sub_format) = sub_format {
+            bytes.extend_from_slice(b"\x16\x00\x10\x00\x03\x00\x00\x00");
+            bytes.extend_from_slice(&sub_format.to_le_bytes());
+            bytes.extend_from_slice(b"\x00\x00\x00\x00\x10\x00\x80\x00\x00\xaa\x00\x38\x9b\x71");
+        }
+        bytes.extend_from_slice(&DEMO[36..]);
+        bytes
+    }
+
+    #[test]
+    fn float_rejected() {
+        assert!(WAV::from(&with_format(3, None)[..]).is_err());
+        assert!(WAV::from(&with_format(0xFFFE, Some(3))[..]).is_err());
+    }
+
+    #[test]
+    fn extensible_pcm() {
+        let wav = WAV::from(&with_format(0xFFFE, Some(1))[..]).unwrap();
+        assert_eq!(WAV::from(DEMO).unwrap().channels, wav.channels);
+        // no room for a sub format
+        assert!(WAV::from(&with_format(0xFFFE, None)[..]).is_err());
+    }
+
+    #[test]
+    fn try_from_slice() {
+        let wav = WAV::try_from(DEMO).unwrap();
+        assert_eq!(WAV::from(DEMO).unwrap().channels, wav.channels)
+    }
+
+    #[test]
+    fn missing_data_chunk() {
+        assert!(WAV::from_reader(Cursor::new(&DEMO[..36])).is_err())
+    }
+
+    fn with_info_chunk() -> Vec<u8> {
+        let mut bytes = DEMO[..36].to_vec();
+        bytes.extend_from_slice(b"LIST\x12\x00\x00\x00INFOINAM\x05\x00\x00\x00demo\x00\x00");
+        bytes.extend_from_slice(&DEMO[36..]);
+        bytes
+    }
+
+    #[test]
+    fn reads_title() {
+        let wav = WAV::from_reader(Cursor::new(with_info_chunk())).unwrap();
+        assert_eq!(Some("demo".to_string()), wav.metadata.title)
+    }
+
+    #[test]
+    fn linear_read_skips_list() {
+        let wav = WAV::from(&with_info_chunk()[..]).unwrap();
+        assert_eq!(Some("demo".to_string()), wav.metadata.title);
+        assert_eq!(WAV::from(DEMO).unwrap().channels, wav.channels)
+    }
+}
+
+#[cfg(test)]
+mod test_convert_to_16bit {
+    use super::convert_to_16bit;
+    use crate::error::SpectrogramError;
+
+    #[test]
+    fn full_scale_32_bit() {
+        assert_eq!(
+            vec![i16::MAX, i16::MIN],
+            convert_to_16bit(&[i32::MAX, i32::MIN], 32).unwrap()
+        )
+    }
+
+    #[test]
+    fn lossless_on_the_16_bit_grid() {
+        let samples: Vec<i16> = vec![i16::MIN, -1, 0, 1, 12345, i16::MAX];
+        let wide_32: Vec<i32> = samples.iter().map(|s| (*s as i32) << 16).collect();
+        let wide_24: Vec<i32> = samples.iter().map(|s| (*s as i32) << 8).collect();
+        assert_eq!(samples, convert_to_16bit(&wide_32, 32).unwrap());
+        assert_eq!(samples, convert_to_16bit(&wide_24, 24).unwrap());
+    }
+
+    #[test]
+    fn rounds_to_nearest() {
+        assert_eq!(
+            vec![1, 0, -1],
+            convert_to_16bit(&[0x17f, 0x7f, -0x81], 24).unwrap()
+        )
+    }
+
+    #[test]
+    fn sixteen_bit_unchanged() {
+        assert_eq!(vec![-5, 7], convert_to_16bit(&[-5, 7], 16).unwrap())
+    }
+
+    #[test]
+    fn narrow_samples_scaled_up() {
+        assert_eq!(
+            vec![i16::MIN, -256, 127 << 8],
+            convert_to_16bit(&[-128, -1, 127], 8).unwrap()
+        )
+    }
+
+    #[test]
+    fn unsupported_widths() {
+        for bits in [0, 33, 80].iter() {
+            assert!(matches!(
+                convert_to_16bit(&[1], *bits),
+                Err(SpectrogramError::UnsupportedBitDepth(b)) if b == *bits
+            ));
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_thirty_two_bit {
+    use super::{WavChunkReader, WAV};
+    use crate::io::validation::{ValidationWarning, WavValidation};
+
+    // mono 32 bit PCM at 8 kHz holding the given samples
+    fn wav_bytes(samples: &[i32], bits_per_sample: u16) -> Vec<u8> {
+        let data_size = samples.len() as u32 * 4;
+        let mut bytes = b"RIFF".to_vec();
+        bytes.extend_from_slice(&(36 + data_size).to_le_bytes());
+        bytes.extend_from_slice(b"WAVEfmt \x10\x00\x00\x00\x01\x00\x01\x00");
+        bytes.extend_from_slice(&8000u32.to_le_bytes());
+        bytes.extend_from_slice(&32000u32.to_le_bytes());
+        bytes.extend_from_slice(&4u16.to_le_bytes());
+        bytes.extend_from_slice(&bits_per_sample.to_le_bytes());
+        bytes.extend_from_slice(b"data");
+        bytes.extend_from_slice(&data_size.to_le_bytes());
+        for sample in samples {
+            bytes.extend_from_slice(&sample.to_le_bytes());
+        }
+        bytes
+    }
+
+    #[test]
+    fn narrowed_to_16_bits() {
+        let samples = [0, 1 << 16, i32::MAX, i32::MIN, -65536];
+        let wav = WAV::from(&wav_bytes(&samples, 32)[..]).unwrap();
+        assert_eq!(vec![vec![0, 1, i16::MAX, i16::MIN, -1]], wav.channels)
+    }
+
+    #[test]
+    fn headers_describe_16_bits() {
+        let wav = WAV::from(&wav_bytes(&[0; 5], 32)[..]).unwrap();
+        assert_eq!(16, wav.fmt_header.bits_per_sample);
+        assert_eq!(10, wav.data_header.size);
+        assert_eq!(8000, wav.fmt_header.sample_rate);
+        assert_eq!(5, wav.n_samples().unwrap())
+    }
+
+    #[test]
+    fn to_16bit_keeps_samples() {
+        let wav = WAV::from(&wav_bytes(&[1 << 16, -(3 << 16)], 32)[..]).unwrap();
+        let channels = wav.channels.clone();
+        assert_eq!(channels, wav.to_16bit().unwrap().channels)
+    }
+
+    #[test]
+    fn other_widths_rejected() {
+        assert!(WAV::from(&wav_bytes(&[0; 5], 24)[..]).is_err())
+    }
+
+    #[test]
+    fn keeps_every_bit() {
+        let samples = vec![1, -(3 << 16) + 7, i32::MAX];
+        let wav = WAV::from(&wav_bytes(&samples, 32)[..]).unwrap();
+        assert_eq!(Some(vec![samples]), wav.to_16bit().unwrap().wide_channels);
+        let demo = WAV::from(&include_bytes!("../demo.wav")[..]).unwrap();
+        assert_eq!(None, demo.wide_channels);
+    }
+
+    #[test]
+    fn validated_before_the_headers_are_rebuilt() {
+        let mut bytes = wav_bytes(&[0; 5], 32);
+        // a byte rate of 16000 rather than the 32000 of 4 byte frames at 8 kHz
+        bytes[28..32].copy_from_slice(&16000u32.to_le_bytes());
+        let (wav, warnings) = WAV::from_chunks(WavChunkReader::new(&bytes[..])).unwrap();
+        assert_eq!(
+            vec![ValidationWarning::ByteRate {
+                expected: 32000,
+                found: 16000
+            }],
+            warnings
+        );
+        // the rebuilt 16 bit headers agree with each other
+        assert!(WavValidation::check(&wav).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_wav_chunk_reader {
+    use super::{WavChunk, WavChunkReader};
+
+    const DEMO: &[u8] = include_bytes!("../demo.wav");
+
+    fn ids(bytes: &[u8]) -> Vec<String> {
+        WavChunkReader::new(bytes)
+            .map(|chunk| match chunk.unwrap() {
+                WavChunk::Riff(_) => "RIFF".to_string(),
+                WavChunk::Fmt(_) => "fmt ".to_string(),
+                WavChunk::Data(_) => "data".to_string(),
+                WavChunk::Unknown { id, .. } => String::from_utf8(id.to_vec()).unwrap(),
+            })
+            .collect()
+    }
+
+    #[test]
+    fn in_file_order() {
+        assert_eq!(vec!["RIFF", "fmt ", "data"], ids(DEMO));
+    }
+
+    #[test]
+    fn unknown_chunks() {
+        let mut bytes = DEMO.to_vec();
+        // after the data, an odd sized chunk followed by another to check the padding byte
+        bytes.extend_from_slice(b"smpl\x03\x00\x00\x00abc\x00");
+        bytes.extend_from_slice(b"fact\x01\x00\x00\x00z\x00");
+        assert_eq!(vec!["RIFF", "fmt ", "data", "smpl", "fact"], ids(&bytes));
+
+        let last = WavChunkReader::new(&bytes[..]).last().unwrap().unwrap();
+        match last {
+            WavChunk::Unknown { id, data } => assert_eq!((*b"fact", vec![b'z']), (id, data)),
+            _ => panic!("expected the fact chunk"),
+        }
+    }
+
+    #[test]
+    fn skipping_unknown_chunks() {
+        let mut bytes = DEMO[..36].to_vec();
+        bytes.extend_from_slice(b"smpl\x03\x00\x00\x00abc\x00");
+        bytes.extend_from_slice(b"LIST\x00\x00\x00\x00");
+        bytes.extend_from_slice(b"fact\x01\x00\x00\x00z\x00");
+        bytes.extend_from_slice(&DEMO[36..]);
+        let ids: Vec<[u8; 4]> = WavChunkReader::new(&bytes[..])
+            .skip_unknown()
+            .filter_map(|chunk| match chunk.unwrap() {
+                WavChunk::Unknown { id, .. } => Some(id),
+                _ => None,
+            })
+            .collect();
+        assert_eq!(vec![*b"LIST"], ids);
+    }
+
+    #[test]
+    fn lazy() {
+        // only the first two chunks are read, the rest of the file is never looked at
+        let mut bytes = DEMO[..36].to_vec();
+        bytes.extend_from_slice(b"garbage");
+        assert_eq!(2, WavChunkReader::new(&bytes[..]).take(2).count());
+    }
+
+    #[test]
+    fn stops_after_an_error() {
+        let mut chunks = WavChunkReader::new(&DEMO[..8]);
+        assert!(chunks.next().unwrap().is_err());
+        assert!(chunks.next().is_none());
+    }
+}
+
+#[cfg(test)]
+mod test_wav_writer {
+    use super::{WavWriter, WAV};
+    use std::io::Cursor;
+
+    const DEMO: &[u8] = include_bytes!("../demo.wav");
+
+    #[test]
+    fn matches_demo_file() {
+        let wav = WAV::from(DEMO).unwrap();
+        let mut bytes = Vec::new();
+        let mut writer = WavWriter::new(
+            Cursor::new(&mut bytes),
+            wav.fmt_header.sample_rate,
+            wav.fmt_header.nchannels,
+            16,
+        )
+        .unwrap();
+        // in blocks, as a recording would arrive
+        let interleaved: Vec<i16> = (0..wav.channels[0].len())
+            .flat_map(|i| wav.channels.iter().map(move |channel| channel[i]))
+            .collect();
+        for block in interleaved.chunks(1000) {
+            writer.write_samples(block).unwrap();
+        }
+        writer.finalize().unwrap();
+        assert_eq!(DEMO, &bytes[..])
+    }
+
+    #[test]
+    fn rejects_24_bit() {
+        assert!(WavWriter::new(Cursor::new(Vec::new()), 44100, 1, 24).is_err())
+    }
+
+    #[test]
+    fn rejects_oversized_headers() {
+        assert!(WavWriter::new(Cursor::new(Vec::new()), 44100, 40000, 16).is_err());
+        assert!(WavWriter::new(Cursor::new(Vec::new()), u32::MAX, 2, 16).is_err());
+    }
+
+    #[test]
+    fn after_other_data() {
+        let mut bytes = b"prefix".to_vec();
+        let mut cursor = Cursor::new(&mut bytes);
+        cursor.set_position(6);
+        let mut writer = WavWriter::new(cursor, 8000, 1, 16).unwrap();
+        writer.write_samples(&[1, -2, 3]).unwrap();
+        writer.finalize().unwrap();
+
+        assert_eq!(b"prefix", &bytes[..6]);
+        let wav = WAV::from(&bytes[6..]).unwrap();
+        assert_eq!(vec![vec![1, -2, 3]], wav.channels);
+        assert_eq!(42, wav.riff_header.file_size);
+    }
+}
+
 #[cfg(test)]
 mod there_and_back_again {
     use super::WAV;
@@ -240,4 +1370,30 @@ mod there_and_back_again {
 
         remove_file("src/tmp.wav").unwrap();
     }
+
+    #[test]
+    fn lossless_in_memory() {
+        let input = include_bytes!("../demo.wav");
+        let mut output = Vec::new();
+        WAV::from(&input[..])
+            .unwrap()
+            .write_to(&mut output)
+            .unwrap();
+        assert_eq!(&input[..], &output[..]);
+    }
+
+    #[test]
+    fn lossless_from_samples_write_read() {
+        let left: Vec<i16> = (0..1000).map(|x| (x * 31) as i16).collect();
+        let right: Vec<i16> = (0..1000).map(|x| -(x * 17) as i16).collect();
+        let wav = WAV::from_samples(vec![left.clone(), right.clone()], 8000).unwrap();
+        wav.write("src/tmp_from_samples.wav").unwrap();
+
+        let result = WAV::from_file("src/tmp_from_samples.wav").unwrap();
+        remove_file("src/tmp_from_samples.wav").unwrap();
+
+        assert_eq!(result.fmt_header.sample_rate, 8000);
+        assert_eq!(result.fmt_header.byte_rate, 32000);
+        assert_eq!(result.channels, vec![left, right]);
+    }
 }
@@ -1,7 +1,16 @@
-#![feature(portable_simd)]
+#![cfg_attr(feature = "nightly_simd", feature(portable_simd))]
 
+pub mod analysis;
+pub mod builder;
+pub mod error;
 pub mod io;
 pub mod messages;
+#[cfg(feature = "server")]
+pub mod server;
+#[cfg(test)]
+pub(crate) mod test_signals;
 pub mod transform;
 pub mod units;
 pub mod widgets;
+
+pub use builder::{SpectrogramBuilder, SpectrogramData};
@@ -1,37 +1,400 @@
 use std::borrow::Cow;
+use std::convert::TryFrom;
+use std::env;
+use std::fs;
+use std::io::{self, Read};
+use std::path::{Path, PathBuf};
+use std::process;
+#[cfg(feature = "microphone")]
+use std::time::Duration;
+use std::time::Instant;
 
 use iced::{
-    button, executor, pick_list, slider, Align, Application, Button, Clipboard, Column, Command,
-    Container, Element, Length, PickList, Radio, Row, Settings, Slider, Text,
+    button, executor, pick_list, slider, text_input, Align, Application, Button, Checkbox,
+    Clipboard, Color, Column, Command, Container, Element, Length, PickList, Radio, Row, Settings,
+    Slider, Text, TextInput,
 };
 
 use iced_native::subscription::Subscription;
-use rfd::{FileDialog, MessageButtons, MessageDialog};
+use rfd::{FileDialog, MessageButtons, MessageDialog, MessageLevel};
 
+use spectrogram::analysis::annotation::{self, Annotation};
+use spectrogram::analysis::cepstrum::real_cepstrum;
+use spectrogram::analysis::equal_loudness::PHON_LEVELS;
+use spectrogram::analysis::loudness::integrated_lufs;
+#[cfg(feature = "microphone")]
+use spectrogram::analysis::pitch::detect_note;
+use spectrogram::analysis::tempo::{estimate_tempo, TempoEstimate};
+use spectrogram::error::SpectrogramError;
+#[cfg(feature = "microphone")]
+use spectrogram::io::microphone::MicrophoneSource;
+use spectrogram::io::raw::{read_raw, Endianness, BIT_DEPTHS};
+use spectrogram::io::validation::ValidationWarning;
 use spectrogram::io::wav::WAV;
 use spectrogram::messages::{cursor_moved_filter, Message};
-use spectrogram::units::{Mapping, Scale, Unit};
-use spectrogram::widgets::axis::{Axis, Orientation};
-use spectrogram::widgets::grid::Grid;
+#[cfg(feature = "server")]
+use spectrogram::server::{SpectrogramServer, DEFAULT_PORT};
+use spectrogram::transform::timestretch::time_stretch;
+use spectrogram::transform::window::WindowFunction;
+use spectrogram::transform::{fourier_transform, Backend};
+use spectrogram::units::{format_unit, midi_to_note, Mapping, Scale, ScaleRange, Unit};
+use spectrogram::widgets::average_spectrum::AverageSpectrum;
+use spectrogram::widgets::axis::{cell_tick_count, min_thickness, Axis, Orientation};
+use spectrogram::widgets::cepstrum::CepstrumWidget;
+use spectrogram::widgets::colormap::Colormap;
+use spectrogram::widgets::colormap_picker::ColormapPicker;
+use spectrogram::widgets::goniometer::Goniometer;
+use spectrogram::widgets::grid::{DisplayMode, Grid, Normalize, WINDOW_SIZES};
+use spectrogram::widgets::histogram::Histogram;
+use spectrogram::widgets::length;
+use spectrogram::widgets::stereo_width::StereoWidthMeter;
+use spectrogram::widgets::text_overlay::TextOverlay;
+use spectrogram::widgets::waveform::Waveform;
+
+// mistakes on the command line are reported without a panic's backtrace, eg.
+// `--sample-rate fast`
+fn usage_error(message: &str) -> ! {
+    eprintln!("error: {}", message);
+    process::exit(2)
+}
+
+// `--input -` reads mono 16 bit little endian PCM from stdin
+// `--sample-rate <hz>` sets its sample rate, defaulting to 44100
+fn read_stdin_samples() -> Option<(Vec<i16>, u32)> {
+    let args: Vec<String> = env::args().collect();
+    let input = args.iter().position(|arg| arg == "--input")?;
+    if args.get(input + 1).map(|arg| arg.as_str()) != Some("-") {
+        return None;
+    }
+    let sample_rate = args
+        .iter()
+        .position(|arg| arg == "--sample-rate")
+        .and_then(|i| args.get(i + 1))
+        .map(|rate| match rate.parse() {
+            Ok(rate) if rate > 0 => rate,
+            _ => usage_error("--sample-rate must be a positive whole number of Hz"),
+        })
+        .unwrap_or(44100);
+
+    let mut bytes = Vec::new();
+    if let Err(e) = io::stdin().read_to_end(&mut bytes) {
+        usage_error(&format!("unable to read stdin: {}", e));
+    }
+    let samples: Vec<i16> = bytes
+        .chunks_exact(2)
+        .map(|sample| i16::from_le_bytes([sample[0], sample[1]]))
+        .collect();
+    if samples.is_empty() {
+        usage_error("no samples were read from stdin");
+    }
+    Some((samples, sample_rate))
+}
+
+// --grid-size 800x400 fixes the grid at that many pixels, eg. on a kiosk display
+fn read_grid_size() -> Option<(u16, u16)> {
+    let args: Vec<String> = env::args().collect();
+    let size = args.iter().position(|arg| arg == "--grid-size")?;
+    let size = args
+        .get(size + 1)
+        .and_then(|size| size.split_once('x'))
+        .and_then(|(width, height)| Some((width.parse().ok()?, height.parse().ok()?)));
+    match size {
+        Some(size) => Some(size),
+        None => usage_error("--grid-size must be a width and height in pixels, eg. 800x400"),
+    }
+}
+
+// `--serve` runs the HTTP server in place of the gui, `--port <n>` defaulting to 7777
+#[cfg(feature = "server")]
+fn read_serve_port() -> Option<u16> {
+    let args: Vec<String> = env::args().collect();
+    args.iter().position(|arg| arg == "--serve")?;
+    let port = args
+        .iter()
+        .position(|arg| arg == "--port")
+        .and_then(|i| args.get(i + 1))
+        .map(|port| match port.parse() {
+            Ok(port) => port,
+            _ => usage_error("--port must be an integer from 0 to 65535"),
+        })
+        .unwrap_or(DEFAULT_PORT);
+    Some(port)
+}
+
+// length of the window shown while listening to the microphone
+#[cfg(feature = "microphone")]
+const LIVE_SECONDS: u32 = 2;
+// samples before the live window the waveform searches for a rising zero crossing to
+// trigger at
+#[cfg(feature = "microphone")]
+const TRIGGER_SEARCH: usize = 2048;
+// quieter peaks, in sample units, are not reported as notes
+#[cfg(feature = "microphone")]
+const NOTE_MIN_AMPLITUDE: f64 = 300.0;
+// a detected note stays on screen this long after it was last detected
+#[cfg(feature = "microphone")]
+const NOTE_HOLD: Duration = Duration::from_millis(200);
+
+// the resolution slider goes up to one column per this many samples, or MAX_WIDTH columns
+const MIN_WINDOW_SIZE: usize = 64;
+const MAX_WIDTH: u32 = 4096;
+
+fn max_width(n_samples: usize) -> u32 {
+    ((n_samples / MIN_WINDOW_SIZE) as u32).clamp(1, MAX_WIDTH)
+}
+
+// the axes and the grid share the window in these portions
+const GRID_FILL_PROPORTION: u16 = 20;
+// iced_audio's default size for tick labels
+const AXIS_TEXT_SIZE: u16 = 12;
+// iced's default window size, which the axis portions are sized against
+const WINDOW_SIZE: (f32, f32) = (1024.0, 768.0);
+
+// portion next to the grid's which leaves an axis at least thickness wide out of extent
+fn axis_fill_proportion(thickness: f32, extent: f32) -> u16 {
+    (thickness * GRID_FILL_PROPORTION as f32 / (extent - thickness)).ceil() as u16
+}
+
+fn show_error(title: &str, error: SpectrogramError) {
+    MessageDialog::new()
+        .set_title(title)
+        .set_description(&format!("Application error message:\n{}", error))
+        .set_buttons(MessageButtons::OkCancel)
+        .show();
+}
+
+// the file still loaded, so there is only the one button to dismiss them
+fn show_warnings(title: &str, warnings: &[ValidationWarning]) {
+    if warnings.is_empty() {
+        return;
+    }
+    let description: Vec<String> = warnings.iter().map(|w| format!("- {}", w)).collect();
+    MessageDialog::new()
+        .set_level(MessageLevel::Warning)
+        .set_title(title)
+        .set_description(&format!(
+            "The file may be corrupt:\n{}",
+            description.join("\n")
+        ))
+        .set_buttons(MessageButtons::Ok)
+        .show();
+}
+
+// choices offered for headerless files, which have nothing to read them from
+const RAW_SAMPLE_RATES: [u32; 8] = [8000, 11025, 16000, 22050, 32000, 44100, 48000, 96000];
+const RAW_CHANNELS: [u16; 8] = [1, 2, 3, 4, 5, 6, 7, 8];
+
+// format of a picked raw PCM file, shown below the grid until it is loaded or cancelled
+struct RawPcmDialog {
+    file: PathBuf,
+    sample_rate: u32,
+    channels: u16,
+    bits_per_sample: u16,
+    endianness: Endianness,
+    sample_rate_pick_list: pick_list::State<u32>,
+    channels_pick_list: pick_list::State<u16>,
+    bits_pick_list: pick_list::State<u16>,
+    load_button: button::State,
+    cancel_button: button::State,
+}
+
+impl RawPcmDialog {
+    fn new(file: PathBuf) -> RawPcmDialog {
+        RawPcmDialog {
+            file,
+            sample_rate: 44100,
+            channels: 1,
+            bits_per_sample: 16,
+            endianness: Endianness::Little,
+            sample_rate_pick_list: pick_list::State::default(),
+            channels_pick_list: pick_list::State::default(),
+            bits_pick_list: pick_list::State::default(),
+            load_button: button::State::new(),
+            cancel_button: button::State::new(),
+        }
+    }
+
+    fn load(&self) -> Result<WAV, SpectrogramError> {
+        let data = fs::read(&self.file)?;
+        read_raw(
+            &data,
+            self.sample_rate,
+            self.channels,
+            self.bits_per_sample,
+            self.endianness,
+        )
+    }
+
+    fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let endianness_controls = Column::new()
+            .spacing(1)
+            .push(
+                Radio::new(
+                    Endianness::Little,
+                    "Little endian",
+                    Some(self.endianness),
+                    Message::RawPcmEndiannessChanged,
+                )
+                .size(20)
+                .spacing(5),
+            )
+            .push(
+                Radio::new(
+                    Endianness::Big,
+                    "Big endian",
+                    Some(self.endianness),
+                    Message::RawPcmEndiannessChanged,
+                )
+                .size(20)
+                .spacing(5),
+            );
+
+        Row::new()
+            .align_items(Align::Center)
+            .spacing(20)
+            .push(Text::new(format!("Raw PCM: {}", self.file.display())))
+            .push(Text::new("Sample rate:"))
+            .push(PickList::new(
+                &mut self.sample_rate_pick_list,
+                Cow::Borrowed(&RAW_SAMPLE_RATES[..]),
+                Some(self.sample_rate),
+                Message::RawPcmSampleRateChanged,
+            ))
+            .push(Text::new("Channels:"))
+            .push(PickList::new(
+                &mut self.channels_pick_list,
+                Cow::Borrowed(&RAW_CHANNELS[..]),
+                Some(self.channels),
+                Message::RawPcmChannelsChanged,
+            ))
+            .push(Text::new("Bits:"))
+            .push(PickList::new(
+                &mut self.bits_pick_list,
+                Cow::Borrowed(&BIT_DEPTHS[..]),
+                Some(self.bits_per_sample),
+                Message::RawPcmBitsChanged,
+            ))
+            .push(endianness_controls)
+            .push(
+                Button::new(&mut self.load_button, Text::new("Load"))
+                    .on_press(Message::RawPcmLoadPressed),
+            )
+            .push(
+                Button::new(&mut self.cancel_button, Text::new("Cancel"))
+                    .on_press(Message::RawPcmCancelled),
+            )
+            .into()
+    }
+}
+
+// common problem and reference frequencies in Hz, mains hum and its first harmonic in
+// both 50 and 60 Hz countries, concert A and 1 kHz
+const HIGHLIGHT_PRESETS: [u32; 6] = [50, 60, 100, 120, 440, 1000];
+// highlights take the next of these colors, wrapping around
+const HIGHLIGHT_COLORS: [Color; 4] = [
+    Color::from_rgb(1.0, 0.2, 0.2),
+    Color::from_rgb(0.2, 1.0, 0.2),
+    Color::from_rgb(0.3, 0.6, 1.0),
+    Color::from_rgb(1.0, 0.9, 0.2),
+];
+
+// samples from the middle of the visible time range which the cepstrum is calculated from
+const CEPSTRUM_WINDOW: usize = 4096;
 
 fn main() -> iced::Result {
-    Spectrogram::run(Settings::default())
+    #[cfg(feature = "server")]
+    if let Some(port) = read_serve_port() {
+        let server = match SpectrogramServer::bind(port) {
+            Ok(server) => server,
+            Err(e) => {
+                eprintln!("error: unable to listen on port {}: {}", port, e);
+                process::exit(1)
+            }
+        };
+        println!("serving spectrograms on http://localhost:{}", port);
+        if let Err(e) = server.serve() {
+            eprintln!("error: stopped serving: {}", e);
+            process::exit(1)
+        }
+        return Ok(());
+    }
+    Spectrogram::run(Settings::with_flags(read_stdin_samples()))
 }
 
 struct Spectrogram {
     wav: WAV,
+    // compared against wav while set, same sample rate and length
+    ref_wav: Option<WAV>,
     n_samples: usize,
+    // start and end sample of the active channel which the grid is calculated from
+    time_range: (usize, usize),
+    loudness: f64,
+    tempo: TempoEstimate,
     samples: Vec<i16>,
     // TODO resolution to u32?
     resolution: (u32, u32),
+    // upper end of the resolution slider, follows the length of the file
+    max_width: u32,
     slider: slider::State,
+    overlap_slider: slider::State,
+    // samples are time stretched by this factor before the grid is calculated from them
+    stretch_factor: f32,
+    stretch_slider: slider::State,
     grid: Grid,
+    average_spectrum: AverageSpectrum,
+    show_average_spectrum: bool,
+    histogram: Histogram,
+    show_histogram: bool,
+    waveform: Waveform,
+    show_waveform: bool,
+    // proportion of the largest spectral flux a column must exceed to be marked as an onset
+    onset_threshold: f32,
+    onset_slider: slider::State,
+    goniometer: Goniometer,
+    stereo_width: StereoWidthMeter,
+    show_goniometer: bool,
+    cepstrum: CepstrumWidget,
+    show_cepstrum: bool,
     x_axis: Axis,
     y_axis: Axis,
+    // width of the spacer under the y axis, in the same portions as the axes and grid
+    // matches the y axis so the x axis lines up with the grid
+    axis_padding: u16,
+    x_axis_fill_proportion: u16,
+    // (width, height) of the grid in pixels, the axes keep to its edges, None to follow
+    // the size of the window
+    grid_size: Option<(u16, u16)>,
     dynamic_axes: bool,
+    human_readable_axes: bool,
     file_button: button::State,
+    raw_pcm_button: button::State,
+    // Some while the format of a raw PCM file is being chosen
+    raw_pcm: Option<RawPcmDialog>,
+    reference_button: button::State,
+    export_button: button::State,
+    export_channels_button: button::State,
+    flip_button: button::State,
     active_channel: usize,
     active_channel_pick_list: pick_list::State<usize>,
+    window_size_pick_list: pick_list::State<usize>,
+    phon_pick_list: pick_list::State<u32>,
+    colormap_picker: ColormapPicker,
+    highlight_pick_list: pick_list::State<u32>,
+    clear_highlights_button: button::State,
+    n_highlights: usize,
+    // a selected region waiting for its label
+    pending_annotation: Option<Annotation>,
+    annotation_label_input: text_input::State,
+    save_annotations_button: button::State,
+    text_overlay: TextOverlay,
+    // midi note, deviation in cents and when it was last detected
+    detected_note: Option<(u8, f32, Instant)>,
+    #[cfg(feature = "microphone")]
+    microphone: Option<MicrophoneSource>,
+    #[cfg(feature = "microphone")]
+    live_button: button::State,
+    #[cfg(feature = "microphone")]
+    peak_hold_button: button::State,
 }
 
 impl Spectrogram {
@@ -40,101 +403,444 @@ impl Spectrogram {
         match number_of_samples {
             Some(n) => n_samples = n,
             None => {
-                n_samples = wav.data_header.size as usize / (wav.fmt_header.nchannels as usize * 2)
+                n_samples = wav
+                    .n_samples()
+                    .expect("wav headers to describe a valid number of samples")
             }
         }
         let sample_rate = wav.fmt_header.sample_rate;
         let max_time = (1.0 / sample_rate as f32) * n_samples as f32;
         let max_frequency = (sample_rate / 2) as f32;
 
+        // wide enough for the tick labels at the default window size
+        let y_axis_fill_proportion = axis_fill_proportion(
+            min_thickness(&Orientation::Vertical, AXIS_TEXT_SIZE),
+            WINDOW_SIZE.0,
+        );
+        let x_axis_fill_proportion = axis_fill_proportion(
+            min_thickness(&Orientation::Horizontal, AXIS_TEXT_SIZE),
+            WINDOW_SIZE.1,
+        );
+
         let active_channel = 0;
-        let samples = wav.channels[active_channel]
-            .iter()
-            .map(|x| *x as i16)
-            .collect::<Vec<i16>>();
-
-        let height = ((n_samples as u32) / width) / 2;
-        let x_scale = Scale {
-            min: 0.0,
-            max: max_time,
-            unit: Unit::Second,
-            mapping: Mapping::Linear,
-        };
-        let y_scale = Scale {
-            min: 0.0,
-            max: max_frequency,
-            unit: Unit::Note,
-            mapping: Mapping::Log10,
-        };
+        let samples = wav
+            .channel(active_channel)
+            .expect("wav to have at least one channel")
+            .to_vec();
+
+        let width = width.min(max_width(n_samples));
+        let x_scale = Scale::default()
+            .with_range(ScaleRange::new(0.0, max_time).expect("wav to contain samples"))
+            .with_unit(Unit::Second);
+        let y_scale = Scale::default()
+            .with_range(ScaleRange::new(0.0, max_frequency).expect("nonzero sample rate"))
+            .with_unit(Unit::Note)
+            .with_mapping(Mapping::Log10);
+
+        let loudness = integrated_lufs(&wav.channels, sample_rate);
 
         let mut spectrogram = Spectrogram {
             wav,
+            ref_wav: None,
             n_samples,
+            time_range: (0, n_samples),
+            loudness,
+            tempo: estimate_tempo(&[], 1.0),
             samples,
-            resolution: (width, height),
+            // the height follows from the samples, set by update_resolution below
+            resolution: (width, 0),
+            max_width: max_width(n_samples),
             slider: slider::State::new(),
-            grid: Grid::new(width, height, 20, x_scale.clone(), y_scale.clone()),
-            x_axis: Axis::new(Orientation::Horizontal, x_scale, 16, 20),
-            y_axis: Axis::new(Orientation::Vertical, y_scale, 16, 1),
+            overlap_slider: slider::State::new(),
+            stretch_factor: 1.0,
+            stretch_slider: slider::State::new(),
+            grid: Grid::new(
+                width,
+                sample_rate,
+                GRID_FILL_PROPORTION,
+                x_scale.clone(),
+                y_scale.clone(),
+                Backend::default(),
+            ),
+            average_spectrum: AverageSpectrum::new(&[], sample_rate, y_scale.clone(), 4),
+            show_average_spectrum: false,
+            histogram: Histogram::new(&[], 4),
+            show_histogram: false,
+            waveform: Waveform::new(&[], 4),
+            show_waveform: false,
+            onset_threshold: 0.5,
+            onset_slider: slider::State::new(),
+            goniometer: Goniometer::new(&[], &[], 4),
+            stereo_width: StereoWidthMeter::new(),
+            show_goniometer: false,
+            cepstrum: CepstrumWidget::new(&[], sample_rate, 4),
+            show_cepstrum: false,
+            x_axis: Axis::new(
+                Orientation::Horizontal,
+                x_scale,
+                16,
+                4,
+                GRID_FILL_PROPORTION,
+            )
+            .with_label("Time"),
+            y_axis: Axis::new(
+                Orientation::Vertical,
+                y_scale,
+                16,
+                4,
+                y_axis_fill_proportion,
+            )
+            .with_label("Frequency"),
+            axis_padding: y_axis_fill_proportion,
+            x_axis_fill_proportion,
+            grid_size: read_grid_size(),
             dynamic_axes: false,
+            human_readable_axes: false,
             file_button: button::State::new(),
+            raw_pcm_button: button::State::new(),
+            raw_pcm: None,
+            reference_button: button::State::new(),
+            export_button: button::State::new(),
+            export_channels_button: button::State::new(),
+            flip_button: button::State::new(),
             active_channel,
             active_channel_pick_list: pick_list::State::default(),
+            window_size_pick_list: pick_list::State::default(),
+            phon_pick_list: pick_list::State::default(),
+            colormap_picker: ColormapPicker::new(&Colormap::default()),
+            highlight_pick_list: pick_list::State::default(),
+            clear_highlights_button: button::State::new(),
+            n_highlights: 0,
+            pending_annotation: None,
+            annotation_label_input: text_input::State::new(),
+            save_annotations_button: button::State::new(),
+            text_overlay: TextOverlay::new(),
+            detected_note: None,
+            #[cfg(feature = "microphone")]
+            microphone: None,
+            #[cfg(feature = "microphone")]
+            live_button: button::State::new(),
+            #[cfg(feature = "microphone")]
+            peak_hold_button: button::State::new(),
         };
+        spectrogram.update_resolution(width);
         spectrogram
-            .grid
-            .update_frequencies(spectrogram.resolution, &spectrogram.samples);
-        spectrogram
     }
 
-    fn update_wav(&mut self, wav: WAV) {
+    fn update_frequencies(&mut self) {
+        self.grid.set_samples(&self.samples);
+        self.update_analysis();
+    }
+
+    // everything derived from the samples and the grid's frequencies
+    fn update_analysis(&mut self) {
+        self.histogram.update_samples(&self.samples);
+        self.waveform.update_samples(&self.samples);
+        self.waveform.update_envelope(&self.grid.envelope);
+        self.waveform
+            .update_onsets(&self.grid.onsets(self.onset_threshold as f64));
+        self.update_cepstrum();
+        self.update_goniometer();
+        self.average_spectrum
+            .update_spectrum(&self.grid.average_spectrum());
+        self.tempo = estimate_tempo(&self.grid.onset_function(), self.grid.hop_rate());
+    }
+
+    // the first two channels over the visible time range, a mono file is drawn as if both
+    // channels were the same. the stereo width meter is shown with it and follows the same samples
+    fn update_goniometer(&mut self) {
+        if !self.show_goniometer {
+            return;
+        }
+        let (start, end) = self.time_range;
+        if let Ok(left) = self.wav.channel(0) {
+            let right = self.wav.channel(1).unwrap_or(left);
+            self.goniometer
+                .update_samples(&left[start..end], &right[start..end]);
+            self.stereo_width
+                .update_samples(&left[start..end], &right[start..end]);
+        }
+    }
+
+    // only calculated while shown, the naive backends are slow at this size
+    fn update_cepstrum(&mut self) {
+        if !self.show_cepstrum {
+            return;
+        }
+        let length = self.samples.len().min(CEPSTRUM_WINDOW) / 2 * 2;
+        let start = (self.samples.len() - length) / 2;
+        let spectrum = fourier_transform(&self.samples[start..start + length], self.grid.backend);
+        self.cepstrum.update_cepstrum(&real_cepstrum(&spectrum));
+    }
+
+    fn from_samples(
+        samples: Vec<i16>,
+        sample_rate: u32,
+        width: u32,
+    ) -> Result<Spectrogram, SpectrogramError> {
+        let wav = WAV::from_samples(vec![samples], sample_rate)?;
+        Ok(Spectrogram::new(wav, None, width))
+    }
+
+    // everything which can fail is checked before anything changes, so a file which can not
+    // be shown, eg. one with an empty data chunk, leaves the current one as it was
+    fn update_wav(&mut self, wav: WAV) -> Result<(), SpectrogramError> {
         let sample_rate = wav.fmt_header.sample_rate;
+        let n_samples = wav.n_samples()?;
+        let max_time = (1.0 / sample_rate as f32) * n_samples as f32;
+        let time_range = ScaleRange::new(0.0, max_time)?;
+        let frequency_range = ScaleRange::new(0.0, (sample_rate / 2) as f32)?;
+        // the first channel is shown until another is picked
+        let first_channel = wav.channel(0)?;
+        if first_channel.len() < n_samples {
+            return Err(SpectrogramError::ShapeMismatch {
+                expected: n_samples,
+                found: first_channel.len(),
+            });
+        }
 
-        self.n_samples = wav.data_header.size as usize / (wav.fmt_header.nchannels as usize * 2);
+        self.n_samples = n_samples;
+        self.loudness = integrated_lufs(&wav.channels, sample_rate);
         self.wav = wav;
-        self.x_axis.scale.max = (1.0 / sample_rate as f32) * self.n_samples as f32;
-        self.y_axis.scale.max = (sample_rate / 2) as f32;
-        self.resolution.1 = ((self.n_samples as u32) / self.resolution.0) / 2;
+        self.grid.sample_rate = sample_rate;
+        self.average_spectrum.sample_rate = sample_rate;
+        self.cepstrum.sample_rate = sample_rate;
+        self.x_axis
+            .set_scale(self.x_axis.scale().clone().with_range(time_range));
+        self.grid.x.range = time_range;
+        self.time_range = (0, self.n_samples);
+        self.y_axis
+            .set_scale(self.y_axis.scale().clone().with_range(frequency_range));
+        self.grid.y.range = frequency_range;
+        self.average_spectrum.y.range = frequency_range;
+        self.max_width = max_width(self.n_samples);
+        self.resolution.0 = self.resolution.0.min(self.max_width);
 
         self.active_channel = 0;
-        self.samples = self.wav.channels[0]
-            .iter()
-            .map(|x| *x as i16)
-            .collect::<Vec<i16>>();
-        self.grid.update_frequencies(self.resolution, &self.samples);
-        self.x_axis.cache.clear();
-        self.y_axis.cache.clear();
+        // a new file rarely lines up with the old reference
+        if let Some(Err(_)) = self.ref_wav.as_ref().map(|r| self.check_reference(r)) {
+            self.ref_wav = None;
+        }
+        self.update_samples()?;
+        // the width may have just been capped to the new file's max_width
+        self.update_resolution(self.resolution.0);
+        Ok(())
+    }
+
+    // starts from silence so the axes and grid are sized for the live window
+    #[cfg(feature = "microphone")]
+    fn start_live(&mut self) -> Result<(), SpectrogramError> {
+        let microphone = MicrophoneSource::new(LIVE_SECONDS)?;
+        // leaves room in the microphone buffer to search for a trigger
+        let n_samples = (microphone.sample_rate * LIVE_SECONDS) as usize - TRIGGER_SEARCH;
+        let silence = WAV::from_samples(vec![vec![0; n_samples]], microphone.sample_rate)?;
+        self.update_wav(silence)?;
+        self.microphone = Some(microphone);
+        Ok(())
+    }
+
+    #[cfg(feature = "microphone")]
+    fn update_live(&mut self) -> Command<Message> {
+        self.expire_note();
+        if let Some(microphone) = &self.microphone {
+            let n_samples = self.n_samples + TRIGGER_SEARCH;
+            let latest = microphone.latest_samples(n_samples);
+            // zero padded at the start until the microphone buffer has filled
+            let mut samples = vec![0; n_samples.saturating_sub(latest.len())];
+            samples.extend_from_slice(&latest);
+            self.samples = samples[TRIGGER_SEARCH..].to_vec();
+            self.update_frequencies();
+            self.waveform.update_triggered(&samples, TRIGGER_SEARCH);
+            self.average_spectrum.update_peak_hold();
+            let note = detect_note(
+                self.grid.latest_magnitudes(),
+                self.grid.sample_rate,
+                self.grid.window_size,
+                NOTE_MIN_AMPLITUDE,
+            );
+            if let Some((note, cents)) = note {
+                return Command::perform(async move { (note, cents) }, |(note, cents)| {
+                    Message::NoteDetected(note, cents)
+                });
+            }
+        }
+        Command::none()
+    }
+
+    fn update_note(&mut self, note: u8, cents: f32) {
+        self.detected_note = Some((note, cents, Instant::now()));
+        self.grid.note_label = Some(format!("{} {:+.0} cents", midi_to_note(note), cents));
+    }
+
+    #[cfg(feature = "microphone")]
+    fn expire_note(&mut self) {
+        if let Some((_, _, detected_at)) = self.detected_note {
+            if detected_at.elapsed() > NOTE_HOLD {
+                self.detected_note = None;
+                self.grid.note_label = None;
+            }
+        }
+    }
+
+    fn channel_samples(&self) -> Result<Vec<i16>, SpectrogramError> {
+        let (start, end) = self.time_range;
+        Ok(self.wav.channel(self.active_channel)?[start..end].to_vec())
+    }
+
+    // the same channel and time range of the reference, or its last channel if it has fewer
+    fn reference_samples(&self) -> Result<Option<Vec<i16>>, SpectrogramError> {
+        let (start, end) = self.time_range;
+        self.ref_wav
+            .as_ref()
+            .map(|reference| {
+                let channel = self.active_channel.min(reference.channels.len().max(1) - 1);
+                Ok(reference.channel(channel)?[start..end].to_vec())
+            })
+            .transpose()
+    }
+
+    // the axes stay in the time of the original recording while stretched
+    fn stretch(&self, samples: Vec<i16>) -> Vec<i16> {
+        if self.stretch_factor == 1.0 {
+            return samples;
+        }
+        time_stretch(
+            &samples,
+            self.grid.sample_rate,
+            self.stretch_factor,
+            WindowFunction::Hann,
+            self.grid.backend,
+        )
     }
 
-    fn update_channel(&mut self, channel: usize) {
+    fn update_samples(&mut self) -> Result<(), SpectrogramError> {
+        self.samples = self.stretch(self.channel_samples()?);
+        self.grid.reference = self.reference_samples()?.map(|r| self.stretch(r));
+        Ok(())
+    }
+
+    fn check_reference(&self, reference: &WAV) -> Result<(), SpectrogramError> {
+        let sample_rate = reference.fmt_header.sample_rate;
+        if sample_rate != self.grid.sample_rate {
+            return Err(SpectrogramError::ReferenceMismatch {
+                property: "sample rate",
+                expected: self.grid.sample_rate as u64,
+                found: sample_rate as u64,
+            });
+        }
+        let n_samples = reference.n_samples()?;
+        if n_samples != self.n_samples {
+            return Err(SpectrogramError::ReferenceMismatch {
+                property: "length",
+                expected: self.n_samples as u64,
+                found: n_samples as u64,
+            });
+        }
+        Ok(())
+    }
+
+    fn update_reference(&mut self, reference: Option<WAV>) -> Result<(), SpectrogramError> {
+        self.ref_wav = reference;
+        self.update_samples()?;
+        self.update_frequencies();
+        self.grid.clear_cache();
+        Ok(())
+    }
+
+    fn load_reference(&mut self, path: PathBuf) -> Result<(), SpectrogramError> {
+        let reference = WAV::from_file(&path.to_string_lossy())?;
+        self.check_reference(&reference)?;
+        self.update_reference(Some(reference))
+    }
+
+    fn update_channel(&mut self, channel: usize) -> Result<(), SpectrogramError> {
+        // checked before anything changes, so a bad channel leaves the current one in place
+        self.wav.channel(channel)?;
         self.active_channel = channel;
-        self.samples = self.wav.channels[channel]
-            .iter()
-            .map(|x| *x as i16)
-            .collect::<Vec<i16>>();
-        self.grid.update_frequencies(self.resolution, &self.samples);
-        self.grid.cache.clear();
+        self.update_samples()?;
+        self.update_frequencies();
+        self.grid.clear_cache();
+        Ok(())
+    }
+
+    // channel_{n}.png for each channel as the grid draws it, the active channel is restored
+    // afterwards even if one of them fails
+    fn export_all_channels(&mut self, directory: &Path) -> Result<Vec<PathBuf>, SpectrogramError> {
+        let active_channel = self.active_channel;
+        let mut written = vec![];
+        let mut result = Ok(());
+        for channel in 0..self.wav.channels.len() {
+            self.active_channel = channel;
+            result = self.update_samples().and_then(|_| {
+                self.grid.set_samples(&self.samples);
+                let path = directory.join(format!("channel_{}.png", channel));
+                fs::write(&path, self.grid.snapshot_png()?)?;
+                written.push(path);
+                Ok(())
+            });
+            if result.is_err() {
+                break;
+            }
+        }
+        self.active_channel = active_channel;
+        self.update_samples()?;
+        self.update_frequencies();
+        result.map(|_| written)
+    }
+
+    fn update_time_range(&mut self, start_sec: f32, end_sec: f32) -> Result<(), SpectrogramError> {
+        let sample_rate = self.grid.sample_rate as f32;
+        let end_sec = end_sec.min(self.n_samples as f32 / sample_rate);
+        let start = (start_sec * sample_rate) as usize;
+        let end = (end_sec * sample_rate) as usize;
+        // every column needs at least two samples
+        if start_sec < 0.0 || end < start + 2 * self.resolution.0 as usize {
+            return Ok(());
+        }
+        if let Ok(range) = ScaleRange::new(start_sec, end_sec) {
+            self.time_range = (start, end);
+            self.update_samples()?;
+            self.x_axis
+                .set_scale(self.x_axis.scale().clone().with_range(range));
+            self.grid.x.range = range;
+            self.update_resolution(self.resolution.0);
+        }
+        Ok(())
     }
 
     fn update_resolution(&mut self, width: u32) {
-        let height = ((self.n_samples as u32) / width) / 2;
-        self.resolution = (width, height)
+        let height = ((self.samples.len() as u32) / width) / 2;
+        self.resolution = (width, height);
+        self.grid.set_resolution(self.resolution, &self.samples);
+        self.update_analysis();
     }
 }
 
 impl Application for Spectrogram {
     type Executor = executor::Default;
     type Message = Message;
-    type Flags = ();
+    type Flags = Option<(Vec<i16>, u32)>;
 
-    fn new(_flags: ()) -> (Self, Command<Message>) {
-        let bytes = std::include_bytes!("demo.wav");
-        let wav = WAV::from(&bytes[..]).unwrap();
-        (Spectrogram::new(wav, None, 100), Command::none())
+    fn new(flags: Option<(Vec<i16>, u32)>) -> (Self, Command<Message>) {
+        match flags {
+            Some((samples, sample_rate)) => (
+                Spectrogram::from_samples(samples, sample_rate, 100)
+                    .unwrap_or_else(|e| usage_error(&e.to_string())),
+                Command::none(),
+            ),
+            None => {
+                let bytes = std::include_bytes!("demo.wav");
+                let wav = WAV::try_from(&bytes[..]).unwrap();
+                (Spectrogram::new(wav, None, 100), Command::none())
+            }
+        }
     }
 
     fn title(&self) -> String {
-        String::from("spectrogram")
+        match &self.wav.metadata.title {
+            Some(title) => format!("spectrogram - {}", title),
+            None => String::from("spectrogram"),
+        }
     }
 
     fn update(&mut self, message: Message, _clipboard: &mut Clipboard) -> Command<Self::Message> {
@@ -142,13 +848,12 @@ impl Application for Spectrogram {
             Message::CursorMoved(_point) => (),
             Message::SliderChanged(value) => {
                 self.update_resolution(value);
-                self.grid.update_frequencies(self.resolution, &self.samples);
                 if self.dynamic_axes {
-                    self.x_axis.tick_count = self.resolution.0 as usize + 1;
-                    self.y_axis.tick_count = self.resolution.1 as usize + 2;
+                    self.x_axis
+                        .set_tick_count(cell_tick_count(self.resolution.0 as usize));
+                    self.y_axis
+                        .set_tick_count(cell_tick_count(self.grid.n_bins()));
                 }
-                self.x_axis.cache.clear();
-                self.y_axis.cache.clear();
             }
             Message::FileButtonPressed => {
                 let file = FileDialog::new()
@@ -158,46 +863,297 @@ impl Application for Spectrogram {
                 match file {
                     Some(file) => {
                         let filename = file.to_str().expect("good filename");
-                        let wav = WAV::from_file(filename);
-                        match wav {
-                            Ok(wav) => self.update_wav(wav),
-                            Err(e) => {
-                                MessageDialog::new()
-                                    .set_title(&format!("Error loading: {}", filename))
-                                    .set_description(&format!("Application error message:\n{}", e))
-                                    .set_buttons(MessageButtons::OkCancel)
-                                    .show();
+                        let loaded = WAV::from_file_checked(filename)
+                            .and_then(|(wav, warnings)| self.update_wav(wav).map(|_| warnings));
+                        match loaded {
+                            Ok(warnings) => {
+                                show_warnings(&format!("Warning loading: {}", filename), &warnings)
                             }
+                            Err(e) => show_error(&format!("Error loading: {}", filename), e),
                         }
                     }
                     None => (),
                 }
             }
+            Message::RawPcmButtonPressed => {
+                if let Some(file) = FileDialog::new().pick_file() {
+                    self.raw_pcm = Some(RawPcmDialog::new(file));
+                }
+            }
+            Message::RawPcmSampleRateChanged(sample_rate) => {
+                if let Some(dialog) = &mut self.raw_pcm {
+                    dialog.sample_rate = sample_rate;
+                }
+            }
+            Message::RawPcmChannelsChanged(channels) => {
+                if let Some(dialog) = &mut self.raw_pcm {
+                    dialog.channels = channels;
+                }
+            }
+            Message::RawPcmBitsChanged(bits_per_sample) => {
+                if let Some(dialog) = &mut self.raw_pcm {
+                    dialog.bits_per_sample = bits_per_sample;
+                }
+            }
+            Message::RawPcmEndiannessChanged(endianness) => {
+                if let Some(dialog) = &mut self.raw_pcm {
+                    dialog.endianness = endianness;
+                }
+            }
+            Message::RawPcmLoadPressed => {
+                if let Some(dialog) = self.raw_pcm.take() {
+                    if let Err(e) = dialog.load().and_then(|wav| self.update_wav(wav)) {
+                        show_error(&format!("Error loading: {}", dialog.file.display()), e);
+                    }
+                }
+            }
+            Message::RawPcmCancelled => self.raw_pcm = None,
+            Message::ReferenceButtonPressed => {
+                if self.ref_wav.is_some() {
+                    if let Err(e) = self.update_reference(None) {
+                        show_error("Error clearing reference", e);
+                    }
+                } else if let Some(file) = FileDialog::new()
+                    .add_filter("WAV", &["wav", "WAV"])
+                    .pick_file()
+                {
+                    return Command::perform(async move { file }, Message::ReferenceFileLoaded);
+                }
+            }
+            Message::ReferenceFileLoaded(file) => {
+                if let Err(e) = self.load_reference(file.clone()) {
+                    show_error(&format!("Error loading reference: {}", file.display()), e);
+                }
+            }
+            Message::ExportButtonPressed => {
+                let file = FileDialog::new()
+                    .add_filter("NumPy", &["npy"])
+                    .set_file_name("spectrogram.npy")
+                    .save_file();
+                if let Some(file) = file {
+                    if let Err(e) = self.grid.to_npy(&file) {
+                        show_error(&format!("Error exporting: {}", file.display()), e);
+                    }
+                }
+            }
+            Message::ExportChannelsButtonPressed => {
+                if let Some(directory) = FileDialog::new().pick_folder() {
+                    if let Err(e) = self.export_all_channels(&directory) {
+                        show_error(&format!("Error exporting to: {}", directory.display()), e);
+                    }
+                }
+            }
             Message::YUnitChanged(unit) => {
                 self.grid.y.unit = unit;
-                self.y_axis.scale.unit = unit;
-                self.y_axis.cache.clear();
+                self.y_axis.set_unit(unit);
             }
             Message::YMappingChanged(mapping) => {
-                self.y_axis.scale.mapping = mapping;
-                self.y_axis.cache.clear();
+                self.y_axis.set_mapping(mapping);
                 self.grid.y.mapping = mapping;
-                self.grid.cache.clear();
+                self.grid.clear_cache();
+                self.average_spectrum.y.mapping = mapping;
+                self.average_spectrum.cache.clear();
+            }
+            Message::YAxisFlipped => {
+                self.y_axis.set_scale(self.y_axis.scale().reversed());
+                self.grid.y = self.grid.y.reversed();
+                self.grid.clear_cache();
+                self.average_spectrum.y = self.average_spectrum.y.reversed();
+                self.average_spectrum.cache.clear();
             }
             Message::ActiveChannelChanged(channel) => {
-                self.update_channel(channel);
+                if let Err(e) = self.update_channel(channel) {
+                    show_error("Error changing channel", e);
+                }
             }
             Message::DynamicAxesChanged(dynamic_axes) => {
                 self.dynamic_axes = dynamic_axes;
                 if self.dynamic_axes {
-                    self.x_axis.tick_count = self.resolution.0 as usize + 1;
-                    self.y_axis.tick_count = self.resolution.1 as usize + 2;
+                    self.x_axis
+                        .set_tick_count(cell_tick_count(self.resolution.0 as usize));
+                    self.y_axis
+                        .set_tick_count(cell_tick_count(self.grid.n_bins()));
                 } else {
-                    self.x_axis.tick_count = 16;
-                    self.y_axis.tick_count = 16;
+                    self.x_axis.set_tick_count(16);
+                    self.y_axis.set_tick_count(16);
+                }
+            }
+            Message::HumanReadableAxesToggled(human_readable_axes) => {
+                self.human_readable_axes = human_readable_axes;
+                self.x_axis.set_human_readable(human_readable_axes);
+                self.y_axis.set_human_readable(human_readable_axes);
+            }
+            Message::WindowSizeChanged(window_size) => {
+                self.grid.window_size = window_size;
+                self.update_frequencies();
+                if self.dynamic_axes {
+                    self.y_axis
+                        .set_tick_count(cell_tick_count(self.grid.n_bins()));
+                }
+            }
+            Message::FrequencyRangeChanged { min, max } => {
+                // the axis does not know the sample rate, so zooming out on it can overshoot
+                let nyquist = (self.grid.sample_rate / 2) as f32;
+                if let Ok(range) = self
+                    .grid
+                    .set_frequency_range(min.max(0.0), max.min(nyquist))
+                {
+                    self.y_axis
+                        .set_scale(self.y_axis.scale().clone().with_range(range));
+                    self.average_spectrum.y.range = range;
+                    self.average_spectrum.cache.clear();
                 }
-                self.y_axis.cache.clear();
-                self.x_axis.cache.clear();
+            }
+            Message::TimeRangeChanged { start_sec, end_sec } => {
+                if let Err(e) = self.update_time_range(start_sec, end_sec) {
+                    show_error("Error changing time range", e);
+                }
+            }
+            Message::StretchFactorChanged(stretch_factor) => {
+                self.stretch_factor = stretch_factor;
+                if let Err(e) = self.update_samples() {
+                    show_error("Error stretching", e);
+                }
+                self.update_resolution(self.resolution.0);
+                self.grid.clear_cache();
+            }
+            Message::OverlapChanged(overlap) => {
+                self.grid.overlap = overlap;
+                self.update_frequencies();
+            }
+            Message::PeakOverlayToggled(show_peak) => {
+                self.grid.show_peak = show_peak;
+                self.grid.clear_cache();
+            }
+            Message::AverageSpectrumToggled(show_average_spectrum) => {
+                self.show_average_spectrum = show_average_spectrum;
+            }
+            Message::PeakHoldReset => {
+                self.average_spectrum.reset_peak_hold();
+            }
+            Message::HistogramToggled(show_histogram) => {
+                self.show_histogram = show_histogram;
+            }
+            Message::WaveformToggled(show_waveform) => {
+                self.show_waveform = show_waveform;
+            }
+            Message::OnsetThresholdChanged(onset_threshold) => {
+                self.onset_threshold = onset_threshold;
+                self.waveform
+                    .update_onsets(&self.grid.onsets(onset_threshold as f64));
+            }
+            Message::GoniometerToggled(show_goniometer) => {
+                self.show_goniometer = show_goniometer;
+                self.update_goniometer();
+            }
+            Message::CepstrumToggled(show_cepstrum) => {
+                self.show_cepstrum = show_cepstrum;
+                self.update_cepstrum();
+            }
+            Message::SpectralGateToggled(gate) => {
+                self.grid.gate = gate;
+                self.grid.update_cleaned();
+            }
+            Message::DenoiseToggled(denoise) => {
+                self.grid.denoise = denoise;
+                self.grid.update_cleaned();
+            }
+            Message::HarmonicsToggled(show_harmonics) => {
+                self.grid.show_harmonics = show_harmonics;
+            }
+            Message::FrequencyHighlightAdded(hz) => {
+                let color = HIGHLIGHT_COLORS[self.n_highlights % HIGHLIGHT_COLORS.len()];
+                self.grid.highlight_frequency(hz, color);
+                self.n_highlights += 1;
+            }
+            Message::FrequencyHighlightCleared => {
+                self.grid.clear_highlights();
+                self.n_highlights = 0;
+            }
+            Message::AnnotatingToggled(annotating) => {
+                self.grid.annotating = annotating;
+            }
+            Message::AnnotationRegionSelected(annotation) => {
+                self.pending_annotation = Some(annotation);
+                self.annotation_label_input = text_input::State::focused();
+            }
+            Message::AnnotationLabelChanged(label) => {
+                if let Some(annotation) = &mut self.pending_annotation {
+                    annotation.label = label;
+                }
+            }
+            Message::AnnotationAdded(annotation) => {
+                self.grid.annotations.push(annotation);
+                self.pending_annotation = None;
+            }
+            Message::AnnotationsSaved => {
+                let file = FileDialog::new()
+                    .add_filter("JSON", &["json"])
+                    .set_file_name("annotations.json")
+                    .save_file();
+                if let Some(file) = file {
+                    let saved = annotation::to_json(&self.grid.annotations, &self.grid.labels)
+                        .and_then(|json| fs::write(&file, json).map_err(SpectrogramError::from));
+                    if let Err(e) = saved {
+                        show_error(&format!("Error saving: {}", file.display()), e);
+                    }
+                }
+            }
+            Message::TextLabelingToggled(labeling) => {
+                self.grid.labeling = labeling;
+            }
+            Message::TextLabelPlaced(label) => self.text_overlay.edit(label),
+            Message::TextLabelChanged(text) => self.text_overlay.set_text(text),
+            Message::TextLabelConfirmed => {
+                if let Some(label) = self.text_overlay.confirm() {
+                    self.grid.labels.push(label);
+                }
+            }
+            Message::TextLabelRemoved(i) => {
+                // delete is also pressed while typing a label, which should only edit its text
+                if !self.text_overlay.editing() && i < self.grid.labels.len() {
+                    self.grid.labels.remove(i);
+                }
+            }
+            Message::EqualLoudnessToggled(show_equal_loudness) => {
+                self.grid.show_equal_loudness = show_equal_loudness;
+            }
+            Message::PhonChanged(phon) => {
+                self.grid.phon = phon;
+            }
+            Message::ColormapPickerToggled => {
+                self.colormap_picker.open = !self.colormap_picker.open;
+            }
+            Message::ColormapChanged(name) => {
+                if let Some(colormap) = Colormap::from_name(name) {
+                    self.grid.colormap = colormap;
+                    self.grid.clear_cache();
+                }
+                self.colormap_picker.select(&self.grid.colormap);
+            }
+            #[cfg(feature = "microphone")]
+            Message::LiveButtonPressed => {
+                if self.microphone.take().is_none() {
+                    if let Err(e) = self.start_live() {
+                        show_error("Error starting microphone", e);
+                    }
+                } else {
+                    self.detected_note = None;
+                    self.grid.note_label = None;
+                }
+            }
+            #[cfg(feature = "microphone")]
+            Message::LiveTick => return self.update_live(),
+            #[cfg(not(feature = "microphone"))]
+            Message::LiveButtonPressed | Message::LiveTick => (),
+            Message::NoteDetected(note, cents) => self.update_note(note, cents),
+            Message::DisplayModeChanged(display_mode) => {
+                self.grid.display_mode = display_mode;
+                self.update_frequencies();
+            }
+            Message::NormalizeChanged(normalize) => {
+                self.grid.normalize = normalize;
+                self.update_frequencies();
             }
         };
 
@@ -205,31 +1161,266 @@ impl Application for Spectrogram {
     }
 
     fn subscription(&self) -> Subscription<Message> {
-        iced_native::subscription::events_with(cursor_moved_filter)
+        let cursor_moved = iced_native::subscription::events_with(cursor_moved_filter);
+        #[cfg(feature = "microphone")]
+        if self.microphone.is_some() {
+            let live_tick = iced::time::every(Duration::from_millis(50)).map(|_| Message::LiveTick);
+            return Subscription::batch(vec![cursor_moved, live_tick]);
+        }
+        cursor_moved
     }
 
     fn view(&mut self) -> Element<Message> {
         let slider = Slider::new(
             &mut self.slider,
-            1..=100,
+            1..=self.max_width,
             self.resolution.0,
             Message::SliderChanged,
         );
 
-        let y_unit = self.y_axis.scale.unit.clone();
-        let y_mapping = self.y_axis.scale.mapping.clone();
+        let overlap_slider = Slider::new(
+            &mut self.overlap_slider,
+            0.0..=0.95,
+            self.grid.overlap,
+            Message::OverlapChanged,
+        )
+        .step(0.05);
 
-        let row1 = Row::new()
-            .height(Length::FillPortion(20))
-            .push(self.y_axis.view())
-            .push(self.grid.view());
+        let stretch_slider = Slider::new(
+            &mut self.stretch_slider,
+            0.25..=4.0,
+            self.stretch_factor,
+            Message::StretchFactorChanged,
+        )
+        .step(0.25);
 
-        let spacer = Row::new().width(Length::FillPortion(1));
+        let onset_slider = Slider::new(
+            &mut self.onset_slider,
+            0.0..=1.0,
+            self.onset_threshold,
+            Message::OnsetThresholdChanged,
+        )
+        .step(0.05);
 
-        let row2 = Row::new()
-            .height(Length::FillPortion(2))
+        let peak_overlay_toggle =
+            Checkbox::new(self.grid.show_peak, "Peaks", Message::PeakOverlayToggled)
+                .size(20)
+                .spacing(5);
+
+        let gate_toggle = Checkbox::new(self.grid.gate, "Gate", Message::SpectralGateToggled)
+            .size(20)
+            .spacing(5);
+
+        let denoise_toggle = Checkbox::new(self.grid.denoise, "Denoise", Message::DenoiseToggled)
+            .size(20)
+            .spacing(5);
+
+        let harmonics_toggle = Checkbox::new(
+            self.grid.show_harmonics,
+            "Harmonics",
+            Message::HarmonicsToggled,
+        )
+        .size(20)
+        .spacing(5);
+
+        let average_spectrum_toggle = Checkbox::new(
+            self.show_average_spectrum,
+            "Average",
+            Message::AverageSpectrumToggled,
+        )
+        .size(20)
+        .spacing(5);
+
+        let waveform_toggle =
+            Checkbox::new(self.show_waveform, "Waveform", Message::WaveformToggled)
+                .size(20)
+                .spacing(5);
+
+        let histogram_toggle =
+            Checkbox::new(self.show_histogram, "Histogram", Message::HistogramToggled)
+                .size(20)
+                .spacing(5);
+
+        let goniometer_toggle = Checkbox::new(
+            self.show_goniometer,
+            "Goniometer",
+            Message::GoniometerToggled,
+        )
+        .size(20)
+        .spacing(5);
+
+        let cepstrum_toggle =
+            Checkbox::new(self.show_cepstrum, "Cepstrum", Message::CepstrumToggled)
+                .size(20)
+                .spacing(5);
+
+        let display_mode_controls = Column::new()
+            .spacing(1)
+            .push(Text::new("Display"))
+            .push(
+                Radio::new(
+                    DisplayMode::Magnitude,
+                    "Magnitude",
+                    Some(self.grid.display_mode),
+                    Message::DisplayModeChanged,
+                )
+                .size(20)
+                .spacing(5),
+            )
+            .push(
+                Radio::new(
+                    DisplayMode::Phase,
+                    "Phase",
+                    Some(self.grid.display_mode),
+                    Message::DisplayModeChanged,
+                )
+                .size(20)
+                .spacing(5),
+            )
+            .push(
+                Radio::new(
+                    DisplayMode::Bark,
+                    "Bark",
+                    Some(self.grid.display_mode),
+                    Message::DisplayModeChanged,
+                )
+                .size(20)
+                .spacing(5),
+            );
+
+        let normalize_controls = Column::new()
+            .spacing(1)
+            .push(Text::new("Normalize"))
+            .push(
+                Radio::new(
+                    Normalize::PerColumn,
+                    "Per column",
+                    Some(self.grid.normalize),
+                    Message::NormalizeChanged,
+                )
+                .size(20)
+                .spacing(5),
+            )
+            .push(
+                Radio::new(
+                    Normalize::GlobalMax,
+                    "Global max",
+                    Some(self.grid.normalize),
+                    Message::NormalizeChanged,
+                )
+                .size(20)
+                .spacing(5),
+            );
+
+        let equal_loudness_controls = Column::new()
+            .spacing(1)
+            .push(
+                Checkbox::new(
+                    self.grid.show_equal_loudness,
+                    "Equal loudness",
+                    Message::EqualLoudnessToggled,
+                )
+                .size(20)
+                .spacing(5),
+            )
+            .push(PickList::new(
+                &mut self.phon_pick_list,
+                Cow::Borrowed(&PHON_LEVELS[..]),
+                Some(self.grid.phon),
+                Message::PhonChanged,
+            ));
+
+        let colormap_controls = Column::new()
+            .spacing(1)
+            .push(Text::new("Colormap"))
+            .push(self.colormap_picker.view());
+
+        let highlight_controls = Column::new()
+            .spacing(1)
+            .push(Text::new("Highlight Hz"))
+            .push(PickList::new(
+                &mut self.highlight_pick_list,
+                Cow::Borrowed(&HIGHLIGHT_PRESETS[..]),
+                None,
+                |hz| Message::FrequencyHighlightAdded(hz as f32),
+            ))
+            .push(
+                Button::new(&mut self.clear_highlights_button, Text::new("Clear"))
+                    .on_press(Message::FrequencyHighlightCleared),
+            );
+
+        let annotation_controls = Column::new()
+            .spacing(1)
+            .push(
+                Checkbox::new(self.grid.annotating, "Annotate", Message::AnnotatingToggled)
+                    .size(20)
+                    .spacing(5),
+            )
+            .push(
+                Checkbox::new(self.grid.labeling, "Label", Message::TextLabelingToggled)
+                    .size(20)
+                    .spacing(5),
+            )
+            .push(
+                Button::new(&mut self.save_annotations_button, Text::new("Save"))
+                    .on_press(Message::AnnotationsSaved),
+            );
+
+        let window_size_controls = Column::new()
+            .spacing(1)
+            .push(Text::new("Window size"))
+            .push(PickList::new(
+                &mut self.window_size_pick_list,
+                Cow::Borrowed(&WINDOW_SIZES[..]),
+                Some(self.grid.window_size),
+                Message::WindowSizeChanged,
+            ));
+
+        let y_unit = self.y_axis.scale().unit.clone();
+        let y_mapping = self.y_axis.scale().mapping.clone();
+
+        let status = Text::new(format!(
+            "Duration: {}    Loudness: {:.1} LUFS    Tempo: {:.0} BPM ({:.0}% confidence)",
+            format_unit(
+                self.n_samples as f32 / self.grid.sample_rate as f32,
+                &Unit::Second
+            ),
+            self.loudness,
+            self.tempo.bpm,
+            self.tempo.confidence * 100.0
+        ))
+        .size(16);
+
+        // a fixed grid takes the axes with it, just thick enough for their tick labels
+        let y_axis_width = min_thickness(&Orientation::Vertical, AXIS_TEXT_SIZE).ceil() as u16;
+        let x_axis_height = min_thickness(&Orientation::Horizontal, AXIS_TEXT_SIZE).ceil() as u16;
+        let y_axis_size = self.grid_size.map(|(_, height)| (y_axis_width, height));
+        let x_axis_size = self.grid_size.map(|(width, _)| (width, x_axis_height));
+
+        let mut row1 = Row::new()
+            .height(length(
+                GRID_FILL_PROPORTION,
+                self.grid_size.map(|size| size.1),
+            ))
+            .push(self.y_axis.view(y_axis_size))
+            .push(self.grid.view(self.grid_size));
+        if self.show_average_spectrum {
+            row1 = row1.push(self.average_spectrum.view());
+        }
+
+        // keeps the x axis under the grid rather than the y axis
+        let spacer = Row::new().width(length(self.axis_padding, y_axis_size.map(|size| size.0)));
+
+        let mut row2 = Row::new()
+            .height(length(
+                self.x_axis_fill_proportion,
+                x_axis_size.map(|size| size.1),
+            ))
             .push(spacer)
-            .push(self.x_axis.view());
+            .push(self.x_axis.view(x_axis_size));
+        if self.show_average_spectrum {
+            row2 = row2.push(Row::new().width(Length::FillPortion(4)));
+        }
 
         let dynamic_axes_controls = Column::new()
             .spacing(1)
@@ -253,6 +1444,15 @@ impl Application for Spectrogram {
                 )
                 .size(20)
                 .spacing(5),
+            )
+            .push(
+                Checkbox::new(
+                    self.human_readable_axes,
+                    "Nice ticks",
+                    Message::HumanReadableAxesToggled,
+                )
+                .size(20)
+                .spacing(5),
             );
 
         let choices: Vec<usize> = (0..(self.wav.fmt_header.nchannels as usize)).collect();
@@ -306,6 +1506,21 @@ impl Application for Spectrogram {
             .push(Text::new("Resolution"))
             .push(slider);
 
+        let overlap_controls = Column::new()
+            .spacing(1)
+            .push(Text::new("Overlap"))
+            .push(overlap_slider);
+
+        let stretch_controls = Column::new()
+            .spacing(1)
+            .push(Text::new(format!("Stretch {:.2}x", self.stretch_factor)))
+            .push(stretch_slider);
+
+        let onset_controls = Column::new()
+            .spacing(1)
+            .push(Text::new(format!("Onsets {:.2}", self.onset_threshold)))
+            .push(onset_slider);
+
         let controls = Row::new()
             .height(Length::FillPortion(2))
             .align_items(Align::Center)
@@ -314,15 +1529,120 @@ impl Application for Spectrogram {
                 Button::new(&mut self.file_button, Text::new("Load .wav file"))
                     .on_press(Message::FileButtonPressed),
             )
+            .push(
+                Button::new(&mut self.raw_pcm_button, Text::new("Raw PCM..."))
+                    .on_press(Message::RawPcmButtonPressed),
+            )
+            .push(
+                Button::new(
+                    &mut self.reference_button,
+                    Text::new(if self.ref_wav.is_some() {
+                        "Clear reference"
+                    } else {
+                        "Compare with .wav"
+                    }),
+                )
+                .on_press(Message::ReferenceButtonPressed),
+            )
+            .push(
+                Button::new(&mut self.export_button, Text::new("Export .npy"))
+                    .on_press(Message::ExportButtonPressed),
+            )
+            .push(
+                Button::new(
+                    &mut self.export_channels_button,
+                    Text::new("Export channels .png"),
+                )
+                .on_press(Message::ExportChannelsButtonPressed),
+            )
             .push(Text::new("Channel:"))
             .push(active_channel_pick_list)
             .push(dynamic_axes_controls)
+            .push(display_mode_controls)
+            .push(normalize_controls)
+            .push(peak_overlay_toggle)
+            .push(harmonics_toggle)
+            .push(colormap_controls)
+            .push(highlight_controls)
+            .push(annotation_controls)
+            .push(equal_loudness_controls)
+            .push(denoise_toggle)
+            .push(gate_toggle)
+            .push(average_spectrum_toggle)
+            .push(waveform_toggle)
+            .push(histogram_toggle)
+            .push(goniometer_toggle)
+            .push(cepstrum_toggle)
             .push(Text::new("Y-axis:"))
             .push(y_unit_controls)
             .push(y_mapping_controls)
-            .push(y_resolution_controls);
+            .push(
+                Button::new(&mut self.flip_button, Text::new("Flip Y"))
+                    .on_press(Message::YAxisFlipped),
+            )
+            .push(y_resolution_controls)
+            .push(window_size_controls)
+            .push(overlap_controls)
+            .push(stretch_controls)
+            .push(onset_controls);
+        #[cfg(feature = "microphone")]
+        let controls = controls.push(
+            Button::new(
+                &mut self.live_button,
+                Text::new(if self.microphone.is_some() {
+                    "Stop"
+                } else {
+                    "Live"
+                }),
+            )
+            .on_press(Message::LiveButtonPressed),
+        );
+        #[cfg(feature = "microphone")]
+        let controls = controls.push(
+            Button::new(&mut self.peak_hold_button, Text::new("Reset peaks"))
+                .on_press(Message::PeakHoldReset),
+        );
 
-        let column = Column::new().push(row1).push(row2).push(controls);
+        let mut column = Column::new().push(row1).push(row2);
+        if self.show_waveform {
+            column = column.push(self.waveform.view());
+        }
+        if self.show_histogram {
+            column = column.push(self.histogram.view());
+        }
+        if self.show_goniometer {
+            column = column
+                .push(self.goniometer.view())
+                .push(self.stereo_width.view());
+        }
+        if self.show_cepstrum {
+            column = column.push(self.cepstrum.view());
+        }
+        if let Some(dialog) = &mut self.raw_pcm {
+            column = column.push(dialog.view());
+        }
+        if let Some(annotation) = &self.pending_annotation {
+            column = column.push(
+                Row::new()
+                    .align_items(Align::Center)
+                    .spacing(20)
+                    .push(Text::new("Annotation label:"))
+                    .push(
+                        TextInput::new(
+                            &mut self.annotation_label_input,
+                            "enter to add",
+                            &annotation.label,
+                            Message::AnnotationLabelChanged,
+                        )
+                        .padding(5)
+                        .on_submit(Message::AnnotationAdded(annotation.clone())),
+                    ),
+            );
+        }
+        if let Some(overlay) = self.text_overlay.view() {
+            column = column.push(overlay);
+        }
+        let column = column.push(controls).push(status);
 
         Container::new(column)
             .width(Length::Fill)
@@ -331,3 +1651,16 @@ impl Application for Spectrogram {
             .into()
     }
 }
+
+#[cfg(test)]
+mod test_spectrogram {
+    use super::Spectrogram;
+
+    #[test]
+    fn columns_fill_the_width() {
+        let samples: Vec<i16> = (0..8000).map(|x| ((x % 64) * 100 - 3150) as i16).collect();
+        let spectrogram = Spectrogram::from_samples(samples, 8000, 100).unwrap();
+        assert_eq!(100, spectrogram.grid.n_columns());
+        assert_eq!((100, 40), spectrogram.resolution);
+    }
+}
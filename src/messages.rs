@@ -1,19 +1,81 @@
+use std::path::PathBuf;
+
 use iced::mouse::Event::CursorMoved;
 use iced::Point;
 
 use iced_native::event::Event;
 
+use super::analysis::annotation::{Annotation, TextLabel};
+use super::io::raw::Endianness;
 use super::units::{Mapping, Unit};
+use super::widgets::grid::{DisplayMode, Normalize};
 
-#[derive(Debug, Clone, Copy)]
+#[derive(Debug, Clone)]
 pub enum Message {
     SliderChanged(u32),
     FileButtonPressed,
+    RawPcmButtonPressed,
+    RawPcmSampleRateChanged(u32),
+    RawPcmChannelsChanged(u16),
+    RawPcmBitsChanged(u16),
+    RawPcmEndiannessChanged(Endianness),
+    RawPcmLoadPressed,
+    RawPcmCancelled,
+    ReferenceButtonPressed,
+    ReferenceFileLoaded(PathBuf),
+    ExportButtonPressed,
+    ExportChannelsButtonPressed,
     CursorMoved(Point),
     YUnitChanged(Unit),
     YMappingChanged(Mapping),
+    YAxisFlipped,
     ActiveChannelChanged(usize),
     DynamicAxesChanged(bool),
+    HumanReadableAxesToggled(bool),
+    OverlapChanged(f32),
+    StretchFactorChanged(f32),
+    WindowSizeChanged(usize),
+    FrequencyRangeChanged { min: f32, max: f32 },
+    TimeRangeChanged { start_sec: f32, end_sec: f32 },
+    PeakOverlayToggled(bool),
+    SpectralGateToggled(bool),
+    DenoiseToggled(bool),
+    AverageSpectrumToggled(bool),
+    HistogramToggled(bool),
+    WaveformToggled(bool),
+    // proportion of the largest spectral flux, see Grid::onsets
+    OnsetThresholdChanged(f32),
+    GoniometerToggled(bool),
+    CepstrumToggled(bool),
+    DisplayModeChanged(DisplayMode),
+    NormalizeChanged(Normalize),
+    HarmonicsToggled(bool),
+    // in Hz
+    FrequencyHighlightAdded(f32),
+    FrequencyHighlightCleared,
+    AnnotatingToggled(bool),
+    // a region dragged out on the grid while annotating, waiting for its label
+    AnnotationRegionSelected(Annotation),
+    AnnotationLabelChanged(String),
+    AnnotationAdded(Annotation),
+    AnnotationsSaved,
+    TextLabelingToggled(bool),
+    // a click on the grid while labeling, waiting for its text
+    TextLabelPlaced(TextLabel),
+    TextLabelChanged(String),
+    TextLabelConfirmed,
+    // index into the grid's labels
+    TextLabelRemoved(usize),
+    EqualLoudnessToggled(bool),
+    PhonChanged(u32),
+    ColormapPickerToggled,
+    // one of colormap::NAMES
+    ColormapChanged(&'static str),
+    LiveButtonPressed,
+    LiveTick,
+    PeakHoldReset,
+    // midi note number and its deviation in cents
+    NoteDetected(u8, f32),
 }
 
 pub fn cursor_moved_filter(event: Event, _: iced_native::event::Status) -> Option<Message> {
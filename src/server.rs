@@ -0,0 +1,345 @@
+use std::convert::TryFrom;
+use std::io::{self, Read};
+use std::net::{Ipv4Addr, TcpListener};
+use std::str::FromStr;
+use std::thread;
+
+use serde::Serialize;
+use tiny_http::{Header, Server};
+
+use crate::builder::{SpectrogramBuilder, SpectrogramData};
+use crate::io::wav::WAV;
+use crate::widgets::grid::WINDOW_SIZES;
+
+pub const DEFAULT_PORT: u16 = 7777;
+// well above the 1MB the WAV parser accepts, so its error is the one reported
+const MAX_BODY: usize = 2_000_000;
+// columns beyond the widest grid the gui allows only cost memory
+const MAX_WIDTH: u32 = 4096;
+
+// spectrograms of POSTed WAV files for other programs on the same machine, eg.
+// curl --data-binary @demo.wav "localhost:7777/?format=png&width=200" > demo.png
+//
+// query parameters are format (json or png, defaulting to json), width, window_size and channel
+pub struct SpectrogramServer {
+    server: Server,
+}
+
+impl SpectrogramServer {
+    // only on the loopback interface as there is no authentication, port 0 picks a free one
+    pub fn bind(port: u16) -> io::Result<SpectrogramServer> {
+        let listener = TcpListener::bind((Ipv4Addr::LOCALHOST, port))?;
+        let server = Server::from_listener(listener, None).map_err(io::Error::other)?;
+        Ok(SpectrogramServer { server })
+    }
+
+    pub fn port(&self) -> io::Result<u16> {
+        self.server
+            .server_addr()
+            .to_ip()
+            .map(|address| address.port())
+            .ok_or_else(|| io::Error::other("not listening on a port"))
+    }
+
+    // until the listener fails. tiny_http reads the headers of each connection on its own
+    // thread, the body is read on another so a client which stalls part way through only
+    // holds up itself
+    pub fn serve(&self) -> io::Result<()> {
+        loop {
+            let mut request = self.server.recv()?;
+            thread::spawn(move || {
+                let response = match read_request(&mut request) {
+                    Ok(body) => respond(&body),
+                    Err(response) => response,
+                };
+                if let Err(e) = request.respond(response.into_http()) {
+                    log::warn!("spectrogram server connection failed: {}", e);
+                }
+            });
+        }
+    }
+}
+
+#[derive(Debug)]
+struct Request {
+    method: String,
+    // path and query, eg. /?format=png
+    target: String,
+    body: Vec<u8>,
+}
+
+#[derive(Debug)]
+struct Response {
+    status: u16,
+    content_type: &'static str,
+    body: Vec<u8>,
+}
+
+impl Response {
+    fn error(status: u16, message: &str) -> Response {
+        Response {
+            status,
+            content_type: "text/plain; charset=utf-8",
+            body: message.as_bytes().to_vec(),
+        }
+    }
+
+    fn into_http(self) -> tiny_http::Response<io::Cursor<Vec<u8>>> {
+        let content_type = Header::from_bytes(&b"Content-Type"[..], self.content_type.as_bytes())
+            .expect("content types to be ascii");
+        tiny_http::Response::from_data(self.body)
+            .with_status_code(self.status)
+            .with_header(content_type)
+    }
+}
+
+#[derive(Serialize)]
+struct SpectrogramJson {
+    // (number of columns, number of frequency bins)
+    resolution: (usize, usize),
+    sample_rate: u32,
+    // seconds
+    duration: f32,
+    // Hz of the last bin
+    max_frequency: f32,
+    // matrix[column][bin] is an intensity between 0.0 and 1.0
+    matrix: Vec<Vec<f64>>,
+}
+
+impl SpectrogramJson {
+    fn new(data: &SpectrogramData) -> SpectrogramJson {
+        SpectrogramJson {
+            resolution: data.resolution,
+            sample_rate: data.sample_rate,
+            duration: data.x.range.max(),
+            max_frequency: data.y.range.max(),
+            matrix: data.to_matrix(),
+        }
+    }
+}
+
+enum Format {
+    Json,
+    Png,
+}
+
+// the method, target and a body of at most MAX_BODY bytes
+fn read_request(request: &mut tiny_http::Request) -> Result<Request, Response> {
+    let too_large = || Response::error(413, "maximum request size is 2MB");
+    if matches!(request.body_length(), Some(length) if length > MAX_BODY) {
+        return Err(too_large());
+    }
+    let mut body = Vec::new();
+    request
+        .as_reader()
+        .take(MAX_BODY as u64 + 1)
+        .read_to_end(&mut body)
+        .map_err(|_| Response::error(400, "malformed request"))?;
+    if body.len() > MAX_BODY {
+        return Err(too_large());
+    }
+    Ok(Request {
+        method: request.method().as_str().to_string(),
+        target: request.url().to_string(),
+        body,
+    })
+}
+
+fn respond(request: &Request) -> Response {
+    spectrogram(request).unwrap_or_else(|response| response)
+}
+
+fn spectrogram(request: &Request) -> Result<Response, Response> {
+    if request.method != "POST" {
+        return Err(Response::error(405, "POST a WAV file"));
+    }
+    let (path, query) = request
+        .target
+        .split_once('?')
+        .unwrap_or((&request.target, ""));
+    if path != "/" {
+        return Err(Response::error(404, "spectrograms are served from /"));
+    }
+
+    let mut format = Format::Json;
+    let mut builder = SpectrogramBuilder::new();
+    for pair in query.split('&').filter(|pair| !pair.is_empty()) {
+        let (key, value) = pair.split_once('=').unwrap_or((pair, ""));
+        match key {
+            "format" => {
+                format = match value {
+                    "json" => Format::Json,
+                    "png" => Format::Png,
+                    _ => return Err(Response::error(400, "format is either json or png")),
+                }
+            }
+            "width" => {
+                let width = parse(key, value)?;
+                if width > MAX_WIDTH {
+                    let msg = format!("width is at most {}", MAX_WIDTH);
+                    return Err(Response::error(400, &msg));
+                }
+                builder = builder.width(width)
+            }
+            "window_size" => {
+                let window_size = parse(key, value)?;
+                let max = WINDOW_SIZES[WINDOW_SIZES.len() - 1];
+                if window_size > max {
+                    let msg = format!("window_size is at most {}", max);
+                    return Err(Response::error(400, &msg));
+                }
+                builder = builder.window_size(window_size)
+            }
+            "channel" => builder = builder.channel(parse(key, value)?),
+            _ => {
+                let msg = format!("unknown parameter {}", key);
+                return Err(Response::error(400, &msg));
+            }
+        }
+    }
+
+    let wav = WAV::try_from(&request.body[..])
+        .map_err(|e| Response::error(400, &format!("invalid WAV file: {}", e)))?;
+    let data = builder
+        .wav(wav)
+        .build()
+        .map_err(|e| Response::error(400, &e.to_string()))?;
+    Ok(match format {
+        Format::Json => Response {
+            status: 200,
+            content_type: "application/json",
+            body: serde_json::to_vec(&SpectrogramJson::new(&data))
+                .map_err(|e| Response::error(500, &e.to_string()))?,
+        },
+        Format::Png => Response {
+            status: 200,
+            content_type: "image/png",
+            body: data.to_png_bytes(),
+        },
+    })
+}
+
+fn parse<T: FromStr>(key: &str, value: &str) -> Result<T, Response> {
+    value
+        .parse()
+        .map_err(|_| Response::error(400, &format!("{} must be an integer", key)))
+}
+
+#[cfg(test)]
+mod test_spectrogram_server {
+    use super::{read_request, respond, Request, SpectrogramServer};
+    use crate::io::wav::WAV;
+    use crate::test_signals::sine;
+    use std::io::{Read, Write};
+    use std::net::TcpStream;
+    use std::thread;
+    use tiny_http::{Header, Method, TestRequest};
+
+    fn sine_wav_bytes() -> Vec<u8> {
+        let mut bytes = Vec::new();
+        WAV::from_samples(vec![sine(1000.0, 8000, 8000)], 8000)
+            .unwrap()
+            .write_to(&mut bytes)
+            .unwrap();
+        bytes
+    }
+
+    fn post(target: &str, body: Vec<u8>) -> Request {
+        Request {
+            method: "POST".to_string(),
+            target: target.to_string(),
+            body,
+        }
+    }
+
+    #[test]
+    fn json() {
+        let response = respond(&post("/?width=4&window_size=256", sine_wav_bytes()));
+        assert_eq!(200, response.status);
+        let json: serde_json::Value = serde_json::from_slice(&response.body).unwrap();
+        assert_eq!(serde_json::json!([4, 129]), json["resolution"]);
+        assert_eq!(8000, json["sample_rate"]);
+        assert_eq!(4, json["matrix"].as_array().unwrap().len());
+    }
+
+    #[test]
+    fn png() {
+        let response = respond(&post(
+            "/?format=png&width=8&window_size=64",
+            sine_wav_bytes(),
+        ));
+        assert_eq!(200, response.status);
+        assert_eq!("image/png", response.content_type);
+        assert_eq!(b"\x89PNG", &response.body[..4]);
+    }
+
+    #[test]
+    fn rejected() {
+        let get = Request {
+            method: "GET".to_string(),
+            target: "/".to_string(),
+            body: vec![],
+        };
+        assert_eq!(405, respond(&get).status);
+        assert_eq!(404, respond(&post("/other", sine_wav_bytes())).status);
+        assert_eq!(400, respond(&post("/?format=bmp", sine_wav_bytes())).status);
+        assert_eq!(400, respond(&post("/?width=many", sine_wav_bytes())).status);
+        assert_eq!(400, respond(&post("/", b"not a wav".to_vec())).status);
+    }
+
+    #[test]
+    fn too_wide() {
+        assert_eq!(400, respond(&post("/?width=4097", sine_wav_bytes())).status);
+        assert_eq!(
+            400,
+            respond(&post("/?window_size=8192", sine_wav_bytes())).status
+        );
+        assert_eq!(
+            200,
+            respond(&post("/?width=4000&window_size=4096", sine_wav_bytes())).status
+        );
+    }
+
+    #[test]
+    fn reads_body() {
+        let mut request: tiny_http::Request = TestRequest::new()
+            .with_method(Method::Post)
+            .with_path("/?format=png")
+            .with_body("abc")
+            .into();
+        let request = read_request(&mut request).unwrap();
+        assert_eq!("POST", request.method);
+        assert_eq!("/?format=png", request.target);
+        assert_eq!(b"abc".to_vec(), request.body);
+    }
+
+    #[test]
+    fn oversized_body() {
+        let mut request: tiny_http::Request = TestRequest::new()
+            .with_method(Method::Post)
+            .with_header("Content-Length: 3000000".parse::<Header>().unwrap())
+            .into();
+        assert_eq!(413, read_request(&mut request).unwrap_err().status);
+    }
+
+    #[test]
+    fn over_tcp() {
+        let server = SpectrogramServer::bind(0).unwrap();
+        let port = server.port().unwrap();
+        thread::spawn(move || server.serve());
+
+        let body = sine_wav_bytes();
+        let mut stream = TcpStream::connect(("127.0.0.1", port)).unwrap();
+        write!(
+            stream,
+            "POST /?format=png&width=8 HTTP/1.1\r\nConnection: close\r\nContent-Length: {}\r\n\r\n",
+            body.len()
+        )
+        .unwrap();
+        stream.write_all(&body).unwrap();
+        let mut response = Vec::new();
+        stream.read_to_end(&mut response).unwrap();
+        assert!(response.starts_with(b"HTTP/1.1 200 OK\r\n"));
+        assert!(response.windows(4).any(|window| window == b"\x89PNG"));
+    }
+}
@@ -0,0 +1,30 @@
+use std::f64::consts::TAU;
+
+// loud enough that truncating to whole samples barely changes the spectrum, quiet enough
+// never to clip
+pub const AMPLITUDE: f64 = 10000.0;
+
+// n samples of a sine with a peak of 1.0, starting phase radians into its cycle
+pub fn unit_sine(frequency: f64, sample_rate: u32, n: usize, phase: f64) -> Vec<f64> {
+    (0..n)
+        .map(|t| (TAU * frequency * t as f64 / sample_rate as f64 + phase).sin())
+        .collect()
+}
+
+// unit_sine with a peak of amplitude, truncated to whole samples
+pub fn scaled_sine(
+    frequency: f64,
+    sample_rate: u32,
+    n: usize,
+    amplitude: f64,
+    phase: f64,
+) -> Vec<i16> {
+    unit_sine(frequency, sample_rate, n, phase)
+        .iter()
+        .map(|x| (amplitude * x) as i16)
+        .collect()
+}
+
+pub fn sine(frequency: f64, sample_rate: u32, n: usize) -> Vec<i16> {
+    scaled_sine(frequency, sample_rate, n, AMPLITUDE, 0.0)
+}
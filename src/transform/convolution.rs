@@ -0,0 +1,98 @@
+use crate::transform::{fourier_transform, inverse_fourier_transform, Backend};
+
+// linear convolution of signal with an fir filter's kernel, every overlap of the two is kept
+// so there are signal.len() + kernel.len() - 1 samples, or none if either is empty
+pub fn convolve(signal: &[f64], kernel: &[f64]) -> Vec<f64> {
+    if signal.is_empty() || kernel.is_empty() {
+        return vec![];
+    }
+    let mut output = vec![0.0; signal.len() + kernel.len() - 1];
+    for (i, x) in signal.iter().enumerate() {
+        for (j, h) in kernel.iter().enumerate() {
+            output[i + j] += x * h;
+        }
+    }
+    output
+}
+
+// convolve through the product of spectra, quicker once the kernel is more than a few dozen taps
+//
+// both are zero padded to a power of two at least as long as the output, so the circular
+// convolution of the transforms never wraps around onto itself
+pub fn fast_convolve(signal: &[f64], kernel: &[f64], backend: Backend) -> Vec<f64> {
+    if signal.is_empty() || kernel.is_empty() {
+        return vec![];
+    }
+    let n_output = signal.len() + kernel.len() - 1;
+    let n_fft = n_output.next_power_of_two();
+    let padded = |samples: &[f64]| {
+        let mut padded = samples.to_vec();
+        padded.resize(n_fft, 0.0);
+        padded
+    };
+    let signal_spectrum = fourier_transform(&padded(signal), backend);
+    let kernel_spectrum = fourier_transform(&padded(kernel), backend);
+    let product: Vec<_> = signal_spectrum
+        .iter()
+        .zip(kernel_spectrum.iter())
+        .map(|(x, h)| x * h)
+        .collect();
+    let mut output = inverse_fourier_transform(&product, n_fft, backend);
+    output.truncate(n_output);
+    output
+}
+
+#[cfg(test)]
+mod test_convolve {
+    use super::{convolve, fast_convolve};
+    use crate::transform::Backend;
+
+    fn assert_close(expected: &[f64], found: &[f64]) {
+        assert_eq!(expected.len(), found.len());
+        for (e, f) in expected.iter().zip(found.iter()) {
+            assert!((e - f).abs() < 1e-9, "{:?} {:?}", expected, found)
+        }
+    }
+
+    #[test]
+    fn delta() {
+        let signal = [0.5, -1.0, 3.0, 2.0];
+        assert_eq!(signal.to_vec(), convolve(&signal, &[1.0]));
+        assert_close(&signal, &fast_convolve(&signal, &[1.0], Backend::default()));
+    }
+
+    #[test]
+    fn linear() {
+        // not circular, the tail of the kernel runs past the end of the signal
+        let expected = [1.0, 4.0, 10.0, 16.0, 17.0, 12.0];
+        assert_eq!(
+            expected.to_vec(),
+            convolve(&[1.0, 2.0, 3.0, 4.0], &[1.0, 2.0, 3.0])
+        );
+        assert_close(
+            &expected,
+            &fast_convolve(&[1.0, 2.0, 3.0, 4.0], &[1.0, 2.0, 3.0], Backend::default()),
+        );
+    }
+
+    #[test]
+    fn fast_agrees() {
+        let signal: Vec<f64> = (0..300)
+            .map(|t| ((t * 7919) % 201) as f64 - 100.0)
+            .collect();
+        let kernel: Vec<f64> = (0..65).map(|t| 1.0 / (1.0 + t as f64)).collect();
+        let expected = convolve(&signal, &kernel);
+        assert_eq!(364, expected.len());
+        let found = fast_convolve(&signal, &kernel, Backend::default());
+        assert_eq!(expected.len(), found.len());
+        for (e, f) in expected.iter().zip(found.iter()) {
+            assert!((e - f).abs() < 1e-6, "{} {}", e, f)
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert!(convolve(&[], &[1.0]).is_empty());
+        assert!(fast_convolve(&[1.0, 2.0], &[], Backend::default()).is_empty());
+    }
+}
@@ -0,0 +1,73 @@
+// mean magnitude of each bin over the first num_frames frames, which are assumed to only
+// contain the stationary background noise
+// frames[frame][bin], every frame is expected to have the same number of bins
+pub fn estimate_noise_spectrum(frames: &[Vec<f64>], num_frames: usize) -> Vec<f64> {
+    let n_bins = frames.first().map_or(0, |frame| frame.len());
+    let num_frames = num_frames.min(frames.len());
+    let mut noise = vec![0.0; n_bins];
+    if num_frames == 0 {
+        return noise;
+    }
+    for frame in &frames[..num_frames] {
+        for (bin, magnitude) in frame.iter().enumerate() {
+            noise[bin] += magnitude / num_frames as f64;
+        }
+    }
+    noise
+}
+
+// removes alpha times the noise spectrum from a frame, floored at zero
+// alpha above 1.0 over subtracts, trading more of the signal for less residual noise
+pub fn spectral_subtraction(frame: &[f64], noise: &[f64], alpha: f64) -> Vec<f64> {
+    frame
+        .iter()
+        .zip(noise.iter())
+        .map(|(magnitude, noise)| (magnitude - alpha * noise).max(0.0))
+        .collect()
+}
+
+#[cfg(test)]
+mod test_estimate_noise_spectrum {
+    use super::estimate_noise_spectrum;
+
+    #[test]
+    fn averages_leading_frames() {
+        let frames = vec![vec![1.0, 0.0], vec![3.0, 2.0], vec![100.0, 100.0]];
+        assert_eq!(vec![2.0, 1.0], estimate_noise_spectrum(&frames, 2));
+    }
+
+    #[test]
+    fn more_frames_than_available() {
+        let frames = vec![vec![1.0], vec![3.0]];
+        assert_eq!(vec![2.0], estimate_noise_spectrum(&frames, 10));
+    }
+
+    #[test]
+    fn empty() {
+        assert!(estimate_noise_spectrum(&[], 2).is_empty());
+        assert_eq!(vec![0.0], estimate_noise_spectrum(&[vec![1.0]], 0));
+    }
+}
+
+#[cfg(test)]
+mod test_spectral_subtraction {
+    use super::spectral_subtraction;
+
+    #[test]
+    fn floors_at_zero() {
+        let frame = [1.0, 0.5, 0.1];
+        let noise = [0.2, 0.2, 0.2];
+        let result = spectral_subtraction(&frame, &noise, 1.0);
+        assert!((result[0] - 0.8).abs() < 1e-12);
+        assert!((result[1] - 0.3).abs() < 1e-12);
+        assert_eq!(0.0, result[2]);
+    }
+
+    #[test]
+    fn over_subtraction() {
+        assert_eq!(
+            vec![0.5, 0.0],
+            spectral_subtraction(&[1.0, 0.3], &[0.25, 0.25], 2.0)
+        );
+    }
+}
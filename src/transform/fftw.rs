@@ -1,5 +1,5 @@
 use fftw::array::AlignedVec;
-use fftw::plan::{R2CPlan, R2CPlan64};
+use fftw::plan::{C2RPlan, C2RPlan64, R2CPlan, R2CPlan64};
 use fftw::types::Flag;
 use num::Complex;
 
@@ -21,3 +21,113 @@ pub fn planned_fourier_transform(mut plan: R2CPlan64, samples: &Vec<f64>) -> Vec
     plan.r2c(&mut a, &mut b).expect("fftw dft to execute");
     b.to_vec()
 }
+
+// spectra of equal length windows from a single plan, MEASURE planning is slow but is
+// only paid once rather than for every window
+pub fn batch_fourier_transform(windows: &[Vec<f64>]) -> Vec<Vec<Complex<f64>>> {
+    let n = match windows.first() {
+        Some(window) => window.len(),
+        None => return vec![],
+    };
+    assert!(
+        windows.iter().all(|window| window.len() == n),
+        "windows to all have the same length"
+    );
+    let mut plan: R2CPlan64 = R2CPlan::aligned(&[n], Flag::MEASURE).expect("plan to create");
+    let mut a = AlignedVec::new(n);
+    let mut b = AlignedVec::new(n / 2 + 1);
+    windows
+        .iter()
+        .map(|window| {
+            a.copy_from_slice(window);
+            plan.r2c(&mut a, &mut b).expect("fftw dft to execute");
+            b.to_vec()
+        })
+        .collect()
+}
+
+// spectrum holds the n_samples / 2 + 1 bins returned by fourier_transform
+// fftw leaves the output scaled by n_samples, which is divided back out here
+pub fn inverse_fourier_transform(spectrum: &[Complex<f64>], n_samples: usize) -> Vec<f64> {
+    let mut plan: C2RPlan64 =
+        C2RPlan::aligned(&[n_samples], Flag::ESTIMATE).expect("plan to create");
+    let mut a = AlignedVec::new(n_samples / 2 + 1);
+    let mut b = AlignedVec::new(n_samples);
+    a.copy_from_slice(spectrum);
+    plan.c2r(&mut a, &mut b)
+        .expect("fftw inverse dft to execute");
+    b.iter().map(|x| x / n_samples as f64).collect()
+}
+
+#[cfg(test)]
+mod test_batch_fourier_transform {
+    use super::{batch_fourier_transform, fourier_transform};
+
+    #[test]
+    fn matches_individual() {
+        let windows: Vec<Vec<f64>> = (0..5)
+            .map(|w| {
+                (0..64)
+                    .map(|x| ((x * w * 7919) % 200) as f64 - 100.0)
+                    .collect()
+            })
+            .collect();
+        let batch = batch_fourier_transform(&windows);
+        assert_eq!(windows.len(), batch.len());
+        for (window, spectrum) in windows.iter().zip(batch) {
+            assert_eq!(33, spectrum.len());
+            for (e, a) in fourier_transform(window).iter().zip(spectrum) {
+                assert!((e - a).norm() < 1e-9)
+            }
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert!(batch_fourier_transform(&[]).is_empty())
+    }
+
+    #[test]
+    #[should_panic]
+    fn unequal_lengths() {
+        batch_fourier_transform(&[vec![0.0; 8], vec![0.0; 16]]);
+    }
+}
+
+#[cfg(test)]
+mod there_and_back_again {
+    use super::{fourier_transform, inverse_fourier_transform};
+    use num::Complex;
+
+    #[test]
+    fn round_trip() {
+        let samples: Vec<f64> = (0..1000)
+            .map(|x| ((x * 7919) % 2000) as f64 - 1000.0)
+            .collect();
+        let result = inverse_fourier_transform(&fourier_transform(&samples), samples.len());
+        for (expected, actual) in samples.iter().zip(result.iter()) {
+            assert!(
+                (expected - actual).abs() < 1e-9,
+                "{} != {}",
+                expected,
+                actual
+            )
+        }
+    }
+
+    #[test]
+    fn odd_length() {
+        let samples = vec![1.0, -2.0, 3.0, 0.5, 4.0];
+        let result = inverse_fourier_transform(&fourier_transform(&samples), samples.len());
+        for (expected, actual) in samples.iter().zip(result.iter()) {
+            assert!((expected - actual).abs() < 1e-12)
+        }
+    }
+
+    #[test]
+    fn dc_only() {
+        let mut spectrum = vec![Complex::new(0.0, 0.0); 5];
+        spectrum[0] = Complex::new(8.0, 0.0);
+        assert_eq!(vec![1.0; 8], inverse_fourier_transform(&spectrum, 8))
+    }
+}
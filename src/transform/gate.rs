@@ -0,0 +1,70 @@
+// fraction of frames, quietest first, which are assumed to only contain noise
+const NOISE_FRAMES: f64 = 0.1;
+
+// zeros every value less than noise_floor_db above its bin's noise floor, where the
+// floor is the mean of the quietest 10% of frames in that bin
+// magnitudes[frame][bin], every frame is expected to have the same number of bins
+pub fn spectral_gate(magnitudes: &mut [Vec<f64>], noise_floor_db: f32) {
+    let n_frames = magnitudes.len();
+    let n_bins = magnitudes.first().map_or(0, |frame| frame.len());
+    let n_noise_frames = ((n_frames as f64 * NOISE_FRAMES).ceil() as usize).max(1);
+    let above_floor = 10f64.powf(noise_floor_db as f64 / 20.0);
+
+    for bin in 0..n_bins {
+        let mut values: Vec<f64> = magnitudes.iter().map(|frame| frame[bin]).collect();
+        values.sort_by(|a, b| a.total_cmp(b));
+        let floor = values[..n_noise_frames].iter().sum::<f64>() / n_noise_frames as f64;
+        let threshold = floor * above_floor;
+        for frame in magnitudes.iter_mut() {
+            if frame[bin] < threshold {
+                frame[bin] = 0.0;
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_spectral_gate {
+    use super::spectral_gate;
+
+    #[test]
+    fn removes_steady_noise() {
+        // bin 0 is constant noise, bin 1 has a tone in the last frame
+        let mut magnitudes: Vec<Vec<f64>> = (0..10).map(|_| vec![0.1, 0.1]).collect();
+        magnitudes[9][1] = 1.0;
+        spectral_gate(&mut magnitudes, 6.0);
+        assert!(magnitudes[..9].iter().all(|frame| frame == &vec![0.0, 0.0]));
+        assert_eq!(vec![0.0, 1.0], magnitudes[9]);
+    }
+
+    #[test]
+    fn keeps_values_above_threshold() {
+        // 6 dB is roughly double the floor of 0.1
+        let mut magnitudes = vec![vec![0.1], vec![0.19], vec![0.21]];
+        spectral_gate(&mut magnitudes, 6.0);
+        assert_eq!(vec![vec![0.0], vec![0.0], vec![0.21]], magnitudes);
+    }
+
+    #[test]
+    fn silence() {
+        let mut magnitudes = vec![vec![0.0, 0.0]; 4];
+        spectral_gate(&mut magnitudes, 6.0);
+        assert_eq!(vec![vec![0.0, 0.0]; 4], magnitudes);
+    }
+
+    #[test]
+    fn not_a_number() {
+        // sorted after every other value rather than panicking
+        let mut magnitudes = vec![vec![0.1], vec![f64::NAN], vec![1.0]];
+        spectral_gate(&mut magnitudes, 6.0);
+        assert_eq!(vec![0.0], magnitudes[0]);
+        assert_eq!(vec![1.0], magnitudes[2]);
+    }
+
+    #[test]
+    fn empty() {
+        let mut magnitudes: Vec<Vec<f64>> = vec![];
+        spectral_gate(&mut magnitudes, 6.0);
+        assert!(magnitudes.is_empty());
+    }
+}
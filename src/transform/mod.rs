@@ -1,3 +1,163 @@
+pub mod convolution;
+pub mod denoise;
+#[cfg(feature = "fftw")]
 pub mod fftw;
+pub mod gate;
 pub mod naive;
+#[cfg(feature = "nightly_simd")]
 pub mod naive_simd;
+// naive_simd's interface on the scalar naive transform, std::simd is only on nightly
+#[cfg(not(feature = "nightly_simd"))]
+#[path = "naive_simd_fallback.rs"]
+pub mod naive_simd;
+pub mod resample;
+pub mod stft;
+pub mod timestretch;
+pub mod window;
+
+use num::cast::ToPrimitive;
+use num::Complex;
+
+// without fftw the simd transform is the fastest available
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Backend {
+    Naive,
+    #[cfg_attr(not(feature = "fftw"), default)]
+    NaiveSimd,
+    #[cfg(feature = "fftw")]
+    #[default]
+    Fftw,
+}
+
+// spectrum of real valued samples, only the n / 2 + 1 non-redundant bins are returned
+// regardless of backend
+pub fn fourier_transform<T: ToPrimitive + Copy>(
+    samples: &[T],
+    backend: Backend,
+) -> Vec<Complex<f64>> {
+    let n_bins = samples.len() / 2 + 1;
+    match backend {
+        Backend::Naive => {
+            let mut spectrum = naive::fourier_transform(samples.to_vec());
+            spectrum.truncate(n_bins);
+            spectrum
+        }
+        Backend::NaiveSimd => {
+            let mut spectrum = naive_simd::fourier_transform(samples.to_vec(), false);
+            spectrum.truncate(n_bins);
+            spectrum
+        }
+        #[cfg(feature = "fftw")]
+        Backend::Fftw => {
+            let f64_samples: Vec<f64> = samples
+                .iter()
+                .map(|x| x.to_f64().expect("samples convertable to f64"))
+                .collect();
+            fftw::fourier_transform(&f64_samples)
+        }
+    }
+}
+
+// fourier_transform of every window, which must all be the same length
+// fftw plans once for all of them instead of once per window
+pub fn batch_fourier_transform(windows: &[Vec<f64>], backend: Backend) -> Vec<Vec<Complex<f64>>> {
+    match backend {
+        #[cfg(feature = "fftw")]
+        Backend::Fftw => fftw::batch_fourier_transform(windows),
+        _ => windows
+            .iter()
+            .map(|window| fourier_transform(window, backend))
+            .collect(),
+    }
+}
+
+// real samples from the n_samples / 2 + 1 bins returned by fourier_transform
+pub fn inverse_fourier_transform(
+    spectrum: &[Complex<f64>],
+    n_samples: usize,
+    backend: Backend,
+) -> Vec<f64> {
+    // the naive transforms need the redundant upper half, the conjugate of the lower
+    let full_spectrum = || {
+        let mut full = spectrum[..n_samples / 2 + 1].to_vec();
+        for k in (n_samples / 2 + 1)..n_samples {
+            full.push(spectrum[n_samples - k].conj());
+        }
+        full
+    };
+    match backend {
+        Backend::Naive => naive::inverse_fourier_transform(full_spectrum())
+            .iter()
+            .map(|x| x.re)
+            .collect(),
+        Backend::NaiveSimd => naive_simd::inverse_fourier_transform(full_spectrum())
+            .iter()
+            .map(|x| x.re)
+            .collect(),
+        #[cfg(feature = "fftw")]
+        Backend::Fftw => fftw::inverse_fourier_transform(spectrum, n_samples),
+    }
+}
+
+#[cfg(test)]
+mod test_backend {
+    use super::{batch_fourier_transform, fourier_transform, inverse_fourier_transform, Backend};
+
+    fn backends() -> Vec<Backend> {
+        vec![
+            Backend::Naive,
+            Backend::NaiveSimd,
+            #[cfg(feature = "fftw")]
+            Backend::Fftw,
+        ]
+    }
+
+    #[test]
+    fn n_bins() {
+        for backend in backends() {
+            assert_eq!(
+                5,
+                fourier_transform(&[1, 0, 0, 0, 0, 0, 0, 0], backend).len()
+            )
+        }
+    }
+
+    #[test]
+    fn backends_agree() {
+        let samples: Vec<i16> = (0..16).map(|x| (x * 37 % 11) as i16 - 5).collect();
+        let expected = fourier_transform(&samples, Backend::Naive);
+        for backend in backends() {
+            for (e, a) in expected.iter().zip(fourier_transform(&samples, backend)) {
+                assert!((e - a).norm() < 1e-6, "{:?} disagrees", backend)
+            }
+        }
+    }
+
+    #[test]
+    fn batch() {
+        let windows: Vec<Vec<f64>> = (0..3)
+            .map(|w| (0..16).map(|x| ((x + w) * 37 % 11) as f64 - 5.0).collect())
+            .collect();
+        for backend in backends() {
+            let batch = batch_fourier_transform(&windows, backend);
+            for (window, spectrum) in windows.iter().zip(batch) {
+                let expected = fourier_transform(window, Backend::Naive);
+                for (e, a) in expected.iter().zip(spectrum) {
+                    assert!((e - a).norm() < 1e-6, "{:?} disagrees", backend)
+                }
+            }
+        }
+    }
+
+    #[test]
+    fn round_trip() {
+        let samples: Vec<i16> = (0..16).map(|x| (x * 37 % 11) as i16 - 5).collect();
+        for backend in backends() {
+            let spectrum = fourier_transform(&samples, backend);
+            let result = inverse_fourier_transform(&spectrum, samples.len(), backend);
+            for (e, a) in samples.iter().zip(result) {
+                assert!((*e as f64 - a).abs() < 1e-9, "{:?} disagrees", backend)
+            }
+        }
+    }
+}
@@ -1,5 +1,5 @@
 use num::cast::ToPrimitive;
-use num::{Complex, Integer};
+use num::Complex;
 
 use std::f64::consts::PI;
 
@@ -26,7 +26,7 @@ fn calculate_kth(k: usize, samples: &Vec<f64>) -> Complex<f64> {
     x_k
 }
 
-pub fn fourier_transform<I: Integer + ToPrimitive>(samples: Vec<I>) -> Vec<Complex<f64>> {
+pub fn fourier_transform<I: ToPrimitive>(samples: Vec<I>) -> Vec<Complex<f64>> {
     let mut transformed_samples: Vec<Complex<f64>> = Vec::new();
     let n_samples = samples.len();
     let samples: Vec<f64> = samples
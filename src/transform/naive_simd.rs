@@ -1,5 +1,5 @@
 use num::cast::ToPrimitive;
-use num::{Complex, Integer};
+use num::Complex;
 
 use std::f64::consts::{PI, TAU};
 use std::simd::{f64x8, Simd};
@@ -8,7 +8,7 @@ use std::simd::{f64x8, Simd};
 const i: Complex<f64> = Complex::new(0.0, 1.0);
 const EIGHT_TAU: Simd<f64, 8> = f64x8::splat(TAU);
 
-fn calculate_kth_nth(x_n: &f64, n: usize, n_samples: usize, k: usize) -> Complex<f64> {
+pub fn calculate_kth_nth(x_n: &f64, n: usize, n_samples: usize, k: usize) -> Complex<f64> {
     let n = n.to_f64().unwrap();
     let n_samples = n_samples.to_f64().unwrap();
     let k = k.to_f64().unwrap();
@@ -58,7 +58,9 @@ fn simd_calculate_kth(k: usize, samples: &Vec<f64>) -> Complex<f64> {
     x_k
 }
 
-pub fn fourier_transform<I: Integer + ToPrimitive>(samples: Vec<I>) -> Vec<Complex<f64>> {
+// when normalize is true each component is divided by the number of samples, so a sine of
+// amplitude a shows as a / 2 in its positive and negative frequency bins whatever the length
+pub fn fourier_transform<I: ToPrimitive>(samples: Vec<I>, normalize: bool) -> Vec<Complex<f64>> {
     let mut transformed_samples: Vec<Complex<f64>> = Vec::new();
     let samples: Vec<f64> = samples
         .iter()
@@ -66,7 +68,10 @@ pub fn fourier_transform<I: Integer + ToPrimitive>(samples: Vec<I>) -> Vec<Compl
         .collect();
     let n_samples = samples.len();
     for k in 0..n_samples {
-        let x_k = simd_calculate_kth(k, &samples);
+        let mut x_k = simd_calculate_kth(k, &samples);
+        if normalize {
+            x_k /= n_samples.to_f64().unwrap();
+        }
         transformed_samples.push(x_k);
     }
     transformed_samples
@@ -186,7 +191,7 @@ mod there_and_back_again {
     fn inpulse_at_origin() {
         let input = INPULSE_AT_ORIGIN.to_vec();
         let input_real = input.iter().map(|x| x.re as i16).collect();
-        let transformed = fourier_transform(input_real);
+        let transformed = fourier_transform(input_real, false);
         let mut result = inverse_fourier_transform(transformed);
         for x in &mut result {
             round_complex(x, 10)
@@ -198,7 +203,7 @@ mod there_and_back_again {
     fn inpulse_at_one() {
         let input = INPULSE_AT_ONE.to_vec();
         let input_real = input.iter().map(|x| x.re as i16).collect();
-        let transformed = fourier_transform(input_real);
+        let transformed = fourier_transform(input_real, false);
         let mut result = inverse_fourier_transform(transformed);
         for x in &mut result {
             round_complex(x, 10)
@@ -225,7 +230,7 @@ mod ft_test {
             Complex::new(1.0, 0.0),
             Complex::new(1.0, 0.0),
         ];
-        let result = fourier_transform(input);
+        let result = fourier_transform(input, false);
         assert_eq!(expected, result);
     }
 
@@ -242,7 +247,7 @@ mod ft_test {
             Complex::new(0.0, 1.0),
             Complex::new(0.707, 0.707),
         ];
-        let mut result = fourier_transform(input);
+        let mut result = fourier_transform(input, false);
         for x in &mut result {
             round_complex(x, 3)
         }
@@ -262,7 +267,7 @@ mod ft_test {
             Complex::new(0.0, 1.0),
             Complex::new(0.707, 0.707),
         ];
-        let mut result = fourier_transform(input);
+        let mut result = fourier_transform(input, false);
         for x in &mut result {
             round_complex(x, 3)
         }
@@ -273,10 +278,28 @@ mod ft_test {
     fn i64_and_i16_equal() {
         let input_i64: Vec<i64> = vec![0, 1, 0, 0, 0, 0, 0, 0];
         let input_i16: Vec<i16> = vec![0, 1, 0, 0, 0, 0, 0, 0];
-        let result_i64 = fourier_transform(input_i64);
-        let result_i16 = fourier_transform(input_i16);
+        let result_i64 = fourier_transform(input_i64, false);
+        let result_i16 = fourier_transform(input_i16, false);
         assert_eq!(result_i64, result_i16);
     }
+
+    #[test]
+    fn normalized() {
+        let input: Vec<i16> = vec![0, 1, 0, 0, 0, 0, 0, 0];
+        let unnormalized = fourier_transform(input.clone(), false);
+        let normalized = fourier_transform(input, true);
+        for (u, n) in unnormalized.iter().zip(normalized) {
+            assert_eq!(u / 8.0, n);
+        }
+    }
+
+    #[test]
+    fn normalized_full_scale_dc() {
+        // the mean, rather than the sum, of the samples
+        let input: Vec<i16> = vec![i16::MAX; 8000];
+        let result = fourier_transform(input, true);
+        assert!((result[0].re - i16::MAX as f64).abs() < 1e-6);
+    }
 }
 
 #[cfg(test)]
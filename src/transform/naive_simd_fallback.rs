@@ -0,0 +1,41 @@
+use num::cast::ToPrimitive;
+use num::Complex;
+
+use super::naive;
+
+// when normalize is true each component is divided by the number of samples
+pub fn fourier_transform<I: ToPrimitive>(samples: Vec<I>, normalize: bool) -> Vec<Complex<f64>> {
+    let n_samples = samples.len();
+    let spectrum = naive::fourier_transform(samples);
+    if normalize {
+        spectrum.iter().map(|x| x / n_samples as f64).collect()
+    } else {
+        spectrum
+    }
+}
+
+pub fn inverse_fourier_transform(samples: Vec<Complex<f64>>) -> Vec<Complex<f64>> {
+    naive::inverse_fourier_transform(samples)
+}
+
+#[cfg(test)]
+mod test_fallback {
+    use super::fourier_transform;
+    use crate::transform::naive;
+    use num::Complex;
+
+    #[test]
+    fn matches_naive() {
+        let samples = vec![100, 211, 62, 116, 34, 98, 178, 12, 201];
+        assert_eq!(
+            naive::fourier_transform(samples.clone()),
+            fourier_transform(samples, false)
+        );
+    }
+
+    #[test]
+    fn normalized_full_scale_dc() {
+        let spectrum = fourier_transform(vec![i16::MAX; 16], true);
+        assert_eq!(Complex::new(i16::MAX as f64, 0.0), spectrum[0]);
+    }
+}
@@ -0,0 +1,61 @@
+// linear interpolation between neighbouring samples
+// there is no low pass filter, so downsampling will alias anything above the new nyquist
+pub fn resample(samples: &[i16], from_rate: u32, to_rate: u32) -> Vec<i16> {
+    if samples.is_empty() || from_rate == to_rate {
+        return samples.to_vec();
+    }
+    let n_out = (samples.len() as u64 * to_rate as u64 / from_rate as u64) as usize;
+    let step = from_rate as f64 / to_rate as f64;
+    let last = samples.len() - 1;
+    (0..n_out)
+        .map(|j| {
+            let position = j as f64 * step;
+            let before = (position.floor() as usize).min(last);
+            let after = (before + 1).min(last);
+            let fraction = position - before as f64;
+            let value =
+                samples[before] as f64 * (1.0 - fraction) + samples[after] as f64 * fraction;
+            value.round() as i16
+        })
+        .collect()
+}
+
+#[cfg(test)]
+mod test_resample {
+    use super::resample;
+    use crate::analysis::features::peak_frequency;
+    use crate::test_signals::sine;
+    use crate::transform::{fourier_transform, Backend};
+
+    #[test]
+    fn same_rate() {
+        let samples = vec![1, 2, 3];
+        assert_eq!(samples, resample(&samples, 44100, 44100))
+    }
+
+    #[test]
+    fn upsample_interpolates() {
+        assert_eq!(vec![0, 50, 100, 100], resample(&[0, 100], 1, 2))
+    }
+
+    #[test]
+    fn length() {
+        assert_eq!(22050, resample(&vec![0; 44100], 44100, 22050).len())
+    }
+
+    #[test]
+    fn downsampled_sine_keeps_its_peak() {
+        let resampled = resample(&sine(440.0, 44100, 44100), 44100, 22050);
+        let n_fft = 8192;
+        let magnitudes: Vec<f64> = fourier_transform(&resampled[..n_fft], Backend::default())
+            .iter()
+            .map(|x| x.norm())
+            .collect();
+        let peak = peak_frequency(&magnitudes, 22050, n_fft);
+        assert!(
+            (peak - 440.0).abs() < 22050.0 / n_fft as f32,
+            "peak at {}",
+            peak
+        )
+    }
+}
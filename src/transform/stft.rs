@@ -0,0 +1,199 @@
+use num::Complex;
+
+use crate::transform::window::WindowFunction;
+
+// samples spanned by each frame of a short time fourier transform
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct WindowSize(pub usize);
+
+// samples between the starts of consecutive frames
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct HopSize(pub usize);
+
+impl HopSize {
+    // 0.0 = no overlap, 0.75 = 75% overlap between consecutive windows
+    pub fn from_overlap(window: WindowSize, overlap: f32) -> HopSize {
+        // a hop of zero would never move past the first frame
+        HopSize(
+            window
+                .0
+                .saturating_sub((window.0 as f32 * overlap) as usize)
+                .max(1),
+        )
+    }
+}
+
+// start of every frame which fits in the samples covered by n_windows windows laid end to end
+pub fn frame_starts(n_windows: usize, window: WindowSize, hop: HopSize) -> Vec<usize> {
+    if n_windows == 0 {
+        return vec![];
+    }
+    let n_frames = (n_windows - 1) * window.0 / hop.0 + 1;
+    (0..n_frames).map(|frame| frame * hop.0).collect()
+}
+
+// offsets of the windows which cover every sample of a frame span samples long, the last is
+// moved back to end with the frame rather than running past it. frames no longer than the
+// window are a single window from the start
+pub fn segment_starts(span: usize, window: WindowSize) -> Vec<usize> {
+    if span <= window.0 || window.0 == 0 {
+        return vec![0];
+    }
+    let mut starts: Vec<usize> = (0..=span - window.0).step_by(window.0).collect();
+    // never empty as span is longer than the window
+    if starts[starts.len() - 1] + window.0 < span {
+        starts.push(span - window.0);
+    }
+    starts
+}
+
+// where the windows of each column of a spectrogram fall and how they are weighted, shared by
+// Grid and SpectrogramBuilder so a matrix from one is what the other would have calculated
+pub struct ColumnLayout {
+    // first sample of each column
+    pub starts: Vec<usize>,
+    // offsets of the windows covering a column, see segment_starts
+    pub segments: Vec<usize>,
+    window_size: usize,
+    // one per sample of a frame, frames shorter than the window are zero padded after them
+    coefficients: Vec<f64>,
+}
+
+impl ColumnLayout {
+    // n_columns columns of span samples laid end to end before overlap is taken into account
+    pub fn new(
+        n_columns: usize,
+        span: usize,
+        hop: HopSize,
+        window_size: WindowSize,
+        window: WindowFunction,
+    ) -> ColumnLayout {
+        ColumnLayout {
+            starts: frame_starts(n_columns, WindowSize(span), hop),
+            segments: segment_starts(span, window_size),
+            window_size: window_size.0,
+            coefficients: window.coefficients(span.min(window_size.0)),
+        }
+    }
+
+    // one window of a column, weighted by the window function and zero padded to the window size
+    pub fn frame(&self, samples: &[i16], column: usize, segment: usize) -> Vec<f64> {
+        let start = self.starts[column] + self.segments[segment];
+        let mut frame: Vec<f64> = samples[start..start + self.coefficients.len()]
+            .iter()
+            .zip(self.coefficients.iter())
+            .map(|(sample, coefficient)| *sample as f64 * coefficient)
+            .collect();
+        frame.resize(self.window_size, 0.0);
+        frame
+    }
+}
+
+// magnitude of each bin averaged over the windows of a column
+pub fn mean_magnitudes(windows: &[Vec<Complex<f64>>]) -> Vec<f64> {
+    let mut magnitudes = vec![0.0; windows.first().map_or(0, |window| window.len())];
+    for window in windows {
+        for (magnitude, x) in magnitudes.iter_mut().zip(window.iter()) {
+            *magnitude += x.norm() / windows.len() as f64;
+        }
+    }
+    magnitudes
+}
+
+#[cfg(test)]
+mod test_hop_size {
+    use super::{HopSize, WindowSize};
+
+    #[test]
+    fn from_overlap() {
+        assert_eq!(HopSize(1024), HopSize::from_overlap(WindowSize(1024), 0.0));
+        assert_eq!(HopSize(256), HopSize::from_overlap(WindowSize(1024), 0.75));
+        assert_eq!(HopSize(7), HopSize::from_overlap(WindowSize(10), 0.3));
+    }
+
+    #[test]
+    fn overlap_past_the_window() {
+        assert_eq!(HopSize(1), HopSize::from_overlap(WindowSize(1024), 1.0));
+        assert_eq!(HopSize(1), HopSize::from_overlap(WindowSize(1024), 1.5));
+    }
+
+    #[test]
+    fn largest_slider_overlap() {
+        // 972.8 samples of overlap round down, leaving a hop of 52
+        assert_eq!(HopSize(52), HopSize::from_overlap(WindowSize(1024), 0.95));
+    }
+}
+
+#[cfg(test)]
+mod test_frame_starts {
+    use super::{frame_starts, HopSize, WindowSize};
+
+    #[test]
+    fn no_overlap() {
+        assert_eq!(vec![0, 4, 8], frame_starts(3, WindowSize(4), HopSize(4)));
+    }
+
+    #[test]
+    fn half_overlap() {
+        // one frame between each pair of non overlapping ones, the last still fits
+        let starts = frame_starts(3, WindowSize(4), HopSize(2));
+        assert_eq!(vec![0, 2, 4, 6, 8], starts);
+        assert!(starts.last().unwrap() + 4 <= 3 * 4);
+    }
+
+    #[test]
+    fn empty() {
+        assert!(frame_starts(0, WindowSize(4), HopSize(2)).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_segment_starts {
+    use super::{segment_starts, WindowSize};
+
+    #[test]
+    fn shorter_than_window() {
+        assert_eq!(vec![0], segment_starts(100, WindowSize(256)));
+        assert_eq!(vec![0], segment_starts(256, WindowSize(256)));
+    }
+
+    #[test]
+    fn whole_windows() {
+        assert_eq!(vec![0, 4, 8], segment_starts(12, WindowSize(4)));
+    }
+
+    #[test]
+    fn last_window_ends_with_span() {
+        assert_eq!(vec![0, 4, 6], segment_starts(10, WindowSize(4)));
+    }
+}
+
+#[cfg(test)]
+mod test_column_layout {
+    use super::{ColumnLayout, HopSize, WindowSize};
+    use crate::transform::window::WindowFunction;
+
+    #[test]
+    fn zero_padded() {
+        let layout = ColumnLayout::new(2, 4, HopSize(4), WindowSize(8), WindowFunction::Hann);
+        assert_eq!(vec![0, 4], layout.starts);
+        assert_eq!(vec![0], layout.segments);
+        let samples = [1, 2, 3, 4, 5, 6, 7, 8];
+        // hann weights of 0.0, 0.5, 1.0 and 0.5
+        let expected = [0.0, 3.0, 7.0, 4.0, 0.0, 0.0, 0.0, 0.0];
+        let frame = layout.frame(&samples, 1, 0);
+        assert_eq!(expected.len(), frame.len());
+        for (e, a) in expected.iter().zip(frame) {
+            assert!((e - a).abs() < 1e-12)
+        }
+    }
+
+    #[test]
+    fn longer_than_window() {
+        let layout =
+            ColumnLayout::new(1, 6, HopSize(6), WindowSize(4), WindowFunction::Rectangular);
+        let samples = [1, 2, 3, 4, 5, 6];
+        assert_eq!(vec![1.0, 2.0, 3.0, 4.0], layout.frame(&samples, 0, 0));
+        assert_eq!(vec![3.0, 4.0, 5.0, 6.0], layout.frame(&samples, 0, 1));
+    }
+}
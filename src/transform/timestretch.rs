@@ -0,0 +1,181 @@
+use std::f64::consts::{PI, TAU};
+
+use num::Complex;
+
+use crate::transform::window::WindowFunction;
+use crate::transform::{fourier_transform, inverse_fourier_transform, Backend};
+
+// frames are the next power of two above this duration
+const FRAME_SECONDS: f32 = 0.04;
+// frames are written this many times per frame length
+const OVERLAP: usize = 4;
+
+fn wrap_phase(phase: f64) -> f64 {
+    (phase + PI).rem_euclid(TAU) - PI
+}
+
+// phase vocoder, a stretch_factor of 2.0 is twice as long at the same pitch
+//
+// frames are read every frame_size / OVERLAP / stretch_factor samples and written every
+// frame_size / OVERLAP samples, the phase of each bin is advanced by the frequency
+// measured between consecutive frames so the stretched frames still line up
+pub fn time_stretch(
+    samples: &[i16],
+    sample_rate: u32,
+    stretch_factor: f32,
+    window: WindowFunction,
+    backend: Backend,
+) -> Vec<i16> {
+    if stretch_factor <= 0.0 || samples.is_empty() {
+        return vec![];
+    }
+    let frame_size = ((sample_rate as f32 * FRAME_SECONDS) as usize).next_power_of_two();
+    let synthesis_hop = frame_size / OVERLAP;
+    let analysis_hop = synthesis_hop as f64 / stretch_factor as f64;
+    let n_bins = frame_size / 2 + 1;
+
+    // zero padded so that the last samples fall in a whole frame
+    let mut padded: Vec<f64> = samples.iter().map(|x| *x as f64).collect();
+    let n_frames = ((samples.len() as f64 / analysis_hop).ceil() as usize).max(1);
+    let last_start = ((n_frames - 1) as f64 * analysis_hop).round() as usize;
+    padded.resize(last_start + frame_size, 0.0);
+
+    let coefficients = window.coefficients(frame_size);
+    let bin_frequencies: Vec<f64> = (0..n_bins)
+        .map(|k| TAU * k as f64 / frame_size as f64)
+        .collect();
+
+    let output_length = (n_frames - 1) * synthesis_hop + frame_size;
+    let mut output = vec![0.0; output_length];
+    let mut window_sum = vec![0.0; output_length];
+    let mut previous_phase = vec![0.0; n_bins];
+    let mut synthesis_phase = vec![0.0; n_bins];
+    let mut previous_start = 0;
+
+    for frame in 0..n_frames {
+        let start = (frame as f64 * analysis_hop).round() as usize;
+        let windowed: Vec<f64> = padded[start..start + frame_size]
+            .iter()
+            .zip(coefficients.iter())
+            .map(|(sample, coefficient)| sample * coefficient)
+            .collect();
+        let spectrum = fourier_transform(&windowed, backend);
+
+        let hop = (start - previous_start) as f64;
+        for (k, bin) in spectrum.iter().enumerate() {
+            let phase = bin.arg();
+            if frame == 0 {
+                synthesis_phase[k] = phase;
+            } else {
+                // deviation from the bin's own frequency, wrapped so it is the smallest one
+                let expected = bin_frequencies[k] * hop;
+                let frequency = if hop > 0.0 {
+                    bin_frequencies[k] + wrap_phase(phase - previous_phase[k] - expected) / hop
+                } else {
+                    bin_frequencies[k]
+                };
+                synthesis_phase[k] += frequency * synthesis_hop as f64;
+            }
+            previous_phase[k] = phase;
+        }
+        previous_start = start;
+
+        let stretched: Vec<Complex<f64>> = spectrum
+            .iter()
+            .zip(synthesis_phase.iter())
+            .map(|(bin, phase)| Complex::from_polar(bin.norm(), *phase))
+            .collect();
+        let resynthesized = inverse_fourier_transform(&stretched, frame_size, backend);
+
+        let offset = frame * synthesis_hop;
+        for (i, sample) in resynthesized.iter().enumerate() {
+            output[offset + i] += sample * coefficients[i];
+            window_sum[offset + i] += coefficients[i] * coefficients[i];
+        }
+    }
+
+    let stretched_length = (samples.len() as f64 * stretch_factor as f64).round() as usize;
+    output
+        .iter()
+        .zip(window_sum.iter())
+        .take(stretched_length)
+        .map(|(sample, sum)| if *sum > 1e-6 { sample / sum } else { 0.0 })
+        .map(|sample| sample.round().clamp(i16::MIN as f64, i16::MAX as f64) as i16)
+        .collect()
+}
+
+#[cfg(test)]
+mod test_time_stretch {
+    use super::time_stretch;
+    use crate::analysis::features::peak_frequency;
+    use crate::test_signals::sine;
+    use crate::transform::window::WindowFunction;
+    use crate::transform::{fourier_transform, Backend};
+
+    fn peak(samples: &[i16], sample_rate: u32) -> f32 {
+        let n_fft = 4096;
+        let magnitudes: Vec<f64> = fourier_transform(&samples[..n_fft], Backend::default())
+            .iter()
+            .map(|x| x.norm())
+            .collect();
+        peak_frequency(&magnitudes, sample_rate, n_fft)
+    }
+
+    #[test]
+    fn twice_as_long() {
+        let stretched = time_stretch(
+            &sine(440.0, 8000, 8000),
+            8000,
+            2.0,
+            WindowFunction::Hann,
+            Backend::default(),
+        );
+        assert_eq!(16000, stretched.len())
+    }
+
+    #[test]
+    fn half_as_long() {
+        let stretched = time_stretch(
+            &sine(440.0, 8000, 8000),
+            8000,
+            0.5,
+            WindowFunction::Hann,
+            Backend::default(),
+        );
+        assert_eq!(4000, stretched.len())
+    }
+
+    #[test]
+    fn same_pitch() {
+        let stretched = time_stretch(
+            &sine(440.0, 8000, 8000),
+            8000,
+            2.0,
+            WindowFunction::Hann,
+            Backend::default(),
+        );
+        // 8000 / 4096 Hz per bin, well within one bin after interpolation
+        assert!((peak(&stretched[1000..], 8000) - 440.0).abs() < 2.0)
+    }
+
+    #[test]
+    fn unstretched_is_nearly_unchanged() {
+        let samples = sine(440.0, 8000, 8000);
+        let stretched = time_stretch(
+            &samples,
+            8000,
+            1.0,
+            WindowFunction::Hann,
+            Backend::default(),
+        );
+        // the first frame is only covered by the rising half of the window
+        for (expected, actual) in samples.iter().zip(stretched.iter()).skip(512).take(6000) {
+            assert!((expected - actual).abs() <= 2, "{} != {}", expected, actual)
+        }
+    }
+
+    #[test]
+    fn empty() {
+        assert!(time_stretch(&[], 8000, 2.0, WindowFunction::Hann, Backend::default()).is_empty())
+    }
+}
@@ -0,0 +1,54 @@
+use std::f64::consts::TAU;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum WindowFunction {
+    Rectangular,
+    Hann,
+    Hamming,
+    Blackman,
+}
+
+impl WindowFunction {
+    // periodic form, which overlaps evenly when hopping by a fraction of n
+    pub fn coefficients(&self, n: usize) -> Vec<f64> {
+        (0..n)
+            .map(|i| {
+                let phase = TAU * i as f64 / n as f64;
+                match self {
+                    WindowFunction::Rectangular => 1.0,
+                    WindowFunction::Hann => 0.5 - 0.5 * phase.cos(),
+                    WindowFunction::Hamming => 0.54 - 0.46 * phase.cos(),
+                    WindowFunction::Blackman => {
+                        0.42 - 0.5 * phase.cos() + 0.08 * (2.0 * phase).cos()
+                    }
+                }
+            })
+            .collect()
+    }
+}
+
+#[cfg(test)]
+mod test_coefficients {
+    use super::WindowFunction;
+
+    #[test]
+    fn hann() {
+        let expected = [0.0, 0.5, 1.0, 0.5];
+        for (e, a) in expected.iter().zip(WindowFunction::Hann.coefficients(4)) {
+            assert!((e - a).abs() < 1e-12)
+        }
+    }
+
+    #[test]
+    fn hann_overlap_adds_to_constant() {
+        let window = WindowFunction::Hann.coefficients(8);
+        for i in 0..4 {
+            assert!((window[i] + window[i + 4] - 1.0).abs() < 1e-12)
+        }
+    }
+
+    #[test]
+    fn rectangular() {
+        assert_eq!(vec![1.0; 3], WindowFunction::Rectangular.coefficients(3))
+    }
+}
@@ -1,53 +1,163 @@
+use std::cmp::Ordering;
+use std::error::Error;
+use std::fmt;
+use std::mem;
+use std::ops::Add;
+use std::ptr;
 use std::time::Duration;
 
-const C0_FREQ: f32 = 16.35;
+const A4_FREQ: f32 = 440.0;
+// semitones from C0 up to A4
+const A4_FROM_C0: f32 = 57.0;
+// about 16.35 Hz
+const C0_FREQ: f32 = 16.351598;
+// frequencies more than an octave below C0 are shown as this rather than as a note
+pub const DC: &str = "DC";
 const NOTES: &'static [&'static str] = &[
-    "C", "C#/Db", "D", "D#/Eb", "E", "F", "F#/Gb", "G", "G#/Ab", "A", "A#/Bb", "B", "C",
+    "C", "C#/Db", "D", "D#/Eb", "E", "F", "F#/Gb", "G", "G#/Ab", "A", "A#/Bb", "B",
 ];
 
-fn freq_to_note(freq_hz: f32) -> String {
-    let c0_distance = (freq_hz / C0_FREQ).log2().max(0.0);
-    let semitones_from_c = (c0_distance.fract() * 12.0).round() as usize;
-    let note = NOTES[semitones_from_c];
-    if semitones_from_c == 12 {
-        format!("{}{}", note, c0_distance.floor() + 1.0)
-    } else {
-        format!("{}{}", note, c0_distance.floor())
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Note {
+    pub name: &'static str,
+    pub octave: i32,
+    // deviation from the nearest note, between -50.0 and 50.0
+    pub cents: f32,
+}
+
+// nearest note, None more than an octave below C0 (including 0 Hz) where it is shown as DC.
+// the octave below C0 is octave -1
+pub fn freq_to_note_struct(hz: f32) -> Option<Note> {
+    if hz.is_nan() || hz < C0_FREQ / 2.0 {
+        return None;
     }
+    // measured from A4 so that 440 Hz is exactly in tune
+    let semitones = 12.0 * (hz / A4_FREQ).log2() + A4_FROM_C0;
+    let nearest = semitones.round();
+    let n = nearest as i32;
+    Some(Note {
+        name: NOTES[n.rem_euclid(12) as usize],
+        octave: n.div_euclid(12),
+        cents: (semitones - nearest) * 100.0,
+    })
 }
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub fn freq_to_note(freq_hz: f32) -> String {
+    match freq_to_note_struct(freq_hz) {
+        Some(note) => format!("{}{}", note.name, note.octave),
+        None => DC.to_string(),
+    }
+}
+
+// midi note 60 is C4
+pub fn midi_to_note(note: u8) -> String {
+    format!("{}{}", NOTES[note as usize % 12], note as i32 / 12 - 1)
+}
+
+#[derive(Clone, Copy, Debug)]
 pub enum Unit {
     Second,
     Hz,
     Note,
+    // for units without a variant of their own, eg. Mel or ERB numbers, given the value
+    // on the scale
+    Custom(fn(f32) -> String),
 }
 
+// custom units are equal when their formatters share an address, a function may have more
+// than one so this can miss but never wrongly matches
+impl PartialEq for Unit {
+    fn eq(&self, other: &Unit) -> bool {
+        match (self, other) {
+            (Unit::Custom(a), Unit::Custom(b)) => ptr::fn_addr_eq(*a, *b),
+            _ => mem::discriminant(self) == mem::discriminant(other),
+        }
+    }
+}
+
+impl Eq for Unit {}
+
 #[derive(Clone, Copy, Debug, PartialEq, Eq)]
 pub enum Mapping {
     Linear,
     Log10,
 }
 
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RangeError {
+    pub min: f32,
+    pub max: f32,
+}
+
+impl fmt::Display for RangeError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(
+            f,
+            "range minimum {} must be less than its maximum {}",
+            self.min, self.max
+        )
+    }
+}
+
+impl Error for RangeError {}
+
+// the span of a time or frequency scale, guaranteed to satisfy min < max
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ScaleRange {
+    min: f32,
+    max: f32,
+}
+
+impl ScaleRange {
+    pub fn new(min: f32, max: f32) -> Result<ScaleRange, RangeError> {
+        // written so NaN is rejected as well
+        if min < max {
+            Ok(ScaleRange { min, max })
+        } else {
+            Err(RangeError { min, max })
+        }
+    }
+
+    pub fn min(&self) -> f32 {
+        self.min
+    }
+
+    pub fn max(&self) -> f32 {
+        self.max
+    }
+}
+
 pub fn normalize(value: f32, scale: &Scale) -> f32 {
-    match scale.mapping {
-        Mapping::Linear => (value - scale.min) / (scale.max - scale.min),
+    let range = scale.range;
+    let normalized = match scale.mapping {
+        Mapping::Linear => (value - range.min) / (range.max - range.min),
         Mapping::Log10 => {
-            let min = scale.min.log10().max(0.0);
-            (value.log10().max(0.0) - min) / (scale.max.log10() - min)
+            let min = scale.log10_min();
+            (value.max(scale.log_floor).log10() - min) / (range.max.log10() - min)
         }
+    };
+    if scale.reversed {
+        1.0 - normalized
+    } else {
+        normalized
     }
 }
 
 // assumes normalized is between 0.0 and 1.0
 // iced_audio::core::normal::Normal would be a more correct type
 pub fn map_normalized(normalized: f32, scale: &Scale) -> f32 {
+    let range = scale.range;
+    let normalized = if scale.reversed {
+        1.0 - normalized
+    } else {
+        normalized
+    };
     match scale.mapping {
-        Mapping::Linear => scale.min + normalized * (scale.max - scale.min),
-        Mapping::Log10 => f32::powf(
-            10.0,
-            normalized * (scale.max.log10() - scale.min.log10().max(0.0)),
-        ),
+        Mapping::Linear => range.min + normalized * (range.max - range.min),
+        Mapping::Log10 => {
+            let min = scale.log10_min();
+            f32::powf(10.0, min + normalized * (range.max.log10() - min))
+        }
     }
 }
 
@@ -55,19 +165,165 @@ pub fn format_unit(f: f32, unit: &Unit) -> String {
     match unit {
         Unit::Second => format!("{:?}", Duration::from_millis((f * 1000.0) as u64)),
         Unit::Hz => f.round().to_string() + " Hz",
-        Unit::Note => freq_to_note(f),
+        Unit::Custom(formatter) => formatter(f),
+        Unit::Note => match freq_to_note_struct(f) {
+            Some(note) => format!("{}{} {:+.0}¢", note.name, note.octave, note.cents),
+            None => DC.to_string(),
+        },
     }
 }
 
-#[derive(Clone)]
+// precision only applies to Hz, the other units have their own formatting
+pub fn format_unit_with_precision(f: f32, unit: &Unit, decimal_places: usize) -> String {
+    match unit {
+        Unit::Hz => format!("{:.*} Hz", decimal_places, f),
+        _ => format_unit(f, unit),
+    }
+}
+
+// low frequencies lose meaningful information when rounded to the nearest Hz
+pub fn auto_precision(f: f32) -> usize {
+    if f < 100.0 {
+        1
+    } else {
+        0
+    }
+}
+
+// steps between nice numbers are 1, 2 or 5 times a power of ten, as in matplotlib's MaxNLocator
+const NICE_STEPS: [f32; 4] = [1.0, 2.0, 5.0, 10.0];
+// most intervals a nice step divides a range into
+const NICE_BINS: f32 = 10.0;
+
+// smallest nice step which divides span into at most NICE_BINS intervals
+fn nice_step(span: f32) -> f32 {
+    let raw = span / NICE_BINS;
+    let magnitude = 10f32.powf(raw.log10().floor());
+    NICE_STEPS
+        .iter()
+        .map(|multiple| multiple * magnitude)
+        .find(|step| *step >= raw)
+        .unwrap_or(10.0 * magnitude)
+}
+
+#[derive(Clone, Debug, PartialEq)]
 pub struct Scale {
     pub unit: Unit,
-    pub min: f32,
-    pub max: f32,
+    pub range: ScaleRange,
     pub mapping: Mapping,
+    // log10 of zero is -inf, so Log10 mappings start at this frequency instead
+    // 1 Hz keeps the sub audible decades off of axes which start at 0 Hz
+    pub log_floor: f32,
+    // max at the bottom of vertical axes and the left of horizontal ones
+    pub reversed: bool,
+}
+
+impl Default for Scale {
+    fn default() -> Self {
+        Scale {
+            unit: Unit::Hz,
+            range: ScaleRange { min: 0.0, max: 1.0 },
+            mapping: Mapping::Linear,
+            log_floor: 1.0,
+            reversed: false,
+        }
+    }
 }
 
 impl Scale {
+    pub fn with_unit(mut self, unit: Unit) -> Self {
+        self.unit = unit;
+        self
+    }
+
+    pub fn with_range(mut self, range: ScaleRange) -> Self {
+        self.range = range;
+        self
+    }
+
+    pub fn with_mapping(mut self, mapping: Mapping) -> Self {
+        self.mapping = mapping;
+        self
+    }
+
+    pub fn with_log_floor(mut self, log_floor: f32) -> Self {
+        self.log_floor = log_floor;
+        self
+    }
+
+    // swaps which end of the axis min and max are drawn at, the range itself keeps min < max
+    pub fn reversed(&self) -> Scale {
+        Scale {
+            reversed: !self.reversed,
+            ..self.clone()
+        }
+    }
+
+    // narrows (factor < 1.0) or widens (factor > 1.0) the range around anchor,
+    // keeping anchor at the same position on screen
+    pub fn zoom(&self, anchor: f32, factor: f32) -> Result<ScaleRange, RangeError> {
+        // the zoomed range does not depend on which way the axis is drawn
+        let scale = Scale {
+            reversed: false,
+            ..self.clone()
+        };
+        let position = normalize(anchor, &scale);
+        ScaleRange::new(
+            map_normalized(position - position * factor, &scale),
+            map_normalized(position + (1.0 - position) * factor, &scale),
+        )
+    }
+
+    // the range widened out to multiples of a nice step, eg. 0 to 22050 Hz becomes 0 to 25000 Hz
+    pub fn human_readable_range(&self) -> (f32, f32) {
+        let step = nice_step(self.range.max - self.range.min);
+        (
+            (self.range.min / step).floor() * step,
+            (self.range.max / step).ceil() * step,
+        )
+    }
+
+    // multiples of the step of human_readable_range split into subdivisions, which fall
+    // inside the range, ascending
+    pub fn human_readable_values(&self, subdivisions: usize) -> Vec<f32> {
+        let (min, max) = self.human_readable_range();
+        let step = nice_step(self.range.max - self.range.min) / subdivisions.max(1) as f32;
+        // multiples which only miss the range through rounding error are kept
+        let tolerance = step * 1e-3;
+        let n_steps = ((max - min) / step).round() as usize;
+        (0..=n_steps)
+            .map(|i| min + i as f32 * step)
+            .filter(|value| {
+                *value >= self.range.min - tolerance && *value <= self.range.max + tolerance
+            })
+            .collect()
+    }
+
+    // width of the range in the unit for Linear mappings and in decades for Log10 ones
+    pub fn span(&self) -> f32 {
+        match self.mapping {
+            Mapping::Linear => self.range.max - self.range.min,
+            Mapping::Log10 => self.range.max.log10() - self.log10_min(),
+        }
+    }
+
+    fn log10_min(&self) -> f32 {
+        self.range.min.max(self.log_floor).log10()
+    }
+
+    // nearest of evenly_spaced_values(tick_count, false), eg. to place markers on ticks
+    pub fn snap_to_grid(&self, value: f32, tick_count: usize) -> f32 {
+        self.evenly_spaced_values(tick_count, false)
+            .into_iter()
+            .min_by(|a, b| {
+                (a - value)
+                    .abs()
+                    .partial_cmp(&(b - value).abs())
+                    .unwrap_or(std::cmp::Ordering::Equal)
+            })
+            .unwrap_or(value)
+    }
+
     pub fn evenly_spaced_values(&self, n: usize, start_at_zero: bool) -> Vec<f32> {
         let n_steps: f32;
         if start_at_zero {
@@ -75,18 +331,57 @@ impl Scale {
         } else {
             n_steps = n as f32;
         }
-        match self.mapping {
+        let range = self.range;
+        let mut values: Vec<f32> = match self.mapping {
             Mapping::Linear => {
-                let step = (self.max - self.min) / n_steps;
-                (0..n).map(|i| self.min + (i as f32 * step)).collect()
+                let step = (range.max - range.min) / n_steps;
+                (0..n).map(|i| range.min + (i as f32 * step)).collect()
             }
             Mapping::Log10 => {
-                let step = (self.max.log10() - self.min.log10().max(0.0)) / n_steps;
+                let min = self.log10_min();
+                let step = (range.max.log10() - min) / n_steps;
                 (0..n)
-                    .map(|i| f32::powf(10.0, self.min + (i as f32 * step)))
+                    .map(|i| f32::powf(10.0, min + (i as f32 * step)))
                     .collect()
             }
+        };
+        // in the order they are drawn along the axis
+        if self.reversed {
+            values.reverse();
         }
+        values
+    }
+
+    // by span alone, eg. to pick the broadest of several files' ranges, spans of Linear and
+    // Log10 mappings are in different units so those are never compared
+    pub fn span_cmp(&self, other: &Scale) -> Option<Ordering> {
+        if self.mapping != other.mapping {
+            return None;
+        }
+        self.span().partial_cmp(&other.span())
+    }
+}
+
+// by span alone, see span_cmp. different ranges of the same span compare Equal, so sorting
+// files' ranges never fails on a tie, but a Linear and a Log10 scale are never ordered and
+// sort_by(|a, b| a.partial_cmp(b).unwrap()) panics on a mix of them
+impl PartialOrd for Scale {
+    fn partial_cmp(&self, other: &Scale) -> Option<Ordering> {
+        self.span_cmp(other)
+    }
+}
+
+// the union of both ranges, eg. a shared axis for files of different sample rates, with the
+// unit, mapping, log floor and direction of the left hand side
+impl Add for Scale {
+    type Output = Scale;
+
+    fn add(self, other: Scale) -> Scale {
+        let range = ScaleRange {
+            min: self.range.min.min(other.range.min),
+            max: self.range.max.max(other.range.max),
+        };
+        self.with_range(range)
     }
 }
 
@@ -127,6 +422,498 @@ mod test_freq_to_note {
 
     #[test]
     fn zero_frequency() {
-        assert_eq!("C0", freq_to_note(0.0))
+        assert_eq!("DC", freq_to_note(0.0))
+    }
+
+    #[test]
+    fn below_c0() {
+        // the octave below C0 is named rather than rounded up to C0
+        assert_eq!("C0", freq_to_note(16.35));
+        assert_eq!("B-1", freq_to_note(15.5));
+        assert_eq!("D-1", freq_to_note(9.0));
+        assert_eq!("DC", freq_to_note(8.0));
+    }
+}
+
+#[cfg(test)]
+mod test_freq_to_note_struct {
+    use super::{format_unit, freq_to_note_struct, Note, Unit};
+
+    #[test]
+    fn a4_in_tune() {
+        assert_eq!(0.0, freq_to_note_struct(440.0).unwrap().cents)
+    }
+
+    #[test]
+    fn sharp_a4() {
+        let note = freq_to_note_struct(450.0).unwrap();
+        assert_eq!(("A", 4), (note.name, note.octave));
+        assert!((note.cents - 38.9).abs() < 0.1)
+    }
+
+    #[test]
+    fn flat_rounds_up_to_next_octave() {
+        // 20 cents below C5
+        let note = freq_to_note_struct(523.25 * 2f32.powf(-0.2 / 12.0)).unwrap();
+        assert_eq!(("C", 5), (note.name, note.octave));
+        assert!((note.cents + 20.0).abs() < 0.1)
+    }
+
+    #[test]
+    fn zero_frequency() {
+        assert_eq!(None, freq_to_note_struct(0.0))
+    }
+
+    #[test]
+    fn below_c0() {
+        // ten semitones below C0, not clamped to it
+        let note = freq_to_note_struct(9.0).unwrap();
+        assert_eq!(
+            Note {
+                name: "D",
+                octave: -1,
+                cents: note.cents
+            },
+            note
+        );
+        assert!((note.cents + 33.7).abs() < 0.1, "{}", note.cents);
+        assert_eq!("D-1 -34¢", format_unit(9.0, &Unit::Note))
+    }
+
+    #[test]
+    fn formatted() {
+        assert_eq!("A4 +39¢", format_unit(450.0, &Unit::Note))
+    }
+
+    #[test]
+    fn formatted_dc() {
+        assert_eq!("DC", format_unit(0.0, &Unit::Note))
+    }
+}
+
+#[cfg(test)]
+mod test_midi_to_note {
+    use super::midi_to_note;
+
+    #[test]
+    fn a4() {
+        assert_eq!("A4", midi_to_note(69))
+    }
+
+    #[test]
+    fn lowest() {
+        assert_eq!("C-1", midi_to_note(0))
+    }
+
+    #[test]
+    fn highest() {
+        assert_eq!("G9", midi_to_note(127))
+    }
+}
+
+#[cfg(test)]
+mod test_format_unit_custom {
+    use super::{format_unit, format_unit_with_precision, Unit};
+
+    fn octaves_from_a4(hz: f32) -> String {
+        format!("{:+.1} oct", (hz / 440.0).log2())
+    }
+
+    #[test]
+    fn custom() {
+        let unit = Unit::Custom(octaves_from_a4);
+        assert_eq!("+1.0 oct", format_unit(880.0, &unit));
+        assert_eq!("-2.0 oct", format_unit(110.0, &unit));
+    }
+
+    #[test]
+    fn closure() {
+        let unit = Unit::Custom(|value| format!("{} mel", value as u32));
+        assert_eq!("12 mel", format_unit(12.7, &unit));
+    }
+
+    #[test]
+    fn equality() {
+        assert_eq!(Unit::Custom(octaves_from_a4), Unit::Custom(octaves_from_a4));
+        assert_ne!(Unit::Custom(octaves_from_a4), Unit::Hz);
+        assert_eq!(Unit::Note, Unit::Note);
+    }
+
+    #[test]
+    fn precision_is_left_to_the_formatter() {
+        let unit = Unit::Custom(octaves_from_a4);
+        assert_eq!("+1.0 oct", format_unit_with_precision(880.0, &unit, 3));
+    }
+}
+
+#[cfg(test)]
+mod test_format_unit_with_precision {
+    use super::{auto_precision, format_unit_with_precision, Unit};
+
+    #[test]
+    fn a0() {
+        assert_eq!("27.5 Hz", format_unit_with_precision(27.5, &Unit::Hz, 1))
+    }
+
+    #[test]
+    fn no_decimals() {
+        assert_eq!(
+            "12000 Hz",
+            format_unit_with_precision(12000.4, &Unit::Hz, 0)
+        )
+    }
+
+    #[test]
+    fn ignored_for_notes() {
+        assert_eq!("A4 +0¢", format_unit_with_precision(440.0, &Unit::Note, 3))
+    }
+
+    #[test]
+    fn automatic() {
+        assert_eq!(
+            (1, 0, 0),
+            (
+                auto_precision(27.5),
+                auto_precision(440.0),
+                auto_precision(12000.0)
+            )
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_log10_evenly_spaced_values {
+    use super::{map_normalized, normalize, Mapping, Scale, ScaleRange};
+
+    fn scale(min: f32, max: f32) -> Scale {
+        Scale::default()
+            .with_range(ScaleRange::new(min, max).unwrap())
+            .with_mapping(Mapping::Log10)
+    }
+
+    fn assert_close(expected: &[f32], actual: &[f32]) {
+        assert_eq!(expected.len(), actual.len());
+        for (e, a) in expected.iter().zip(actual) {
+            assert!(
+                (e - a).abs() <= e * 1e-4,
+                "expected {:?}, found {:?}",
+                expected,
+                actual
+            )
+        }
+    }
+
+    #[test]
+    fn min_zero_starts_at_log_floor() {
+        let values = scale(0.0, 1000.0).evenly_spaced_values(4, true);
+        assert_close(&[1.0, 10.0, 100.0, 1000.0], &values)
+    }
+
+    #[test]
+    fn min_zero_with_smaller_floor() {
+        let values = scale(0.0, 1000.0)
+            .with_log_floor(1e-3)
+            .evenly_spaced_values(3, true);
+        assert_close(&[1e-3, 1.0, 1000.0], &values)
+    }
+
+    #[test]
+    fn min_one() {
+        let values = scale(1.0, 10000.0).evenly_spaced_values(5, true);
+        assert_close(&[1.0, 10.0, 100.0, 1000.0, 10000.0], &values)
+    }
+
+    #[test]
+    fn min_twenty() {
+        // log10(20) = 1.30103, log10(20000) = 4.30103
+        let values = scale(20.0, 20000.0).evenly_spaced_values(4, true);
+        assert_close(&[20.0, 200.0, 2000.0, 20000.0], &values)
+    }
+
+    #[test]
+    fn min_hundred_without_endpoint() {
+        let values = scale(100.0, 10000.0).evenly_spaced_values(2, false);
+        assert_close(&[100.0, 1000.0], &values)
+    }
+
+    #[test]
+    fn normalize_round_trip() {
+        let scale = scale(20.0, 20000.0);
+        assert!((normalize(2000.0, &scale) - 2.0 / 3.0).abs() < 1e-5);
+        assert!((map_normalized(2.0 / 3.0, &scale) - 2000.0).abs() < 0.1);
+    }
+}
+
+#[cfg(test)]
+mod test_human_readable {
+    use super::{Scale, ScaleRange};
+
+    fn scale(min: f32, max: f32) -> Scale {
+        Scale::default().with_range(ScaleRange::new(min, max).unwrap())
+    }
+
+    #[test]
+    fn nyquist() {
+        assert_eq!((0.0, 25000.0), scale(0.0, 22050.0).human_readable_range());
+    }
+
+    #[test]
+    fn rounds_min_down() {
+        assert_eq!((0.0, 20000.0), scale(20.0, 20000.0).human_readable_range());
+        assert_eq!((1.0, 3.5), scale(1.23, 3.37).human_readable_range());
+    }
+
+    #[test]
+    fn already_nice() {
+        assert_eq!((0.0, 1.0), scale(0.0, 1.0).human_readable_range());
+    }
+
+    #[test]
+    fn values_inside_the_range() {
+        assert_eq!(
+            vec![0.0, 5000.0, 10000.0, 15000.0, 20000.0],
+            scale(0.0, 22050.0).human_readable_values(1)
+        );
+        // steps of 500 Hz, halved
+        assert_eq!(
+            vec![8500.0, 8750.0, 9000.0, 9250.0],
+            scale(8500.0, 12500.0).human_readable_values(2)[..4]
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_snap_to_grid {
+    use super::{Mapping, Scale, ScaleRange};
+
+    fn scale() -> Scale {
+        Scale::default().with_range(ScaleRange::new(0.0, 22050.0).unwrap())
+    }
+
+    #[test]
+    fn nearest_tick() {
+        assert_eq!(0.0, scale().snap_to_grid(441.0, 10));
+        assert_eq!(2205.0, scale().snap_to_grid(1500.0, 10));
+        assert_eq!(6615.0, scale().snap_to_grid(7000.0, 10));
+    }
+
+    #[test]
+    fn past_the_last_tick() {
+        assert_eq!(19845.0, scale().snap_to_grid(22050.0, 10))
+    }
+
+    #[test]
+    fn log10() {
+        let scale = Scale::default()
+            .with_range(ScaleRange::new(10.0, 10000.0).unwrap())
+            .with_mapping(Mapping::Log10);
+        assert!((scale.snap_to_grid(120.0, 3) - 100.0).abs() < 0.01)
+    }
+
+    #[test]
+    fn no_ticks() {
+        assert_eq!(441.0, scale().snap_to_grid(441.0, 0))
+    }
+}
+
+#[cfg(test)]
+mod test_zoom {
+    use super::{Mapping, Scale, ScaleRange};
+
+    #[test]
+    fn linear_around_center() {
+        let scale = Scale::default().with_range(ScaleRange::new(0.0, 1000.0).unwrap());
+        assert_eq!(ScaleRange::new(250.0, 750.0), scale.zoom(500.0, 0.5))
+    }
+
+    #[test]
+    fn anchor_stays_in_place() {
+        let scale = Scale::default().with_range(ScaleRange::new(0.0, 1000.0).unwrap());
+        let range = scale.zoom(100.0, 0.5).unwrap();
+        assert_eq!((50.0, 550.0), (range.min, range.max))
+    }
+
+    #[test]
+    fn log10() {
+        let scale = Scale::default()
+            .with_range(ScaleRange::new(10.0, 100000.0).unwrap())
+            .with_mapping(Mapping::Log10);
+        let range = scale.zoom(1000.0, 0.5).unwrap();
+        assert!((range.min - 100.0).abs() < 0.01 && (range.max - 10000.0).abs() < 1.0)
+    }
+}
+
+#[cfg(test)]
+mod test_scale_ordering {
+    use super::{Mapping, Scale, ScaleRange};
+    use std::cmp::Ordering;
+
+    fn scale(min: f32, max: f32, mapping: Mapping) -> Scale {
+        Scale::default()
+            .with_range(ScaleRange::new(min, max).unwrap())
+            .with_mapping(mapping)
+    }
+
+    #[test]
+    fn linear_by_span() {
+        let mut scales = [
+            scale(0.0, 22050.0, Mapping::Linear),
+            scale(1000.0, 2000.0, Mapping::Linear),
+            scale(0.0, 8000.0, Mapping::Linear),
+        ];
+        scales.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        let maxes: Vec<f32> = scales.iter().map(|scale| scale.range.max).collect();
+        assert_eq!(vec![2000.0, 8000.0, 22050.0], maxes);
+    }
+
+    #[test]
+    fn log10_by_decades() {
+        // three decades against a little over two, though the second is wider in Hz
+        let audible = scale(20.0, 20000.0, Mapping::Log10);
+        let high = scale(1000.0, 200000.0, Mapping::Log10);
+        assert!(audible > high);
+        assert!((audible.span() - 3.0).abs() < 1e-6);
+    }
+
+    #[test]
+    fn equal_spans() {
+        let low = scale(0.0, 100.0, Mapping::Linear);
+        let high = scale(100.0, 200.0, Mapping::Linear);
+        assert_eq!(Some(Ordering::Equal), low.span_cmp(&high));
+        assert_eq!(Some(Ordering::Equal), low.partial_cmp(&high));
+        assert!(low != high);
+
+        let mut scales = [high.clone(), scale(0.0, 50.0, Mapping::Linear), low.clone()];
+        scales.sort_by(|a, b| a.partial_cmp(b).unwrap());
+        // a stable sort keeps ties in their original order
+        let mins: Vec<f32> = scales.iter().map(|scale| scale.range.min).collect();
+        assert_eq!(vec![0.0, 100.0, 0.0], mins);
+    }
+
+    // spans in Hz and in decades can not be compared
+    #[test]
+    fn mixed_mappings() {
+        let linear = scale(1.0, 100.0, Mapping::Linear);
+        let log10 = scale(1.0, 100.0, Mapping::Log10);
+        assert_eq!(None, linear.span_cmp(&log10));
+        assert_eq!(None, linear.partial_cmp(&log10));
+    }
+}
+
+#[cfg(test)]
+mod test_scale_add {
+    use super::{Mapping, Scale, ScaleRange, Unit};
+
+    fn scale(min: f32, max: f32) -> Scale {
+        Scale::default().with_range(ScaleRange::new(min, max).unwrap())
+    }
+
+    #[test]
+    fn overlapping() {
+        assert_eq!(
+            scale(0.0, 2000.0),
+            scale(0.0, 1000.0) + scale(500.0, 2000.0)
+        );
+    }
+
+    #[test]
+    fn disjoint() {
+        assert_eq!(
+            scale(0.0, 4000.0),
+            scale(3000.0, 4000.0) + scale(0.0, 1000.0)
+        );
+    }
+
+    #[test]
+    fn left_hand_side() {
+        let left = scale(20.0, 8000.0)
+            .with_unit(Unit::Note)
+            .with_mapping(Mapping::Log10)
+            .reversed();
+        let expected = left
+            .clone()
+            .with_range(ScaleRange::new(0.0, 22050.0).unwrap());
+        assert_eq!(expected, left + scale(0.0, 22050.0).with_unit(Unit::Second));
+    }
+}
+
+#[cfg(test)]
+mod test_reversed {
+    use super::{map_normalized, normalize, Scale, ScaleRange};
+
+    fn scale() -> Scale {
+        Scale::default().with_range(ScaleRange::new(100.0, 1100.0).unwrap())
+    }
+
+    #[test]
+    fn normalize_flips() {
+        let reversed = scale().reversed();
+        assert_eq!(1.0, normalize(100.0, &reversed));
+        assert_eq!(0.0, normalize(1100.0, &reversed));
+        assert_eq!(0.75, normalize(350.0, &reversed));
+    }
+
+    #[test]
+    fn map_normalized_round_trip() {
+        let reversed = scale().reversed();
+        assert_eq!(1100.0, map_normalized(0.0, &reversed));
+        assert_eq!(
+            350.0,
+            map_normalized(normalize(350.0, &reversed), &reversed)
+        );
+    }
+
+    #[test]
+    fn twice_is_unchanged() {
+        assert!(!scale().reversed().reversed().reversed);
+        assert_eq!(0.25, normalize(350.0, &scale().reversed().reversed()));
+    }
+
+    #[test]
+    fn evenly_spaced_values() {
+        assert_eq!(
+            vec![1100.0, 600.0, 100.0],
+            scale().reversed().evenly_spaced_values(3, true)
+        )
+    }
+
+    #[test]
+    fn zoom_ignores_direction() {
+        assert_eq!(
+            scale().zoom(300.0, 0.5),
+            scale().reversed().zoom(300.0, 0.5)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_scale_range {
+    use super::{RangeError, ScaleRange};
+
+    #[test]
+    fn valid() {
+        let range = ScaleRange::new(20.0, 20000.0).unwrap();
+        assert_eq!((20.0, 20000.0), (range.min, range.max))
+    }
+
+    #[test]
+    fn equal_bounds() {
+        assert_eq!(
+            Err(RangeError {
+                min: 100.0,
+                max: 100.0
+            }),
+            ScaleRange::new(100.0, 100.0)
+        )
+    }
+
+    #[test]
+    fn reversed_bounds() {
+        assert!(ScaleRange::new(1000.0, 10.0).is_err())
+    }
+
+    #[test]
+    fn nan() {
+        assert!(ScaleRange::new(f32::NAN, 10.0).is_err())
     }
 }
@@ -0,0 +1,179 @@
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry, Path, Stroke},
+    Color, Element, Length, Point, Rectangle, Size,
+};
+
+use crate::messages::Message;
+use crate::units::Scale;
+use crate::widgets::grid::bin_edges;
+
+// fraction of each held peak kept per update, 0.99 at 20 updates per second
+// halves a peak in about three and a half seconds
+pub const PEAK_HOLD_DECAY: f64 = 0.99;
+
+// horizontal bar chart of the time averaged magnitude of each frequency bin
+pub struct AverageSpectrum {
+    spectrum: Vec<f64>,
+    // loudest magnitude seen in each bin, decaying with every update, empty until updated
+    peak_hold: Vec<f64>,
+    pub decay_rate: f64,
+    pub sample_rate: u32,
+    pub y: Scale,
+    fill_proportion: u16,
+    pub cache: Cache,
+}
+
+impl AverageSpectrum {
+    pub fn new(
+        spectrum: &[f64],
+        sample_rate: u32,
+        y: Scale,
+        fill_proportion: u16,
+    ) -> AverageSpectrum {
+        AverageSpectrum {
+            spectrum: spectrum.to_vec(),
+            peak_hold: vec![],
+            decay_rate: PEAK_HOLD_DECAY,
+            sample_rate,
+            y,
+            fill_proportion,
+            cache: Cache::new(),
+        }
+    }
+
+    pub fn update_spectrum(&mut self, spectrum: &[f64]) {
+        self.spectrum = spectrum.to_vec();
+        self.cache.clear();
+    }
+
+    // folds the current spectrum into the held peaks, eg. once per live frame
+    pub fn update_peak_hold(&mut self) {
+        // a different window size changes the bins, so nothing held still applies
+        if self.peak_hold.len() != self.spectrum.len() {
+            self.peak_hold = vec![0.0; self.spectrum.len()];
+        }
+        for (peak, current) in self.peak_hold.iter_mut().zip(self.spectrum.iter()) {
+            *peak = peak.max(*current) * self.decay_rate;
+        }
+        self.cache.clear();
+    }
+
+    pub fn reset_peak_hold(&mut self) {
+        for peak in self.peak_hold.iter_mut() {
+            *peak = 0.0;
+        }
+        self.cache.clear();
+    }
+
+    pub fn peak_hold(&self) -> &[f64] {
+        &self.peak_hold
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let fill_proportion = self.fill_proportion;
+        Canvas::new(self)
+            .width(Length::FillPortion(fill_proportion))
+            .height(Length::FillPortion(fill_proportion))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for AverageSpectrum {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let spectrum = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+            // same bin placement as Grid so bars line up with its rows
+            let y_positions = bin_edges(
+                self.spectrum.len(),
+                self.sample_rate,
+                &self.y,
+                bounds.height,
+            );
+
+            // bars and held peaks share a scale so the line is never below a bar
+            let max = self
+                .spectrum
+                .iter()
+                .chain(self.peak_hold.iter())
+                .cloned()
+                .fold(0.0, f64::max);
+            if max == 0.0 {
+                return;
+            }
+
+            for (bin, magnitude) in self.spectrum.iter().enumerate() {
+                let height = y_positions[bin + 1] - y_positions[bin];
+                let bar = Size::new((magnitude / max) as f32 * bounds.width, -height);
+                let point = Point::new(0.0, bounds.height - y_positions[bin]);
+                frame.fill(&Path::rectangle(point, bar), Color::from_rgb(1.0, 0.0, 1.0));
+            }
+
+            if self.peak_hold.len() == self.spectrum.len() {
+                let line = Path::new(|builder| {
+                    let mut started = false;
+                    for (bin, peak) in self.peak_hold.iter().enumerate() {
+                        // bins outside of a zoomed range collapse to nothing
+                        if y_positions[bin + 1] == y_positions[bin] {
+                            continue;
+                        }
+                        let point = Point::new(
+                            (peak / max) as f32 * bounds.width,
+                            bounds.height - (y_positions[bin] + y_positions[bin + 1]) / 2.0,
+                        );
+                        if started {
+                            builder.line_to(point);
+                        } else {
+                            builder.move_to(point);
+                            started = true;
+                        }
+                    }
+                });
+                let stroke = Stroke::default()
+                    .with_color(Color::from_rgb(1.0, 0.5, 0.0))
+                    .with_width(1.0);
+                frame.stroke(&line, stroke);
+            }
+        });
+        vec![spectrum]
+    }
+}
+
+#[cfg(test)]
+mod test_peak_hold {
+    use super::AverageSpectrum;
+    use crate::units::Scale;
+
+    fn average_spectrum(spectrum: &[f64]) -> AverageSpectrum {
+        let mut average_spectrum = AverageSpectrum::new(spectrum, 8000, Scale::default(), 1);
+        average_spectrum.decay_rate = 0.5;
+        average_spectrum
+    }
+
+    #[test]
+    fn holds_and_decays() {
+        let mut average_spectrum = average_spectrum(&[4.0, 0.0]);
+        average_spectrum.update_peak_hold();
+        assert_eq!(&[2.0, 0.0], average_spectrum.peak_hold());
+        average_spectrum.update_spectrum(&[1.0, 8.0]);
+        average_spectrum.update_peak_hold();
+        assert_eq!(&[1.0, 4.0], average_spectrum.peak_hold());
+    }
+
+    #[test]
+    fn reset() {
+        let mut average_spectrum = average_spectrum(&[4.0, 2.0]);
+        average_spectrum.update_peak_hold();
+        average_spectrum.reset_peak_hold();
+        assert_eq!(&[0.0, 0.0], average_spectrum.peak_hold());
+    }
+
+    #[test]
+    fn bins_change() {
+        let mut average_spectrum = average_spectrum(&[4.0, 2.0]);
+        average_spectrum.update_peak_hold();
+        average_spectrum.update_spectrum(&[2.0, 2.0, 2.0]);
+        average_spectrum.update_peak_hold();
+        assert_eq!(&[1.0, 1.0, 1.0], average_spectrum.peak_hold());
+    }
+}
@@ -1,17 +1,18 @@
-use iced::{Color, Element, Point, Rectangle, Size};
+use iced::{mouse, Color, Element, Point, Rectangle, Size};
 use iced_audio::core::offset::Offset;
+use iced_audio::core::Normal;
 use iced_audio::graphics::text_marks;
 use iced_audio::graphics::tick_marks;
 use iced_audio::native::tick_marks::Tier;
 use iced_audio::style;
 use iced_graphics::Primitive;
-use iced_native::Length;
 
 use iced::canvas;
-use iced::canvas::{Cache, Canvas, Cursor, Geometry};
+use iced::canvas::{event, Cache, Canvas, Cursor, Event, Frame, Geometry};
 
 use crate::messages::Message;
-use crate::units::{format_unit, Scale};
+use crate::units::{format_unit, map_normalized, normalize, Mapping, Scale, Unit};
+use crate::widgets::length;
 
 pub enum Orientation {
     Horizontal,
@@ -20,10 +21,63 @@ pub enum Orientation {
 
 pub struct Axis {
     orientation: Orientation,
-    pub scale: Scale,
-    pub tick_count: usize,
+    // scale and tick counts are only changed through the setters, which clear the cache
+    // along with them
+    scale: Scale,
+    tick_count: usize,
+    // number of minor ticks drawn between each pair of major ticks
+    minor_tick_count: usize,
+    label: Option<String>,
+    // of the line, ticks and all text, eg. near white on a dark theme
+    color: Color,
+    // ticks and labels at nice numbers, see Scale::human_readable_range, rather than evenly
+    // spaced across the axis, only for linear mappings
+    human_readable: bool,
     fill_proportion: u16,
-    pub cache: Cache,
+    cache: Cache,
+    // relative to the axis, None while the cursor is elsewhere
+    cursor_position: Option<Point>,
+}
+
+// space reserved along the outer edge of the axis for its label
+const LABEL_GUTTER: f32 = 16.0;
+const TOOLTIP_TEXT_SIZE: f32 = 14.0;
+// longest tick label expected, eg. "20.0 kHz"
+const MAX_LABEL_CHARS: f32 = 8.0;
+// rough width of a glyph in ems
+const GLYPH_WIDTH: f32 = 0.6;
+const TOOLTIP_PADDING: f32 = 4.0;
+// most major ticks which follow the cells of the grid, any more blur into a solid bar
+const MAX_CELL_TICKS: usize = 33;
+
+// major ticks on the boundaries between cells of the grid, every boundary while they fit in
+// MAX_CELL_TICKS and otherwise every nth for the smallest n dividing cells evenly. counts with
+// no such n, eg. primes, are evenly spaced instead as few aligned ticks say little
+pub fn cell_tick_count(cells: usize) -> usize {
+    if cells < MAX_CELL_TICKS {
+        return cells + 1;
+    }
+    // cells itself always qualifies, leaving a tick at either end
+    let step = (2..=cells)
+        .find(|step| cells % step == 0 && cells / step < MAX_CELL_TICKS)
+        .expect("cells to divide itself");
+    let aligned = cells / step + 1;
+    if aligned < MAX_CELL_TICKS / 2 {
+        MAX_CELL_TICKS
+    } else {
+        aligned
+    }
+}
+
+// smallest extent across an axis at which tick labels of text_size are not clipped
+pub fn min_thickness(orientation: &Orientation, text_size: u16) -> f32 {
+    let text_size = text_size as f32;
+    match orientation {
+        // labels share the bottom half with the label gutter
+        Orientation::Horizontal => 2.0 * (text_size + LABEL_GUTTER),
+        // labels share the left half with the label gutter
+        Orientation::Vertical => 2.0 * (LABEL_GUTTER + MAX_LABEL_CHARS * text_size * GLYPH_WIDTH),
+    }
 }
 
 impl Axis {
@@ -31,22 +85,136 @@ impl Axis {
         orientation: Orientation,
         scale: Scale,
         tick_count: usize,
+        minor_tick_count: usize,
         fill_proportion: u16,
     ) -> Self {
         Axis {
             orientation,
             scale,
             tick_count,
+            minor_tick_count,
+            label: None,
+            color: Color::BLACK,
+            human_readable: false,
             fill_proportion,
             cache: Cache::new(),
+            cursor_position: None,
         }
     }
 
-    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+    pub fn with_label(mut self, text: &str) -> Axis {
+        self.label = Some(text.to_string());
+        self
+    }
+
+    pub fn with_color(mut self, color: Color) -> Axis {
+        self.color = color;
+        self
+    }
+
+    pub fn scale(&self) -> &Scale {
+        &self.scale
+    }
+
+    // setters clear the cache, otherwise the labels are stale until something else redraws
+    pub fn set_scale(&mut self, scale: Scale) {
+        self.scale = scale;
+        self.cache.clear();
+    }
+
+    pub fn set_tick_count(&mut self, tick_count: usize) {
+        self.tick_count = tick_count;
+        self.cache.clear();
+    }
+
+    pub fn set_minor_tick_count(&mut self, minor_tick_count: usize) {
+        self.minor_tick_count = minor_tick_count;
+        self.cache.clear();
+    }
+
+    pub fn set_human_readable(&mut self, human_readable: bool) {
+        self.human_readable = human_readable;
+        self.cache.clear();
+    }
+
+    pub fn set_unit(&mut self, unit: Unit) {
+        self.scale.unit = unit;
+        self.cache.clear();
+    }
+
+    pub fn set_mapping(&mut self, mapping: Mapping) {
+        self.scale.mapping = mapping;
+        self.cache.clear();
+    }
+
+    // value of the scale under a position within an axis of the given size
+    fn value_at(&self, position: Point, size: Size) -> f32 {
+        let normalized = match self.orientation {
+            Orientation::Horizontal => position.x / size.width,
+            Orientation::Vertical => 1.0 - position.y / size.height,
+        };
+        map_normalized(normalized.clamp(0.0, 1.0), &self.scale)
+    }
+
+    // labels and ticks at the nice values within the scale, placed through the scale's own
+    // mapping so they stay lined up with the grid
+    fn human_readable_marks(&self) -> (text_marks::Group, tick_marks::Group, tick_marks::Group) {
+        let normal = |value: &f32| Normal::new(normalize(*value, &self.scale));
+        let values = self.scale.human_readable_values(1);
+        let labels: Vec<String> = values
+            .iter()
+            .map(|value| format_unit(*value, &self.scale.unit))
+            .collect();
+        let text_marks: Vec<(Normal, &str)> = values
+            .iter()
+            .map(normal)
+            .zip(labels.iter().map(|s| s.as_str()))
+            .collect();
+        let ticks: Vec<(Normal, Tier)> = values.iter().map(|v| (normal(v), Tier::One)).collect();
+        let minor_ticks: Vec<(Normal, Tier)> = self
+            .scale
+            .human_readable_values(self.minor_tick_count + 1)
+            .iter()
+            .map(|v| (normal(v), Tier::Two))
+            .collect();
+        (
+            text_marks::Group::from_normalized(&text_marks),
+            tick_marks::Group::from_normalized(&ticks),
+            tick_marks::Group::from_normalized(&minor_ticks),
+        )
+    }
+
+    // the value under the cursor on a translucent background, kept within the bounds
+    fn draw_tooltip(&self, frame: &mut Frame, position: Point, size: Size) {
+        let content = format_unit(self.value_at(position, size), &self.scale.unit);
+        // iced can not measure text on a canvas, so the width is estimated
+        let text_size = Size::new(
+            content.chars().count() as f32 * TOOLTIP_TEXT_SIZE * GLYPH_WIDTH
+                + 2.0 * TOOLTIP_PADDING,
+            TOOLTIP_TEXT_SIZE + 2.0 * TOOLTIP_PADDING,
+        );
+        let corner = Point::new(
+            (position.x + 8.0)
+                .min(size.width - text_size.width)
+                .max(0.0),
+            (position.y - text_size.height - 4.0).max(0.0),
+        );
+        frame.fill_rectangle(corner, text_size, Color::from_rgba(0.0, 0.0, 0.0, 0.7));
+        frame.fill_text(canvas::Text {
+            content,
+            position: Point::new(corner.x + TOOLTIP_PADDING, corner.y + TOOLTIP_PADDING),
+            color: Color::WHITE,
+            size: TOOLTIP_TEXT_SIZE,
+            ..Default::default()
+        });
+    }
+
+    // size is (width, height) in pixels, None to fill the axis' portion of the window
+    pub fn view<'a>(&'a mut self, size: Option<(u16, u16)>) -> Element<'a, Message> {
         let fill_proportion = self.fill_proportion;
         Canvas::new(self)
-            .width(Length::FillPortion(fill_proportion))
-            .height(Length::FillPortion(fill_proportion))
+            .width(length(fill_proportion, size.map(|size| size.0)))
+            .height(length(fill_proportion, size.map(|size| size.1)))
             .into()
     }
 }
@@ -56,22 +224,37 @@ impl canvas::Program<Message> for Axis {
         let axis = self.cache.draw(bounds.size(), |frame| {
             let width = bounds.size().width;
             let height = bounds.size().height;
+            let label_gutter = match self.label {
+                Some(_) => LABEL_GUTTER,
+                None => 0.0,
+            };
 
-            //let label_values = &self.scale.evenly_spaced_values(self.tick_count, true);
-            let label_values = &self.scale.evenly_spaced_values(16, true);
-            let labels: Vec<String> = label_values
-                .iter()
-                .map(|f| format_unit(*f, &self.scale.unit))
-                .collect();
+            let (text_marks, ticks, minor_ticks) =
+                if self.human_readable && self.scale.mapping == Mapping::Linear {
+                    self.human_readable_marks()
+                } else {
+                    //let label_values = &self.scale.evenly_spaced_values(self.tick_count, true);
+                    let label_values = &self.scale.evenly_spaced_values(16, true);
+                    let labels: Vec<String> = label_values
+                        .iter()
+                        .map(|f| format_unit(*f, &self.scale.unit))
+                        .collect();
 
-            // I dont believe there is a way around this extra allocation
-            let str_labels: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
+                    // I dont believe there is a way around this extra allocation
+                    let str_labels: Vec<&str> = labels.iter().map(|s| s.as_str()).collect();
 
-            let text_marks = text_marks::Group::evenly_spaced(&str_labels[..]);
+                    let text_marks = text_marks::Group::evenly_spaced(&str_labels[..]);
 
-            let ticks = tick_marks::Group::evenly_spaced(self.tick_count, Tier::One);
+                    let ticks = tick_marks::Group::evenly_spaced(self.tick_count, Tier::One);
+                    let minor_ticks = tick_marks::Group::evenly_spaced(
+                        (self.tick_count.max(1) - 1) * (self.minor_tick_count + 1) + 1,
+                        Tier::Two,
+                    );
+                    (text_marks, ticks, minor_ticks)
+                };
             let axis_line: Rectangle;
             let rendered_tick_marks: Primitive;
+            let rendered_minor_tick_marks: Primitive;
             let rendered_text_marks: Primitive;
             match self.orientation {
                 Orientation::Horizontal => {
@@ -79,7 +262,11 @@ impl canvas::Program<Message> for Axis {
                         Rectangle::new(Point::new(0.0, 0.0), Size::new(width, height / 2.0));
                     let text_bounds = Rectangle::new(
                         Point::new(0.0, height / 2.0),
-                        Size::new(width, height / 2.0),
+                        Size::new(width, height / 2.0 - label_gutter),
+                    );
+                    let minor_tick_bounds = Rectangle::new(
+                        Point::new(0.0, height / 8.0),
+                        Size::new(width, height / 4.0),
                     );
                     axis_line =
                         Rectangle::new(Point::new(0.0, height / 4.0), Size::new(width, 1.0));
@@ -94,6 +281,17 @@ impl canvas::Program<Message> for Axis {
                         false,
                         &tick_marks::PrimitiveCache::default(),
                     );
+                    rendered_minor_tick_marks = tick_marks::draw_horizontal_tick_marks(
+                        &minor_tick_bounds,
+                        &minor_ticks,
+                        &THIN_TICKS,
+                        &style::tick_marks::Placement::Center {
+                            offset: Offset::ZERO,
+                            fill_length: true,
+                        },
+                        false,
+                        &tick_marks::PrimitiveCache::default(),
+                    );
                     rendered_text_marks = text_marks::draw_horizontal_text_marks(
                         &text_bounds,
                         &text_marks,
@@ -111,8 +309,14 @@ impl canvas::Program<Message> for Axis {
                         Point::new(width / 2.0, 1.0),
                         Size::new(width / 2.0, height - 1.0),
                     );
-                    let text_bounds =
-                        Rectangle::new(Point::new(0.0, 0.0), Size::new(width / 2.0, height));
+                    let text_bounds = Rectangle::new(
+                        Point::new(label_gutter, 0.0),
+                        Size::new(width / 2.0 - label_gutter, height),
+                    );
+                    let minor_tick_bounds = Rectangle::new(
+                        Point::new(width * 0.625, 1.0),
+                        Size::new(width / 4.0, height - 1.0),
+                    );
                     axis_line =
                         Rectangle::new(Point::new(width * 0.75, 0.0), Size::new(1.0, height));
                     rendered_tick_marks = tick_marks::draw_vertical_tick_marks(
@@ -126,6 +330,17 @@ impl canvas::Program<Message> for Axis {
                         false,
                         &tick_marks::PrimitiveCache::default(),
                     );
+                    rendered_minor_tick_marks = tick_marks::draw_vertical_tick_marks(
+                        &minor_tick_bounds,
+                        &minor_ticks,
+                        &THIN_TICKS,
+                        &style::tick_marks::Placement::Center {
+                            offset: Offset::ZERO,
+                            fill_length: true,
+                        },
+                        false,
+                        &tick_marks::PrimitiveCache::default(),
+                    );
                     rendered_text_marks = text_marks::draw_vertical_text_marks(
                         &text_bounds,
                         &text_marks,
@@ -139,32 +354,109 @@ impl canvas::Program<Message> for Axis {
                     );
                 }
             }
-            frame.fill_rectangle(axis_line.position(), axis_line.size(), Color::BLACK);
-            fill_from_primitive(rendered_tick_marks, frame);
-            fill_from_primitive(rendered_text_marks, frame);
+            frame.fill_rectangle(axis_line.position(), axis_line.size(), self.color);
+            fill_from_primitive(rendered_tick_marks, frame, self.color);
+            fill_from_primitive(rendered_minor_tick_marks, frame, self.color);
+
+            if let Some(label) = &self.label {
+                // iced can not rotate glyphs, so vertical labels are stacked a character per line
+                let (content, position) = match self.orientation {
+                    Orientation::Horizontal => (
+                        label.clone(),
+                        Point::new(width / 2.0, height - label_gutter / 2.0),
+                    ),
+                    Orientation::Vertical => (
+                        label
+                            .chars()
+                            .map(|c| c.to_string())
+                            .collect::<Vec<String>>()
+                            .join("\n"),
+                        Point::new(label_gutter / 2.0, height / 2.0),
+                    ),
+                };
+                frame.fill_text(canvas::Text {
+                    content,
+                    position,
+                    color: self.color,
+                    size: 14.0,
+                    horizontal_alignment: iced::HorizontalAlignment::Center,
+                    vertical_alignment: iced::VerticalAlignment::Center,
+                    ..Default::default()
+                });
+            }
+            fill_from_primitive(rendered_text_marks, frame, self.color);
         });
-        vec![axis]
+
+        // drawn outside of the cache so moving the cursor does not redraw the ticks
+        match self.cursor_position {
+            Some(position) => {
+                let mut frame = Frame::new(bounds.size());
+                self.draw_tooltip(&mut frame, position, bounds.size());
+                vec![axis, frame.into_geometry()]
+            }
+            None => vec![axis],
+        }
+    }
+
+    fn update(
+        &mut self,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match (event, cursor.position_in(&bounds)) {
+            (Event::Mouse(mouse::Event::CursorMoved { .. }), position) => {
+                self.cursor_position = position;
+                (event::Status::Ignored, None)
+            }
+            // zooms around the value under the cursor, like scrolling over the grid
+            (Event::Mouse(mouse::Event::WheelScrolled { delta }), Some(position)) => {
+                let scrolled = match delta {
+                    mouse::ScrollDelta::Lines { y, .. } => y,
+                    mouse::ScrollDelta::Pixels { y, .. } => y,
+                };
+                let factor = if scrolled > 0.0 { 0.8 } else { 1.25 };
+                let anchor = self.value_at(position, bounds.size());
+                let message =
+                    self.scale
+                        .zoom(anchor, factor)
+                        .ok()
+                        .map(|range| match self.orientation {
+                            Orientation::Horizontal => Message::TimeRangeChanged {
+                                start_sec: range.min().max(0.0),
+                                end_sec: range.max(),
+                            },
+                            Orientation::Vertical => Message::FrequencyRangeChanged {
+                                min: range.min().max(0.0),
+                                max: range.max(),
+                            },
+                        });
+                (event::Status::Captured, message)
+            }
+            _ => (event::Status::Ignored, None),
+        }
     }
 }
 
 // renderes primities created iced_audio by onto a frame
+// quads and text are all drawn in color, whatever iced_audio styled them with
 // TODO consider removing iced_audio dependancy or developing a cleaner solution
-fn fill_from_primitive(primitive: Primitive, frame: &mut canvas::Frame) {
+fn fill_from_primitive(primitive: Primitive, frame: &mut canvas::Frame, color: Color) {
     match primitive {
         Primitive::Group { primitives } => {
             for primitive in primitives {
                 match primitive {
                     Primitive::Quad { bounds, .. } => {
-                        frame.fill_rectangle(bounds.position(), bounds.size(), Color::BLACK);
+                        frame.fill_rectangle(bounds.position(), bounds.size(), color);
                     }
                     Primitive::Text {
                         content,
                         bounds,
-                        color,
                         size,
                         font,
                         horizontal_alignment,
                         vertical_alignment,
+                        ..
                     } => {
                         let text = canvas::Text {
                             content,
@@ -187,25 +479,21 @@ fn fill_from_primitive(primitive: Primitive, frame: &mut canvas::Frame) {
                     for primitive in primitives {
                         match primitive {
                             Primitive::Quad { bounds, .. } => {
-                                frame.fill_rectangle(
-                                    bounds.position(),
-                                    bounds.size(),
-                                    Color::BLACK,
-                                );
+                                frame.fill_rectangle(bounds.position(), bounds.size(), color);
                             }
                             Primitive::Text {
                                 content,
                                 bounds,
-                                color,
                                 size,
                                 font,
                                 horizontal_alignment,
                                 vertical_alignment,
+                                ..
                             } => {
                                 let text = canvas::Text {
                                     content: content.to_string(),
                                     position: bounds.position(),
-                                    color: *color,
+                                    color,
                                     size: *size,
                                     font: *font,
                                     horizontal_alignment: *horizontal_alignment,
@@ -241,3 +529,131 @@ const THIN_TICKS: style::tick_marks::Style = style::tick_marks::Style {
         color: Color::BLACK,
     },
 };
+
+#[cfg(test)]
+mod test_min_thickness {
+    use super::{min_thickness, Orientation, GLYPH_WIDTH};
+
+    #[test]
+    fn grows_with_text_size() {
+        for orientation in &[Orientation::Horizontal, Orientation::Vertical] {
+            assert!(min_thickness(orientation, 12) < min_thickness(orientation, 16));
+        }
+    }
+
+    #[test]
+    fn fits_labels() {
+        // the text bounds left after the gutter hold a line of text
+        assert_eq!(28.0, min_thickness(&Orientation::Horizontal, 12) / 2.0);
+        let label_width = min_thickness(&Orientation::Vertical, 12) / 2.0 - 16.0;
+        assert!(label_width >= 8.0 * 12.0 * GLYPH_WIDTH);
+    }
+}
+
+#[cfg(test)]
+mod test_cell_tick_count {
+    use super::{cell_tick_count, MAX_CELL_TICKS};
+
+    #[test]
+    fn every_boundary() {
+        assert_eq!(9, cell_tick_count(8));
+        assert_eq!(MAX_CELL_TICKS, cell_tick_count(MAX_CELL_TICKS - 1));
+    }
+
+    #[test]
+    fn thinned() {
+        // the bins of a 1024 sample window, every 19th boundary
+        assert_eq!(28, cell_tick_count(513));
+        assert_eq!(33, cell_tick_count(512));
+        assert_eq!(26, cell_tick_count(1025));
+    }
+
+    #[test]
+    fn prime() {
+        assert_eq!(MAX_CELL_TICKS, cell_tick_count(521));
+    }
+}
+
+#[cfg(test)]
+mod test_value_at {
+    use super::{Axis, Orientation};
+    use crate::units::{Scale, ScaleRange};
+    use iced::{Point, Size};
+
+    fn scale() -> Scale {
+        Scale::default().with_range(ScaleRange::new(0.0, 1000.0).unwrap())
+    }
+
+    #[test]
+    fn vertical() {
+        let axis = Axis::new(Orientation::Vertical, scale(), 16, 0, 1);
+        let size = Size::new(50.0, 200.0);
+        assert_eq!(1000.0, axis.value_at(Point::new(10.0, 0.0), size));
+        assert_eq!(250.0, axis.value_at(Point::new(10.0, 150.0), size));
+    }
+
+    #[test]
+    fn horizontal() {
+        let axis = Axis::new(Orientation::Horizontal, scale(), 16, 0, 1);
+        assert_eq!(
+            100.0,
+            axis.value_at(Point::new(20.0, 5.0), Size::new(200.0, 50.0))
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_wheel_zoom {
+    use super::{Axis, Orientation};
+    use crate::messages::Message;
+    use crate::units::{Scale, ScaleRange};
+    use iced::canvas::{event, Cursor, Event, Program};
+    use iced::{mouse, Point, Rectangle, Size};
+
+    fn scroll(axis: &mut Axis, position: Point, y: f32) -> (event::Status, Option<Message>) {
+        let bounds = Rectangle::new(Point::ORIGIN, Size::new(200.0, 200.0));
+        let delta = mouse::ScrollDelta::Lines { x: 0.0, y };
+        axis.update(
+            Event::Mouse(mouse::Event::WheelScrolled { delta }),
+            bounds,
+            Cursor::Available(position),
+        )
+    }
+
+    fn scale() -> Scale {
+        Scale::default().with_range(ScaleRange::new(0.0, 1000.0).unwrap())
+    }
+
+    #[test]
+    fn vertical_zooms_in_around_cursor() {
+        let mut axis = Axis::new(Orientation::Vertical, scale(), 16, 0, 1);
+        // 500 Hz halfway up, the range shrinks to 80% around it
+        match scroll(&mut axis, Point::new(10.0, 100.0), 1.0) {
+            (event::Status::Captured, Some(Message::FrequencyRangeChanged { min, max })) => {
+                assert!((min - 100.0).abs() < 1e-3 && (max - 900.0).abs() < 1e-3)
+            }
+            message => panic!("unexpected {:?}", message.1),
+        }
+    }
+
+    #[test]
+    fn horizontal_zooms_out() {
+        let mut axis = Axis::new(Orientation::Horizontal, scale(), 16, 0, 1);
+        match scroll(&mut axis, Point::new(40.0, 10.0), -1.0) {
+            (event::Status::Captured, Some(Message::TimeRangeChanged { start_sec, end_sec })) => {
+                // 200 stays where it was, clamped at zero on the left
+                assert_eq!(0.0, start_sec);
+                assert!((end_sec - 1200.0).abs() < 1e-3)
+            }
+            message => panic!("unexpected {:?}", message.1),
+        }
+    }
+
+    #[test]
+    fn outside_bounds() {
+        let mut axis = Axis::new(Orientation::Vertical, scale(), 16, 0, 1);
+        let (status, message) = scroll(&mut axis, Point::new(300.0, 100.0), 1.0);
+        assert_eq!(event::Status::Ignored, status);
+        assert!(message.is_none());
+    }
+}
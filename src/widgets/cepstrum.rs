@@ -0,0 +1,89 @@
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry, Path, Stroke},
+    Color, Element, Length, Point, Rectangle,
+};
+
+use crate::messages::Message;
+use crate::units::{format_unit, Unit};
+
+// line chart of a real cepstrum against quefrency, peaks mark periodicities
+// such as the fundamental of a harmonic sound or the delay of an echo
+pub struct CepstrumWidget {
+    cepstrum: Vec<f64>,
+    pub sample_rate: u32,
+    fill_proportion: u16,
+    pub cache: Cache,
+}
+
+impl CepstrumWidget {
+    pub fn new(cepstrum: &[f64], sample_rate: u32, fill_proportion: u16) -> CepstrumWidget {
+        CepstrumWidget {
+            cepstrum: cepstrum.to_vec(),
+            sample_rate,
+            fill_proportion,
+            cache: Cache::new(),
+        }
+    }
+
+    pub fn update_cepstrum(&mut self, cepstrum: &[f64]) {
+        self.cepstrum = cepstrum.to_vec();
+        self.cache.clear();
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let fill_proportion = self.fill_proportion;
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::FillPortion(fill_proportion))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for CepstrumWidget {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let cepstrum = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+            // the second half mirrors the first
+            let n = self.cepstrum.len() / 2;
+            if n < 2 {
+                return;
+            }
+            // quefrency zero is the average log magnitude, which would flatten everything else
+            let values = &self.cepstrum[1..n];
+            let max = values.iter().map(|x| x.abs()).fold(0.0, f64::max);
+            if max == 0.0 {
+                return;
+            }
+
+            let line = Path::new(|builder| {
+                for (i, value) in values.iter().enumerate() {
+                    let point = Point::new(
+                        i as f32 / (values.len() - 1) as f32 * bounds.width,
+                        (0.5 - (value / max) as f32 / 2.0) * bounds.height,
+                    );
+                    if i == 0 {
+                        builder.move_to(point);
+                    } else {
+                        builder.line_to(point);
+                    }
+                }
+            });
+            let stroke = Stroke::default()
+                .with_color(Color::from_rgb(1.0, 1.0, 0.0))
+                .with_width(1.0);
+            frame.stroke(&line, stroke);
+
+            let max_quefrency = n as f32 / self.sample_rate as f32;
+            frame.fill_text(canvas::Text {
+                position: Point::new(bounds.width, bounds.height),
+                content: format!("quefrency: {}", format_unit(max_quefrency, &Unit::Second)),
+                color: Color::WHITE,
+                vertical_alignment: iced::VerticalAlignment::Bottom,
+                horizontal_alignment: iced::HorizontalAlignment::Right,
+                ..Default::default()
+            });
+        });
+        vec![cepstrum]
+    }
+}
@@ -0,0 +1,362 @@
+use std::error::Error;
+use std::fmt;
+
+use iced::Color;
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorError {
+    // not of the form #rrggbb
+    InvalidFormat(String),
+    InvalidHex(String),
+    // a custom colormap needs at least one stop
+    NoStops,
+}
+
+impl fmt::Display for ColorError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            ColorError::InvalidFormat(s) => write!(f, "expected a color like #1a1a2e, found {}", s),
+            ColorError::InvalidHex(s) => write!(f, "{} is not valid hexadecimal", s),
+            ColorError::NoStops => write!(f, "a colormap needs at least one color stop"),
+        }
+    }
+}
+
+impl Error for ColorError {}
+
+// css style #rrggbb, case insensitive
+pub fn from_hex(s: &str) -> Result<Color, ColorError> {
+    let digits = match s.strip_prefix('#') {
+        Some(digits) if digits.len() == 6 && digits.is_ascii() => digits,
+        _ => return Err(ColorError::InvalidFormat(s.to_string())),
+    };
+    let channel = |i: usize| {
+        u8::from_str_radix(&digits[i..i + 2], 16)
+            .map(|value| value as f32 / 255.0)
+            .map_err(|_| ColorError::InvalidHex(s.to_string()))
+    };
+    Ok(Color::from_rgb(channel(0)?, channel(2)?, channel(4)?))
+}
+
+#[derive(Debug, Clone, Default, PartialEq)]
+pub enum Colormap {
+    // black to magenta
+    #[default]
+    Magenta,
+    // perceptually uniform dark blue to yellow, as in matplotlib
+    Viridis,
+    // (position, color) stops sorted by position, colors between stops are interpolated
+    Custom(Vec<(f32, Color)>),
+}
+
+// the built in colormaps, by the names used to pick them
+pub const NAMES: [&str; 2] = ["Magenta", "Viridis"];
+// (width, height) of the gradient swatches shown next to colormap names
+pub const PREVIEW_SIZE: (u32, u32) = (256, 20);
+
+impl Colormap {
+    pub fn from_name(name: &str) -> Option<Colormap> {
+        match name {
+            "Magenta" => Some(Colormap::Magenta),
+            "Viridis" => Some(Colormap::Viridis),
+            _ => None,
+        }
+    }
+
+    // width colors from 0.0 at the left to 1.0 at the right
+    pub fn gradient(&self, width: u32) -> Vec<Color> {
+        (0..width)
+            .map(|i| self.color(i as f32 / (width - 1).max(1) as f32))
+            .collect()
+    }
+
+    // PREVIEW_SIZE swatch of the gradient, every row the same
+    #[cfg(feature = "image")]
+    pub fn preview(&self) -> image::RgbaImage {
+        let (width, height) = PREVIEW_SIZE;
+        let gradient = self.gradient(width);
+        image::RgbaImage::from_fn(width, height, |x, _| {
+            let color = gradient[x as usize];
+            image::Rgba([
+                (color.r * 255.0).round() as u8,
+                (color.g * 255.0).round() as u8,
+                (color.b * 255.0).round() as u8,
+                255,
+            ])
+        })
+    }
+
+    pub fn custom(stops: Vec<(f32, String)>) -> Result<Colormap, ColorError> {
+        if stops.is_empty() {
+            return Err(ColorError::NoStops);
+        }
+        let mut parsed = stops
+            .iter()
+            .map(|(position, hex)| Ok((*position, from_hex(hex)?)))
+            .collect::<Result<Vec<(f32, Color)>, ColorError>>()?;
+        parsed.sort_by(|a, b| a.0.partial_cmp(&b.0).unwrap_or(std::cmp::Ordering::Equal));
+        Ok(Colormap::Custom(parsed))
+    }
+
+    // intensity between 0.0 and 1.0
+    pub fn color(&self, intensity: f32) -> Color {
+        match self {
+            Colormap::Magenta => Color::from_rgb(intensity, 0.0, intensity),
+            Colormap::Viridis => {
+                let stops: Vec<(f32, Color)> = VIRIDIS
+                    .iter()
+                    .enumerate()
+                    .map(|(i, [r, g, b])| {
+                        (
+                            i as f32 / (VIRIDIS.len() - 1) as f32,
+                            Color::from_rgb8(*r, *g, *b),
+                        )
+                    })
+                    .collect();
+                interpolate(&stops, intensity)
+            }
+            Colormap::Custom(stops) => interpolate(stops, intensity),
+        }
+    }
+}
+
+// evenly spaced samples of matplotlib's viridis
+const VIRIDIS: [[u8; 3]; 9] = [
+    [0x44, 0x01, 0x54],
+    [0x47, 0x2c, 0x7a],
+    [0x3b, 0x51, 0x8b],
+    [0x2c, 0x71, 0x8e],
+    [0x21, 0x90, 0x8d],
+    [0x27, 0xad, 0x81],
+    [0x5c, 0xc8, 0x63],
+    [0xaa, 0xdc, 0x32],
+    [0xfd, 0xe7, 0x25],
+];
+
+// stops are sorted by position, intensities outside of them take the nearest stop's color
+fn interpolate(stops: &[(f32, Color)], intensity: f32) -> Color {
+    let upper = stops
+        .iter()
+        .position(|(position, _)| *position >= intensity)
+        .unwrap_or(stops.len() - 1);
+    let (end, to) = stops[upper];
+    if upper == 0 || intensity >= end {
+        return to;
+    }
+    let (start, from) = stops[upper - 1];
+    let t = (intensity - start) / (end - start);
+    Color::from_rgb(
+        from.r + (to.r - from.r) * t,
+        from.g + (to.g - from.g) * t,
+        from.b + (to.b - from.b) * t,
+    )
+}
+
+// diverging map for differences mapped onto 0.0..1.0, warm above 0.5 and cool below
+pub fn difference_color(intensity: f32) -> Color {
+    let difference = (intensity * 2.0 - 1.0).clamp(-1.0, 1.0);
+    if difference >= 0.0 {
+        Color::from_rgb(difference, difference * 0.5, 0.0)
+    } else {
+        Color::from_rgb(0.0, -difference * 0.5, -difference)
+    }
+}
+
+// collects hex color stops, eg. as read from a config file, into a Colormap::Custom
+#[derive(Debug, Clone, Default)]
+pub struct Gradient {
+    stops: Vec<(f32, String)>,
+}
+
+impl Gradient {
+    pub fn new() -> Self {
+        Gradient::default()
+    }
+
+    pub fn with_stop(mut self, position: f32, color: &str) -> Self {
+        self.stops.push((position, color.to_string()));
+        self
+    }
+
+    pub fn build(self) -> Result<Colormap, ColorError> {
+        Colormap::custom(self.stops)
+    }
+}
+
+#[cfg(test)]
+mod test_from_hex {
+    use super::{from_hex, ColorError};
+    use iced::Color;
+
+    #[test]
+    fn red() {
+        assert_eq!(
+            Ok(Color {
+                r: 1.0,
+                g: 0.0,
+                b: 0.0,
+                a: 1.0
+            }),
+            from_hex("#FF0000")
+        )
+    }
+
+    #[test]
+    fn lowercase() {
+        assert_eq!(from_hex("#FF00FF"), from_hex("#ff00ff"))
+    }
+
+    #[test]
+    fn missing_hash() {
+        assert_eq!(
+            Err(ColorError::InvalidFormat("FF0000".to_string())),
+            from_hex("FF0000")
+        )
+    }
+
+    #[test]
+    fn invalid_digit() {
+        assert_eq!(
+            Err(ColorError::InvalidHex("#GG0000".to_string())),
+            from_hex("#GG0000")
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_viridis {
+    use super::Colormap;
+    use iced::Color;
+
+    #[test]
+    fn ends() {
+        assert_eq!(
+            (
+                Color::from_rgb8(0x44, 0x01, 0x54),
+                Color::from_rgb8(0xfd, 0xe7, 0x25)
+            ),
+            (Colormap::Viridis.color(0.0), Colormap::Viridis.color(1.0))
+        )
+    }
+
+    #[test]
+    fn gets_brighter() {
+        let brightness = |intensity| {
+            let color = Colormap::Viridis.color(intensity);
+            color.r + color.g + color.b
+        };
+        assert!(brightness(0.2) < brightness(0.5) && brightness(0.5) < brightness(0.8))
+    }
+}
+
+#[cfg(test)]
+mod test_preview {
+    use super::{Colormap, NAMES};
+    use iced::Color;
+
+    #[test]
+    fn ends() {
+        let gradient = Colormap::Magenta.gradient(256);
+        assert_eq!(256, gradient.len());
+        assert_eq!(Color::from_rgb(0.0, 0.0, 0.0), gradient[0]);
+        assert_eq!(Color::from_rgb(1.0, 0.0, 1.0), gradient[255]);
+    }
+
+    #[test]
+    fn single_column() {
+        assert_eq!(1, Colormap::Viridis.gradient(1).len());
+    }
+
+    #[cfg(feature = "image")]
+    #[test]
+    fn preview() {
+        use super::PREVIEW_SIZE;
+
+        let preview = Colormap::Magenta.preview();
+        assert_eq!(PREVIEW_SIZE, preview.dimensions());
+        let last = PREVIEW_SIZE.0 - 1;
+        assert_eq!([0, 0, 0, 255], preview.get_pixel(0, 0).0);
+        assert_eq!([255, 0, 255, 255], preview.get_pixel(last, 0).0);
+        // every row is the same
+        assert_eq!(
+            preview.get_pixel(last, 0),
+            preview.get_pixel(last, PREVIEW_SIZE.1 - 1)
+        );
+    }
+
+    #[test]
+    fn named() {
+        for name in NAMES.iter() {
+            assert!(Colormap::from_name(name).is_some());
+        }
+        assert_eq!(None, Colormap::from_name("Jet"));
+    }
+}
+
+#[cfg(test)]
+mod test_difference_color {
+    use super::difference_color;
+    use iced::Color;
+
+    #[test]
+    fn equal_is_black() {
+        assert_eq!(Color::BLACK, difference_color(0.5))
+    }
+
+    #[test]
+    fn louder_is_warm() {
+        assert_eq!(Color::from_rgb(1.0, 0.5, 0.0), difference_color(1.0))
+    }
+
+    #[test]
+    fn quieter_is_cool() {
+        assert_eq!(Color::from_rgb(0.0, 0.5, 1.0), difference_color(0.0))
+    }
+}
+
+#[cfg(test)]
+mod test_gradient {
+    use super::{ColorError, Colormap, Gradient};
+    use iced::Color;
+
+    #[test]
+    fn interpolates() {
+        let colormap = Gradient::new()
+            .with_stop(0.0, "#000000")
+            .with_stop(1.0, "#FF00FF")
+            .build()
+            .unwrap();
+        assert_eq!(Colormap::Magenta.color(0.5), colormap.color(0.5))
+    }
+
+    #[test]
+    fn unsorted_stops() {
+        let colormap = Gradient::new()
+            .with_stop(1.0, "#FFFFFF")
+            .with_stop(0.0, "#000000")
+            .build()
+            .unwrap();
+        assert_eq!(Color::BLACK, colormap.color(0.0))
+    }
+
+    #[test]
+    fn clamps_outside_of_stops() {
+        let colormap = Gradient::new()
+            .with_stop(0.25, "#FF0000")
+            .with_stop(0.75, "#0000FF")
+            .build()
+            .unwrap();
+        assert_eq!(
+            (
+                Color::from_rgb(1.0, 0.0, 0.0),
+                Color::from_rgb(0.0, 0.0, 1.0)
+            ),
+            (colormap.color(0.0), colormap.color(1.0))
+        )
+    }
+
+    #[test]
+    fn empty() {
+        assert_eq!(Err(ColorError::NoStops), Gradient::new().build())
+    }
+}
@@ -0,0 +1,107 @@
+use iced::{button, Align, Button, Column, Element, Row, Text};
+
+use crate::messages::Message;
+use crate::widgets::colormap::{Colormap, NAMES};
+use crate::widgets::swatch::Swatch;
+
+// a built in colormap shown as its gradient beside its name, which iced's text only PickList
+// can not do
+struct Entry {
+    name: &'static str,
+    swatch: Swatch,
+}
+
+impl Entry {
+    fn new(name: &'static str) -> Option<Entry> {
+        Colormap::from_name(name).map(|colormap| Entry {
+            name,
+            swatch: Swatch::new(&colormap),
+        })
+    }
+
+    fn view<'a>(&'a mut self) -> Row<'a, Message> {
+        Row::new()
+            .spacing(5)
+            .align_items(Align::Center)
+            .push(self.swatch.view())
+            .push(Text::new(self.name))
+    }
+}
+
+// a button showing the current colormap which opens a list of every one of colormap::NAMES
+pub struct ColormapPicker {
+    pub open: bool,
+    toggle: button::State,
+    // None for custom colormaps
+    selected: Option<Entry>,
+    entries: Vec<(Entry, button::State)>,
+}
+
+impl ColormapPicker {
+    pub fn new(colormap: &Colormap) -> ColormapPicker {
+        let mut picker = ColormapPicker {
+            open: false,
+            toggle: button::State::new(),
+            selected: None,
+            entries: NAMES
+                .iter()
+                .filter_map(|name| Entry::new(name))
+                .map(|entry| (entry, button::State::new()))
+                .collect(),
+        };
+        picker.select(colormap);
+        picker
+    }
+
+    // closes the list, its swatch is only redrawn when the colormap changes
+    pub fn select(&mut self, colormap: &Colormap) {
+        let name = NAMES
+            .iter()
+            .find(|name| Colormap::from_name(name).as_ref() == Some(colormap));
+        if self.selected.as_ref().map(|entry| entry.name) != name.copied() {
+            self.selected = name.and_then(|name| Entry::new(name));
+        }
+        self.open = false;
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let current: Element<'a, Message> = match self.selected.as_mut() {
+            Some(entry) => entry.view().into(),
+            None => Text::new("Custom").into(),
+        };
+        let mut picker = Column::new()
+            .spacing(1)
+            .push(Button::new(&mut self.toggle, current).on_press(Message::ColormapPickerToggled));
+        if self.open {
+            for (entry, button) in self.entries.iter_mut() {
+                let name = entry.name;
+                picker = picker.push(
+                    Button::new(button, entry.view()).on_press(Message::ColormapChanged(name)),
+                );
+            }
+        }
+        picker.into()
+    }
+}
+
+#[cfg(test)]
+mod test_select {
+    use super::ColormapPicker;
+    use crate::widgets::colormap::Colormap;
+
+    #[test]
+    fn closes() {
+        let mut picker = ColormapPicker::new(&Colormap::Magenta);
+        picker.open = true;
+        picker.select(&Colormap::Viridis);
+        assert!(!picker.open);
+        assert_eq!(Some("Viridis"), picker.selected.map(|entry| entry.name));
+    }
+
+    #[test]
+    fn custom() {
+        let custom = Colormap::custom(vec![(0.0, "#ff0000".to_string())]).unwrap();
+        let picker = ColormapPicker::new(&custom);
+        assert!(picker.selected.is_none());
+    }
+}
@@ -0,0 +1,71 @@
+use std::collections::VecDeque;
+use std::time::Instant;
+
+// frames the rolling average is taken over
+pub const WINDOW: usize = 30;
+
+// times of the most recent draws, for watching the frame rate while optimizing
+#[derive(Debug, Default)]
+pub struct FrameCounter {
+    frames: VecDeque<Instant>,
+}
+
+impl FrameCounter {
+    pub fn new() -> Self {
+        FrameCounter::default()
+    }
+
+    pub fn record(&mut self, now: Instant) {
+        // one more than the window, as a window of frames has that many intervals
+        if self.frames.len() > WINDOW {
+            self.frames.pop_front();
+        }
+        self.frames.push_back(now);
+    }
+
+    // frames per second over the recorded window, None until there are two frames
+    pub fn fps(&self) -> Option<f32> {
+        let (first, last) = (self.frames.front()?, self.frames.back()?);
+        let elapsed = last.duration_since(*first).as_secs_f32();
+        if elapsed == 0.0 {
+            return None;
+        }
+        Some((self.frames.len() - 1) as f32 / elapsed)
+    }
+}
+
+#[cfg(test)]
+mod test_frame_counter {
+    use super::{FrameCounter, WINDOW};
+    use std::time::{Duration, Instant};
+
+    #[test]
+    fn steady() {
+        let start = Instant::now();
+        let mut counter = FrameCounter::new();
+        for frame in 0..10 {
+            counter.record(start + Duration::from_millis(frame * 20));
+        }
+        assert!((counter.fps().unwrap() - 50.0).abs() < 0.01)
+    }
+
+    #[test]
+    fn rolling() {
+        // a slow start falls out of the window
+        let start = Instant::now();
+        let mut counter = FrameCounter::new();
+        counter.record(start);
+        for frame in 1..=WINDOW as u64 * 2 {
+            counter.record(start + Duration::from_secs(1) + Duration::from_millis(frame * 10));
+        }
+        assert!((counter.fps().unwrap() - 100.0).abs() < 0.01)
+    }
+
+    #[test]
+    fn single_frame() {
+        let mut counter = FrameCounter::new();
+        assert_eq!(None, counter.fps());
+        counter.record(Instant::now());
+        assert_eq!(None, counter.fps());
+    }
+}
@@ -0,0 +1,161 @@
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry, Path, Stroke},
+    Color, Element, Length, Point, Rectangle, Size,
+};
+
+use crate::messages::Message;
+
+// evenly spaced samples drawn from the time window, the rest would only overdraw them
+pub const MAX_POINTS: usize = 4096;
+const POINT_SIZE: f32 = 1.5;
+
+// (side, mid) of each pair of left and right samples, scaled to -1.0..=1.0
+//
+// the conventional goniometer is the left / right plane rotated by 45 degrees, so
+// mono signals draw a vertical line and signals panned hard to one side a diagonal
+pub fn mid_side(left: &[i16], right: &[i16]) -> Vec<(f32, f32)> {
+    let full_scale = -(i16::MIN as f32);
+    left.iter()
+        .zip(right.iter())
+        .map(|(l, r)| {
+            let (l, r) = (*l as f32 / full_scale, *r as f32 / full_scale);
+            ((l - r) / 2.0, (l + r) / 2.0)
+        })
+        .collect()
+}
+
+// xy plot of the stereo image, older samples are drawn dimmer than newer ones
+pub struct Goniometer {
+    points: Vec<(f32, f32)>,
+    fill_proportion: u16,
+    pub cache: Cache,
+}
+
+impl Goniometer {
+    pub fn new(left: &[i16], right: &[i16], fill_proportion: u16) -> Goniometer {
+        let mut goniometer = Goniometer {
+            points: vec![],
+            fill_proportion,
+            cache: Cache::new(),
+        };
+        goniometer.update_samples(left, right);
+        goniometer
+    }
+
+    pub fn update_samples(&mut self, left: &[i16], right: &[i16]) {
+        // rounded up so the decimated points never exceed MAX_POINTS
+        let len = left.len().min(right.len());
+        let step = len.div_ceil(MAX_POINTS).max(1);
+        self.points = mid_side(left, right).into_iter().step_by(step).collect();
+        self.cache.clear();
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let fill_proportion = self.fill_proportion;
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::FillPortion(fill_proportion))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for Goniometer {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let goniometer = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+            // square plot in the middle of the bounds, side and mid each span -0.5..=0.5 at
+            // full scale so a full scale signal on one channel reaches the corners
+            let center = Point::new(bounds.width / 2.0, bounds.height / 2.0);
+            let radius = bounds.width.min(bounds.height) / 2.0;
+            // side is positive toward the left channel, which is drawn on the upper left
+            let to_point = |side: f32, mid: f32| {
+                Point::new(
+                    center.x - side * 2.0 * radius,
+                    center.y - mid * 2.0 * radius,
+                )
+            };
+
+            // mid and side axes, and the diagonals of the left and right channels
+            let guide = Stroke::default()
+                .with_color(Color::from_rgb(0.3, 0.3, 0.3))
+                .with_width(1.0);
+            for (from, to) in [
+                ((0.0, -0.5), (0.0, 0.5)),
+                ((-0.5, 0.0), (0.5, 0.0)),
+                ((-0.5, -0.5), (0.5, 0.5)),
+                ((-0.5, 0.5), (0.5, -0.5)),
+            ]
+            .iter()
+            {
+                frame.stroke(
+                    &Path::line(to_point(from.0, from.1), to_point(to.0, to.1)),
+                    guide,
+                );
+            }
+            frame.fill_text(canvas::Text {
+                content: "L".to_string(),
+                position: to_point(0.5, 0.5),
+                color: Color::WHITE,
+                ..Default::default()
+            });
+            frame.fill_text(canvas::Text {
+                content: "R".to_string(),
+                position: to_point(-0.5, 0.5),
+                color: Color::WHITE,
+                horizontal_alignment: iced::HorizontalAlignment::Right,
+                ..Default::default()
+            });
+
+            let n_points = self.points.len() as f32;
+            for (i, (side, mid)) in self.points.iter().enumerate() {
+                // fades in from the oldest sample to the newest
+                let age = (i + 1) as f32 / n_points;
+                let point = to_point(*side, *mid);
+                frame.fill_rectangle(
+                    Point::new(point.x - POINT_SIZE / 2.0, point.y - POINT_SIZE / 2.0),
+                    Size::new(POINT_SIZE, POINT_SIZE),
+                    Color::from_rgba(0.0, 1.0, 0.5, 0.1 + 0.9 * age),
+                );
+            }
+        });
+        vec![goniometer]
+    }
+}
+
+#[cfg(test)]
+mod test_mid_side {
+    use super::{mid_side, Goniometer, MAX_POINTS};
+
+    #[test]
+    fn mono_is_vertical() {
+        let samples = [-16384, 0, 16384];
+        assert!(mid_side(&samples, &samples)
+            .iter()
+            .all(|(side, _)| *side == 0.0))
+    }
+
+    #[test]
+    fn hard_left_is_diagonal() {
+        assert_eq!(vec![(0.25, 0.25)], mid_side(&[16384], &[0]))
+    }
+
+    #[test]
+    fn out_of_phase_is_horizontal() {
+        assert_eq!(vec![(0.5, 0.0)], mid_side(&[16384], &[-16384]))
+    }
+
+    #[test]
+    fn decimated() {
+        let samples = vec![0; MAX_POINTS * 3];
+        let goniometer = Goniometer::new(&samples, &samples, 1);
+        assert_eq!(MAX_POINTS, goniometer.points.len())
+    }
+
+    #[test]
+    fn decimated_just_past_the_limit() {
+        let samples = vec![0; MAX_POINTS + 1];
+        let goniometer = Goniometer::new(&samples, &samples, 1);
+        assert!(goniometer.points.len() <= MAX_POINTS)
+    }
+}
@@ -1,129 +1,905 @@
 use iced::{
-    canvas::{self, Cache, Canvas, Cursor, Frame, Geometry, Path},
-    Color, Element, Length, Point, Rectangle, Size,
+    canvas::{self, event, Canvas, Cursor, Event, Frame, Geometry, Path, Stroke},
+    keyboard, mouse, Color, Element, Point, Rectangle, Size,
 };
 
+#[cfg(not(feature = "headless"))]
+use iced::canvas::Cache;
+
+#[cfg(feature = "performance")]
+use std::cell::RefCell;
+use std::f64::consts::PI;
+use std::path;
+#[cfg(feature = "performance")]
+use std::time::Instant;
+
+#[cfg(feature = "fftw")]
 use fftw::array::AlignedVec;
+#[cfg(feature = "fftw")]
 use fftw::plan::{R2CPlan, R2CPlan64};
+#[cfg(feature = "fftw")]
 use fftw::types::Flag;
+use num::Complex;
 
+use crate::analysis::annotation::{self, Annotation, TextLabel};
+use crate::analysis::bark::bark_filterbank;
+use crate::analysis::envelope::envelope_follow;
+use crate::analysis::equal_loudness;
+use crate::analysis::features::{peak_frequency, pick_onsets, spectral_flux};
+use crate::analysis::weighting::Weighting;
+use crate::error::SpectrogramError;
+use crate::io::npy;
+use crate::io::png;
 use crate::messages::Message;
-use crate::units::{format_unit, map_normalized, normalize, Mapping, Scale};
+use crate::transform::denoise::{estimate_noise_spectrum, spectral_subtraction};
+use crate::transform::gate::spectral_gate;
+use crate::transform::stft::{mean_magnitudes, ColumnLayout, HopSize, WindowSize};
+use crate::transform::window::WindowFunction;
+use crate::transform::{self, Backend};
+use crate::units::{
+    auto_precision, format_unit, format_unit_with_precision, map_normalized, normalize, Mapping,
+    RangeError, Scale, ScaleRange,
+};
+use crate::widgets::colormap::{difference_color, Colormap};
+#[cfg(feature = "performance")]
+use crate::widgets::frame_counter::FrameCounter;
+use crate::widgets::length;
+use crate::widgets::text_overlay;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum DisplayMode {
+    Magnitude,
+    Phase,
+    // magnitudes summed over critical bands, each bin drawn at the level of its band
+    Bark,
+}
+
+// what magnitudes are divided by to bring them between 0.0 and 1.0
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Normalize {
+    // the loudest bin of their column, so quiet passages are drawn as bright as loud ones
+    #[default]
+    PerColumn,
+    // the loudest bin of the whole spectrogram
+    GlobalMax,
+}
+
+// vertical position of the lower edge of each frequency bin plus the upper edge of the
+// last, clamped to the bounds so bins outside of a zoomed range collapse to nothing
+pub fn bin_edges(n_bins: usize, sample_rate: u32, scale: &Scale, height: f32) -> Vec<f32> {
+    let nyquist = sample_rate as f32 / 2.0;
+    (0..=n_bins)
+        .map(|bin| bin as f32 * nyquist / n_bins as f32)
+        .map(|frequency| normalize(frequency, scale).clamp(0.0, 1.0) * height)
+        .collect()
+}
+
+pub const WINDOW_SIZES: [usize; 5] = [256, 512, 1024, 2048, 4096];
+// drags shorter than this in both directions, in pixels, are clicks
+const DRAG_THRESHOLD: f32 = 4.0;
+// one per Bark between 20 Hz and 20 kHz
+pub const BARK_FILTERS: usize = 24;
+// the start of the time range which denoising assumes is only background noise
+const NOISE_SECONDS: f32 = 0.5;
+// time constants of the amplitude envelope, quick to catch transients and slow to let go of them
+const ENVELOPE_ATTACK_MS: f32 = 5.0;
+const ENVELOPE_RELEASE_MS: f32 = 100.0;
 
 pub struct Grid {
     resolution: (u32, u32),
-    x: Scale,
+    // 0.0 = no overlap, 0.75 = 75% overlap between consecutive fft windows
+    pub overlap: f32,
+    // fft size, independent of the span of samples each column covers
+    pub window_size: usize,
+    // applied to each window before its transform, as in SpectrogramBuilder
+    pub window: WindowFunction,
+    n_columns: usize,
+    pub sample_rate: u32,
+    pub x: Scale,
     pub y: Scale,
     fill_proportion: u16,
     frequencies: Vec<f64>,
-    pub cache: Cache,
+    // denoised and or spectrally gated copy of frequencies, drawn instead of them while
+    // denoise or gate is set
+    pub gate: bool,
+    pub gate_db: f32,
+    pub denoise: bool,
+    // multiple of the noise spectrum subtracted from every column
+    pub denoise_alpha: f64,
+    cleaned: Vec<f64>,
+    // interpolated frequency in Hz of the loudest bin in each column
+    peaks: Vec<f32>,
+    pub show_peak: bool,
+    pub display_mode: DisplayMode,
+    pub normalize: Normalize,
+    // magnitude colors, phase is always drawn around the hue circle
+    pub colormap: Colormap,
+    // samples of a second recording covering the same time as the main samples,
+    // magnitudes are drawn as the difference between the two while it is set
+    pub reference: Option<Vec<i16>>,
+    // placed by clicking on the grid, in Hz
+    pub fundamental: Option<f32>,
+    // where the left button was pressed and where the cursor is now, while it is held
+    drag: Option<(Point, Point)>,
+    pub show_harmonics: bool,
+    // eg. mains hum or a reference pitch, drawn as lines across the grid
+    highlighted_frequencies: Vec<(f32, Color)>,
+    pub annotations: Vec<Annotation>,
+    // drags select a region to annotate rather than zooming while set
+    pub annotating: bool,
+    pub labels: Vec<TextLabel>,
+    // clicks place a text label rather than the fundamental while set
+    pub labeling: bool,
+    // counting the fundamental as the first harmonic
+    pub n_harmonics: usize,
+    pub show_equal_loudness: bool,
+    // one of equal_loudness::PHON_LEVELS
+    pub phon: u32,
+    // applied to normalized magnitudes, results above 1.0 are clipped
+    pub weighting: Weighting,
+    // unnormalized magnitudes of the most recent column
+    latest_magnitudes: Vec<f64>,
+    // peak level of each sample the frequencies were calculated from, in sample units
+    pub envelope: Vec<f32>,
+    // drawn large in the corner, eg. the note detected while listening live
+    pub note_label: Option<String>,
+    pub backend: Backend,
+    // reused between calculations, only reallocated when the window size changes
+    #[cfg(feature = "fftw")]
+    input_buf: AlignedVec<f64>,
+    #[cfg(feature = "fftw")]
+    output_buf: AlignedVec<Complex<f64>>,
+    // not built with the headless feature, there is no window to draw to and iced's Cache
+    // would stop Grid being Sync
+    #[cfg(not(feature = "headless"))]
+    cache: Cache,
+    // draw only borrows the grid, but every call records a frame
+    #[cfg(feature = "performance")]
+    frame_counter: RefCell<FrameCounter>,
 }
 
 impl Grid {
-    pub fn new(width: u32, height: u32, fill_proportion: u16, x: Scale, y: Scale) -> Grid {
+    // the height, and with it the samples in each column, is set with the samples by
+    // set_resolution
+    pub fn new(
+        width: u32,
+        sample_rate: u32,
+        fill_proportion: u16,
+        x: Scale,
+        y: Scale,
+        backend: Backend,
+    ) -> Grid {
         let frequencies: Vec<f64> = vec![];
         Grid {
-            resolution: (width, height),
+            resolution: (width, 0),
+            overlap: 0.0,
+            window_size: 1024,
+            window: WindowFunction::Hann,
+            n_columns: width as usize,
+            sample_rate,
             x,
             y,
             fill_proportion,
             frequencies,
+            gate: false,
+            gate_db: 6.0,
+            denoise: false,
+            denoise_alpha: 1.0,
+            cleaned: vec![],
+            peaks: vec![],
+            show_peak: false,
+            display_mode: DisplayMode::Magnitude,
+            normalize: Normalize::default(),
+            colormap: Colormap::default(),
+            reference: None,
+            fundamental: None,
+            drag: None,
+            show_harmonics: false,
+            highlighted_frequencies: vec![],
+            annotations: vec![],
+            annotating: false,
+            labels: vec![],
+            labeling: false,
+            n_harmonics: 8,
+            show_equal_loudness: false,
+            phon: 40,
+            weighting: Weighting::default(),
+            latest_magnitudes: vec![],
+            envelope: vec![],
+            note_label: None,
+            backend,
+            #[cfg(feature = "fftw")]
+            input_buf: AlignedVec::new(1024),
+            #[cfg(feature = "fftw")]
+            output_buf: AlignedVec::new(513),
+            #[cfg(not(feature = "headless"))]
             cache: Cache::new(),
+            #[cfg(feature = "performance")]
+            frame_counter: RefCell::new(FrameCounter::new()),
         }
     }
 
+    // loads a precomputed spectrogram where matrix[column][bin] is an intensity
+    // between 0.0 and 1.0, as returned by to_matrix
+    pub fn from_matrix(
+        matrix: Vec<Vec<f64>>,
+        sample_rate: u32,
+        fill_proportion: u16,
+        x: Scale,
+        y: Scale,
+    ) -> Result<Grid, SpectrogramError> {
+        let n_bins = matrix.first().map_or(0, |column| column.len());
+        if n_bins < 2 {
+            return Err(SpectrogramError::ShapeMismatch {
+                expected: 2,
+                found: n_bins,
+            });
+        }
+        if let Some(column) = matrix.iter().find(|column| column.len() != n_bins) {
+            return Err(SpectrogramError::ShapeMismatch {
+                expected: n_bins,
+                found: column.len(),
+            });
+        }
+
+        let window_size = (n_bins - 1) * 2;
+        let n_columns = matrix.len();
+        let mut grid = Grid::new(
+            n_columns as u32,
+            sample_rate,
+            fill_proportion,
+            x,
+            y,
+            Backend::default(),
+        );
+        // each column as if it came from exactly one window
+        grid.resolution.1 = (n_bins - 1) as u32;
+        grid.window_size = window_size;
+        grid.n_columns = n_columns;
+        grid.peaks = matrix
+            .iter()
+            .map(|column| peak_frequency(column, sample_rate, window_size))
+            .collect();
+        grid.frequencies = matrix.into_iter().flatten().collect();
+        Ok(grid)
+    }
+
     pub fn update_frequencies(&mut self, resolution: (u32, u32), samples: &Vec<i16>) {
+        self.set_resolution(resolution, samples);
+    }
+
+    // the resolution and the frequencies calculated from it always change together
+    pub fn set_resolution(&mut self, resolution: (u32, u32), samples: &[i16]) {
         self.resolution = resolution;
+        self.set_samples(samples);
+    }
+
+    // new samples at the current resolution, eg. another channel of the same file
+    pub fn set_samples(&mut self, samples: &[i16]) {
         self.calculate_frequencies(samples);
+        self.update_cleaned();
+        self.envelope = envelope_follow(
+            samples,
+            ENVELOPE_ATTACK_MS,
+            ENVELOPE_RELEASE_MS,
+            self.sample_rate,
+        );
+    }
+
+    // zooming only changes which frequencies are drawn, the spectra stay as they are
+    pub fn set_frequency_range(&mut self, min: f32, max: f32) -> Result<ScaleRange, RangeError> {
+        let range = ScaleRange::new(min, max)?;
+        self.y.range = range;
+        self.clear_cache();
+        Ok(range)
+    }
+
+    pub fn clear_cache(&mut self) {
+        #[cfg(not(feature = "headless"))]
         self.cache.clear();
     }
 
-    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+    // size is (width, height) in pixels, None to fill the grid's portion of the window
+    pub fn view<'a>(&'a mut self, size: Option<(u16, u16)>) -> Element<'a, Message> {
         let fill_proportion = self.fill_proportion;
         Canvas::new(self)
-            .width(Length::FillPortion(fill_proportion))
-            .height(Length::FillPortion(fill_proportion))
+            .width(length(fill_proportion, size.map(|size| size.0)))
+            .height(length(fill_proportion, size.map(|size| size.1)))
             .into()
     }
 
-    fn calculate_frequencies(&mut self, samples: &Vec<i16>) {
-        self.frequencies = vec![];
-        let n_columns = self.resolution.0 as usize;
-        let n_rows = self.resolution.1 as usize * 2;
+    // magnitude of each frequency bin averaged over all time columns
+    pub fn average_spectrum(&self) -> Vec<f64> {
+        let n_bins = self.n_bins();
+        let mut average = vec![0.0; n_bins];
+        for column in self.frequencies.chunks_exact(n_bins) {
+            for (bin, magnitude) in column.iter().enumerate() {
+                average[bin] += magnitude / self.n_columns as f64;
+            }
+        }
+        average
+    }
 
-        let f64_samples: Vec<f64> = samples.iter().map(|x| *x as f64).collect();
+    // recalculates the cleaned copy after denoise, gate, their settings or the frequencies
+    // change, phase has no noise floor so it is never cleaned
+    pub fn update_cleaned(&mut self) {
+        self.cleaned = vec![];
+        if (self.denoise || self.gate) && self.display_mode == DisplayMode::Magnitude {
+            let mut matrix = self.to_matrix();
+            if self.denoise {
+                let noise_columns = (NOISE_SECONDS * self.hop_rate()).ceil() as usize;
+                let noise = estimate_noise_spectrum(&matrix, noise_columns.max(1));
+                for column in matrix.iter_mut() {
+                    *column = spectral_subtraction(column, &noise, self.denoise_alpha);
+                }
+            }
+            if self.gate {
+                spectral_gate(&mut matrix, self.gate_db);
+            }
+            self.cleaned = matrix.into_iter().flatten().collect();
+        }
+        self.clear_cache();
+    }
+
+    // matrix[column][bin] is the intensity of that time frequency cell
+    pub fn to_matrix(&self) -> Vec<Vec<f64>> {
+        self.frequencies
+            .chunks_exact(self.n_bins())
+            .map(|column| column.to_vec())
+            .collect()
+    }
 
-        let mut plan: R2CPlan64 =
-            R2CPlan::aligned(&[n_rows], Flag::MEASURE).expect("plan to create");
-        let mut inputs = AlignedVec::new(n_rows);
-        let mut outputs = AlignedVec::new(n_rows / 2 + 1);
+    // the intensities drawn, image[row][column] with the highest frequency bin in the top row
+    // and one column per time column, eg. to check the output without a window
+    pub fn snapshot(&self) -> Vec<Vec<f32>> {
+        let n_bins = self.n_bins();
+        let intensities = if self.cleaned.is_empty() {
+            &self.frequencies
+        } else {
+            &self.cleaned
+        };
+        (0..n_bins)
+            .rev()
+            .map(|bin| {
+                intensities
+                    .chunks_exact(n_bins)
+                    .map(|column| column[bin] as f32)
+                    .collect()
+            })
+            .collect()
+    }
 
-        for column in 0..n_columns {
-            let start = column * n_rows;
-            let end = (column + 1) * n_rows;
-            inputs.copy_from_slice(&f64_samples[start..end]);
-            plan.r2c(&mut inputs, &mut outputs)
-                .expect("fftw dft to execute");
-            let real: Vec<f64> = outputs.iter().map(|x| x.norm()).collect();
-            let max = real.iter().map(|x| *x as u64).max().unwrap() as f64;
-            let mut normalized: Vec<f64> = real.iter().map(|x| x / max).collect();
-            self.frequencies.append(&mut normalized);
+    // one row per time column, one value per frequency bin
+    pub fn to_npy(&self, path: &path::Path) -> Result<(), SpectrogramError> {
+        let n_bins = self.n_bins();
+        npy::to_npy(&self.frequencies, (self.n_columns, n_bins), path)
+    }
+
+    // the snapshot through the colormap, a pixel per cell
+    pub fn snapshot_png(&self) -> Result<Vec<u8>, SpectrogramError> {
+        let image = self.snapshot();
+        let height = image.len();
+        let width = image.first().map_or(0, |row| row.len());
+        let mut pixels = Vec::with_capacity(width * height * 3);
+        for intensity in image.iter().flatten() {
+            let color = self.colormap.color(*intensity);
+            pixels.extend_from_slice(&[
+                (color.r * 255.0).round() as u8,
+                (color.g * 255.0).round() as u8,
+                (color.b * 255.0).round() as u8,
+            ]);
         }
+        png::encode_rgb(&pixels, width as u32, height as u32)
     }
-}
 
-impl canvas::Program<Message> for Grid {
-    fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
-        let grid = self.cache.draw(bounds.size(), |frame| {
-            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+    // spectral flux between consecutive columns, the first column has no predecessor
+    pub fn onset_function(&self) -> Vec<f64> {
+        let n_bins = self.n_bins();
+        let columns: Vec<&[f64]> = self.frequencies.chunks_exact(n_bins).collect();
+        let mut onset_function = vec![0.0];
+        for pair in columns.windows(2) {
+            onset_function.push(spectral_flux(pair[0], pair[1]));
+        }
+        onset_function
+    }
 
-            let n_rows = self.resolution.0;
-            let n_columns = self.resolution.1 + 1;
+    // first sample of each column which pick_onsets marks as an onset. threshold is a
+    // proportion of the largest spectral flux, so one setting suits every window size
+    pub fn onsets(&self, threshold: f64) -> Vec<usize> {
+        let onset_function = self.onset_function();
+        let largest = onset_function.iter().cloned().fold(0.0, f64::max);
+        let hop_size = self.hop_size().0;
+        pick_onsets(&onset_function, threshold * largest)
+            .into_iter()
+            .map(|column| column * hop_size)
+            .collect()
+    }
 
-            let linear_y_scale = Scale {
-                unit: self.y.unit,
-                min: self.y.min,
-                max: self.y.max,
-                mapping: Mapping::Linear,
-            };
-            let mut y_positions: Vec<f32> = linear_y_scale
-                .evenly_spaced_values(n_columns as usize, false)
+    // multiples of the fundamental above it, up to the nyquist frequency
+    pub fn harmonics(&self) -> Vec<f32> {
+        let nyquist = self.sample_rate as f32 / 2.0;
+        match self.fundamental {
+            Some(fundamental) if fundamental > 0.0 => (2..=self.n_harmonics)
+                .map(|k| k as f32 * fundamental)
+                .take_while(|frequency| *frequency <= nyquist)
+                .collect(),
+            _ => vec![],
+        }
+    }
+
+    pub fn highlight_frequency(&mut self, hz: f32, color: Color) {
+        self.highlighted_frequencies.push((hz, color));
+        self.clear_cache();
+    }
+
+    pub fn clear_highlights(&mut self) {
+        self.highlighted_frequencies.clear();
+        self.clear_cache();
+    }
+
+    pub fn latest_magnitudes(&self) -> &[f64] {
+        &self.latest_magnitudes
+    }
+
+    pub fn n_columns(&self) -> usize {
+        self.n_columns
+    }
+
+    pub fn n_bins(&self) -> usize {
+        self.window_size / 2 + 1
+    }
+
+    // number of samples spanned by each column before overlap is taken into account
+    fn column_span(&self) -> usize {
+        self.resolution.1 as usize * 2
+    }
+
+    fn hop_size(&self) -> HopSize {
+        HopSize::from_overlap(WindowSize(self.column_span()), self.overlap)
+    }
+
+    // columns per second
+    pub fn hop_rate(&self) -> f32 {
+        self.sample_rate as f32 / self.hop_size().0 as f32
+    }
+
+    // spectra of the windows covering each column, also sets the number of columns
+    //
+    // columns longer than the window are split into several windows by segment_starts so none
+    // of their samples are skipped, shorter ones are a single zero padded window
+    fn column_spectra(&mut self, samples: &[i16]) -> Vec<Vec<Vec<Complex<f64>>>> {
+        let window_size = self.window_size;
+        // only uses windows which fit in the span covered by the non-overlapping columns
+        let layout = ColumnLayout::new(
+            self.resolution.0 as usize,
+            self.column_span(),
+            self.hop_size(),
+            WindowSize(window_size),
+            self.window,
+        );
+        let n_columns = layout.starts.len();
+        self.n_columns = n_columns;
+
+        // planned once per calculation rather than once per window
+        #[cfg(feature = "fftw")]
+        let mut plan: Option<R2CPlan64> = match self.backend {
+            Backend::Fftw => {
+                if self.input_buf.len() != window_size {
+                    self.input_buf = AlignedVec::new(window_size);
+                    self.output_buf = AlignedVec::new(window_size / 2 + 1);
+                }
+                Some(R2CPlan::aligned(&[window_size], Flag::MEASURE).expect("plan to create"))
+            }
+            _ => None,
+        };
+
+        let mut spectra = Vec::with_capacity(n_columns);
+        for column_index in 0..n_columns {
+            let mut column = Vec::with_capacity(layout.segments.len());
+            for segment in 0..layout.segments.len() {
+                let frame = layout.frame(samples, column_index, segment);
+                #[cfg(feature = "fftw")]
+                let spectrum: Vec<Complex<f64>> = match plan.as_mut() {
+                    Some(plan) => {
+                        self.input_buf.copy_from_slice(&frame);
+                        plan.r2c(&mut self.input_buf, &mut self.output_buf)
+                            .expect("fftw dft to execute");
+                        self.output_buf.to_vec()
+                    }
+                    None => transform::fourier_transform(&frame, self.backend),
+                };
+                #[cfg(not(feature = "fftw"))]
+                let spectrum = transform::fourier_transform(&frame, self.backend);
+                column.push(spectrum);
+            }
+            spectra.push(column);
+        }
+        spectra
+    }
+
+    // None for drags short enough to be clicks
+    fn drag_message(&self, start: Point, end: Point, size: Size) -> Option<Message> {
+        let (dx, dy) = ((end.x - start.x).abs(), (end.y - start.y).abs());
+        if dx < DRAG_THRESHOLD && dy < DRAG_THRESHOLD {
+            return None;
+        }
+        if self.annotating {
+            let (left, right) = (start.x.min(end.x), start.x.max(end.x));
+            let (top, bottom) = (start.y.min(end.y), start.y.max(end.y));
+            return Some(Message::AnnotationRegionSelected(Annotation {
+                time_start: map_normalized(left / size.width, &self.x),
+                time_end: map_normalized(right / size.width, &self.x),
+                freq_min: map_normalized(1.0 - bottom / size.height, &self.y),
+                freq_max: map_normalized(1.0 - top / size.height, &self.y),
+                label: String::new(),
+                color: annotation::DEFAULT_COLOR,
+            }));
+        }
+        if dx > dy {
+            let (from, to) = (start.x.min(end.x), start.x.max(end.x));
+            Some(Message::TimeRangeChanged {
+                start_sec: map_normalized(from / size.width, &self.x).max(0.0),
+                end_sec: map_normalized(to / size.width, &self.x),
+            })
+        } else {
+            let a = map_normalized(1.0 - start.y / size.height, &self.y);
+            let b = map_normalized(1.0 - end.y / size.height, &self.y);
+            Some(Message::FrequencyRangeChanged {
+                min: a.min(b).max(0.0),
+                max: a.max(b).min(self.sample_rate as f32 / 2.0),
+            })
+        }
+    }
+
+    // comparing replaces magnitudes with their difference from the reference
+    pub fn comparing(&self) -> bool {
+        self.reference.is_some() && self.display_mode == DisplayMode::Magnitude
+    }
+
+    fn calculate_frequencies(&mut self, samples: &[i16]) {
+        self.frequencies = vec![];
+        self.peaks = vec![];
+        self.latest_magnitudes = vec![];
+        let spectra = self.column_spectra(samples);
+        let same_length = self.reference.as_ref().map(|r| r.len()) == Some(samples.len());
+        let reference_spectra = if self.comparing() && same_length {
+            // taken so self can be borrowed mutably while the reference is transformed
+            let reference = self.reference.take().unwrap_or_default();
+            let spectra = self.column_spectra(&reference);
+            self.reference = Some(reference);
+            Some(spectra)
+        } else {
+            None
+        };
+
+        // normalized in a separate pass so the maximum can come from one column or all of them
+        let magnitudes: Vec<Vec<f64>> = spectra
+            .iter()
+            .map(|column| mean_magnitudes(column))
+            .collect();
+        self.peaks = magnitudes
+            .iter()
+            .map(|real| peak_frequency(real, self.sample_rate, self.window_size))
+            .collect();
+        self.latest_magnitudes = magnitudes.last().cloned().unwrap_or_default();
+
+        let gains = self.weighting.gains(self.n_bins(), self.sample_rate);
+        let normalize = self.normalize;
+        let weighted = |magnitudes: &[Vec<f64>]| -> Vec<Vec<f64>> {
+            normalize_columns(magnitudes, normalize)
                 .iter()
-                .map(|value| normalize(*value, &self.y))
-                .map(|normalized| normalized * bounds.height)
-                .collect();
-            y_positions.push(bounds.height);
-
-            let linear_x_scale = Scale {
-                unit: self.x.unit,
-                min: self.x.min,
-                max: self.x.max,
-                mapping: Mapping::Linear,
-            };
-            let mut x_positions: Vec<f32> = linear_x_scale
-                .evenly_spaced_values(n_rows as usize, false)
+                .map(|column| {
+                    column
+                        .iter()
+                        .zip(gains.iter())
+                        .map(|(magnitude, gain)| (magnitude * gain).min(1.0))
+                        .collect()
+                })
+                .collect()
+        };
+
+        match self.display_mode {
+            DisplayMode::Magnitude => {
+                let mut columns = weighted(&magnitudes);
+                if let Some(reference_spectra) = &reference_spectra {
+                    let reference: Vec<Vec<f64>> = reference_spectra
+                        .iter()
+                        .map(|column| mean_magnitudes(column))
+                        .collect();
+                    // -1.0..1.0 mapped onto 0.0..1.0, 0.5 where both are equally loud
+                    for (main, reference) in columns
+                        .iter_mut()
+                        .flatten()
+                        .zip(weighted(&reference).into_iter().flatten())
+                    {
+                        *main = (*main - reference + 1.0) / 2.0;
+                    }
+                }
+                self.frequencies = columns.into_iter().flatten().collect();
+            }
+            DisplayMode::Phase => {
+                // phases of separate windows do not average, so each column shows its first
+                // -pi..pi mapped onto 0.0..1.0
+                self.frequencies = spectra
+                    .iter()
+                    .flat_map(|column| column[0].iter())
+                    .map(|x| (x.arg() + PI) / (2.0 * PI))
+                    .collect();
+            }
+            DisplayMode::Bark => {
+                let filterbank = bark_filterbank(BARK_FILTERS, self.window_size, self.sample_rate);
+                // the band each bin belongs to most, None outside of the filterbank
+                let band_of_bin: Vec<Option<usize>> = (0..self.n_bins())
+                    .map(|bin| {
+                        filterbank
+                            .iter()
+                            .enumerate()
+                            .filter(|(_, filter)| filter[bin] > 0.0)
+                            .max_by(|a, b| a.1[bin].partial_cmp(&b.1[bin]).unwrap())
+                            .map(|(band, _)| band)
+                    })
+                    .collect();
+                let energies: Vec<Vec<f64>> = magnitudes
+                    .iter()
+                    .map(|real| {
+                        filterbank
+                            .iter()
+                            .map(|filter| {
+                                filter
+                                    .iter()
+                                    .zip(real.iter())
+                                    .map(|(weight, magnitude)| *weight as f64 * magnitude)
+                                    .sum()
+                            })
+                            .collect()
+                    })
+                    .collect();
+                self.frequencies = normalize_columns(&energies, normalize)
+                    .iter()
+                    .flat_map(|normalized| {
+                        band_of_bin
+                            .iter()
+                            .map(move |band| band.map_or(0.0, |band| normalized[band]))
+                    })
+                    .collect();
+            }
+        }
+    }
+}
+
+// every column divided by its own loudest magnitude, or every one by the loudest overall
+fn normalize_columns(columns: &[Vec<f64>], normalize: Normalize) -> Vec<Vec<f64>> {
+    match normalize {
+        Normalize::PerColumn => columns
+            .iter()
+            .map(|column| normalized_magnitudes(column))
+            .collect(),
+        Normalize::GlobalMax => {
+            // silence stays at zero rather than dividing by zero
+            let max = columns
                 .iter()
-                .map(|value| normalize(*value, &self.x))
-                .map(|normalized| normalized * bounds.width)
-                .collect();
-            x_positions.push(bounds.width);
-
-            let mut index = 0;
-            for row in 0..n_rows {
-                for column in 0..n_columns {
-                    let height = y_positions[(column + 1) as usize] - y_positions[column as usize];
-                    let cell = Size::new(bounds.width / (n_rows as f32), -height);
-                    let point = Point::new(
-                        x_positions[row as usize],
-                        bounds.height - y_positions[column as usize],
-                    );
-                    let inner_rec = Path::rectangle(point, cell);
-                    let intensity = self.frequencies[index] as f32;
-                    frame.fill(&inner_rec, Color::from_rgb(intensity, 0.0, intensity));
+                .flatten()
+                .fold(f64::MIN_POSITIVE, |max, magnitude| max.max(*magnitude));
+            columns
+                .iter()
+                .map(|column| column.iter().map(|magnitude| magnitude / max).collect())
+                .collect()
+        }
+    }
+}
+
+// silence stays at zero rather than dividing by zero
+fn normalized_magnitudes(magnitudes: &[f64]) -> Vec<f64> {
+    let max = magnitudes.iter().cloned().fold(0.0, f64::max);
+    if max == 0.0 {
+        return vec![0.0; magnitudes.len()];
+    }
+    magnitudes.iter().map(|x| x / max).collect()
+}
+
+impl Grid {
+    fn draw_spectrogram(&self, frame: &mut Frame, bounds: Rectangle) {
+        frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+        let n_rows = self.n_columns as u32;
+        let n_columns = self.n_bins() as u32;
+
+        let y_positions = bin_edges(n_columns as usize, self.sample_rate, &self.y, bounds.height);
+
+        let linear_x_scale = self.x.clone().with_mapping(Mapping::Linear);
+        let mut x_positions: Vec<f32> = linear_x_scale
+            .evenly_spaced_values(n_rows as usize, false)
+            .iter()
+            .map(|value| normalize(*value, &self.x))
+            .map(|normalized| normalized * bounds.width)
+            .collect();
+        x_positions.push(bounds.width);
+
+        let intensities = if self.cleaned.is_empty() {
+            &self.frequencies
+        } else {
+            &self.cleaned
+        };
+        let mut index = 0;
+        for row in 0..n_rows {
+            for column in 0..n_columns {
+                let height = y_positions[(column + 1) as usize] - y_positions[column as usize];
+                if height == 0.0 {
                     index += 1;
+                    continue;
                 }
+                let cell = Size::new(bounds.width / (n_rows as f32), -height);
+                let point = Point::new(
+                    x_positions[row as usize],
+                    bounds.height - y_positions[column as usize],
+                );
+                let inner_rec = Path::rectangle(point, cell);
+                let intensity = intensities[index] as f32;
+                let color = match self.display_mode {
+                    DisplayMode::Magnitude if self.comparing() => difference_color(intensity),
+                    DisplayMode::Magnitude | DisplayMode::Bark => self.colormap.color(intensity),
+                    DisplayMode::Phase => hsv_to_rgb(intensity, 1.0, 1.0),
+                };
+                frame.fill(&inner_rec, color);
+                index += 1;
+            }
+        }
+
+        if self.show_peak {
+            let visible = self.y.range.min()..=self.y.range.max();
+            for (column, peak) in self.peaks.iter().enumerate() {
+                if !visible.contains(peak) {
+                    continue;
+                }
+                let x = (x_positions[column] + x_positions[column + 1]) / 2.0;
+                let y = bounds.height - normalize(*peak, &self.y) * bounds.height;
+                frame.fill(&Path::circle(Point::new(x, y), 2.0), Color::WHITE);
+            }
+        }
+    }
+}
+
+const DASH_LENGTH: f32 = 6.0;
+const DASH_GAP: f32 = 4.0;
+// sound pressure level drawn at the right edge of the grid by the equal-loudness contour
+const CONTOUR_MAX_DB: f32 = 130.0;
+
+impl Grid {
+    // level in dB SPL across, frequency up, points outside of the frequency range are skipped
+    fn draw_equal_loudness(&self, frame: &mut Frame, bounds: Rectangle) {
+        let contour = match equal_loudness::contour(self.phon) {
+            Some(contour) if self.show_equal_loudness => contour,
+            _ => return,
+        };
+        let points: Vec<Point> = contour
+            .iter()
+            .map(|(hz, db)| (normalize(*hz, &self.y), *db))
+            .filter(|(normalized, _)| (0.0..=1.0).contains(normalized))
+            .map(|(normalized, db)| {
+                Point::new(
+                    (db / CONTOUR_MAX_DB).clamp(0.0, 1.0) * bounds.width,
+                    bounds.height - normalized * bounds.height,
+                )
+            })
+            .collect();
+        if points.len() < 2 {
+            return;
+        }
+        let curve = Path::new(|builder| {
+            builder.move_to(points[0]);
+            for point in &points[1..] {
+                builder.line_to(*point);
             }
         });
+        let stroke = Stroke::default()
+            .with_color(Color::from_rgba(1.0, 1.0, 1.0, 0.5))
+            .with_width(2.0);
+        frame.stroke(&curve, stroke);
+    }
+
+    // translucent rectangles labelled in their upper left corner, cut off at the edges
+    fn draw_annotations(&self, frame: &mut Frame, bounds: Rectangle) {
+        for annotation in &self.annotations {
+            let left = normalize(annotation.time_start, &self.x).clamp(0.0, 1.0);
+            let right = normalize(annotation.time_end, &self.x).clamp(0.0, 1.0);
+            let bottom = normalize(annotation.freq_min, &self.y).clamp(0.0, 1.0);
+            let top = normalize(annotation.freq_max, &self.y).clamp(0.0, 1.0);
+            // reversed scales swap which end is drawn where
+            let (left, right) = (left.min(right), left.max(right));
+            let (bottom, top) = (bottom.min(top), bottom.max(top));
+            if right <= left || top <= bottom {
+                continue;
+            }
+            let corner = Point::new(left * bounds.width, bounds.height - top * bounds.height);
+            let size = Size::new(
+                (right - left) * bounds.width,
+                (top - bottom) * bounds.height,
+            );
+            let color = annotation.color;
+            frame.fill_rectangle(corner, size, Color { a: 0.25, ..color });
+            frame.stroke(
+                &Path::rectangle(corner, size),
+                Stroke::default().with_color(color).with_width(1.0),
+            );
+            frame.fill_text(canvas::Text {
+                content: annotation.label.clone(),
+                position: Point::new(corner.x + 2.0, corner.y + 2.0),
+                color,
+                ..Default::default()
+            });
+        }
+    }
+
+    // highlights outside of the frequency range are skipped
+    fn draw_highlights(&self, frame: &mut Frame, bounds: Rectangle) {
+        for (hz, color) in &self.highlighted_frequencies {
+            let normalized = normalize(*hz, &self.y);
+            if !(0.0..=1.0).contains(&normalized) {
+                continue;
+            }
+            let y = bounds.height - normalized * bounds.height;
+            let line = Path::line(Point::new(0.0, y), Point::new(bounds.width, y));
+            frame.stroke(&line, Stroke::default().with_color(*color).with_width(2.0));
+        }
+    }
+
+    fn draw_harmonics(&self, frame: &mut Frame, bounds: Rectangle) {
+        let fundamental = match self.fundamental {
+            Some(fundamental) => fundamental,
+            None => return,
+        };
+        let stroke = Stroke::default().with_color(Color::WHITE).with_width(1.0);
+        let y = bounds.height - normalize(fundamental, &self.y) * bounds.height;
+        let marker = Path::line(Point::new(0.0, y), Point::new(bounds.width, y));
+        frame.stroke(&marker, stroke);
+
+        if !self.show_harmonics {
+            return;
+        }
+        for harmonic in self.harmonics() {
+            let y = bounds.height - normalize(harmonic, &self.y) * bounds.height;
+            let dashes = Path::new(|builder| {
+                let mut x = 0.0;
+                while x < bounds.width {
+                    builder.move_to(Point::new(x, y));
+                    builder.line_to(Point::new((x + DASH_LENGTH).min(bounds.width), y));
+                    x += DASH_LENGTH + DASH_GAP;
+                }
+            });
+            frame.stroke(&dashes, stroke);
+        }
+    }
+}
+
+// hue in 0.0..=1.0 wraps around, so 0.0 and 1.0 are both red
+fn hsv_to_rgb(hue: f32, saturation: f32, value: f32) -> Color {
+    let h = (hue.rem_euclid(1.0)) * 6.0;
+    let c = value * saturation;
+    let x = c * (1.0 - (h % 2.0 - 1.0).abs());
+    let (r, g, b) = match h as u32 {
+        0 => (c, x, 0.0),
+        1 => (x, c, 0.0),
+        2 => (0.0, c, x),
+        3 => (0.0, x, c),
+        4 => (x, 0.0, c),
+        _ => (c, 0.0, x),
+    };
+    let m = value - c;
+    Color::from_rgb(r + m, g + m, b + m)
+}
+
+impl canvas::Program<Message> for Grid {
+    fn draw(&self, bounds: Rectangle, cursor: Cursor) -> Vec<Geometry> {
+        #[cfg(not(feature = "headless"))]
+        let grid = self
+            .cache
+            .draw(bounds.size(), |frame| self.draw_spectrogram(frame, bounds));
+        #[cfg(feature = "headless")]
+        let grid = {
+            let mut frame = Frame::new(bounds.size());
+            self.draw_spectrogram(&mut frame, bounds);
+            frame.into_geometry()
+        };
 
         let cursor_position = cursor.position().unwrap_or(Point::new(0.0, 0.0));
 
@@ -131,27 +907,827 @@ impl canvas::Program<Message> for Grid {
         let normalized_y = 1.0 - (cursor_position.y - bounds.y) / bounds.height;
 
         let x_unit = format_unit(map_normalized(normalized_x, &self.x), &self.x.unit);
-        let y_unit = format_unit(map_normalized(normalized_y, &self.y), &self.y.unit);
+        let y_value = map_normalized(normalized_y, &self.y);
+        let y_unit = format_unit_with_precision(y_value, &self.y.unit, auto_precision(y_value));
 
-        if bounds.contains(cursor_position) {
-            let overlay = {
-                let mut frame = Frame::new(bounds.size());
-                let text_position = Point::new(0.0, bounds.height);
-                let text = canvas::Text {
-                    position: text_position,
-                    content: format!("x: {}\ny: {}", x_unit, y_unit),
+        // drawn outside of the cache so moving the marker does not recalculate the grid
+        let mut frame = Frame::new(bounds.size());
+        self.draw_annotations(&mut frame, bounds);
+        text_overlay::draw_labels(&mut frame, &self.labels, &self.x, &self.y, bounds);
+        self.draw_highlights(&mut frame, bounds);
+        self.draw_harmonics(&mut frame, bounds);
+        self.draw_equal_loudness(&mut frame, bounds);
+        if let Some(note_label) = &self.note_label {
+            frame.fill_text(canvas::Text {
+                position: Point::new(bounds.width - 10.0, 10.0),
+                content: note_label.clone(),
+                color: Color::WHITE,
+                size: 48.0,
+                horizontal_alignment: iced::HorizontalAlignment::Right,
+                ..Default::default()
+            });
+        }
+        #[cfg(feature = "performance")]
+        {
+            let mut frame_counter = self.frame_counter.borrow_mut();
+            frame_counter.record(Instant::now());
+            if let Some(fps) = frame_counter.fps() {
+                frame.fill_text(canvas::Text {
+                    position: Point::new(10.0, 10.0),
+                    content: format!("{:.0} fps", fps),
                     color: Color::WHITE,
-                    vertical_alignment: iced::VerticalAlignment::Bottom,
-                    horizontal_alignment: iced::HorizontalAlignment::Left,
                     ..Default::default()
-                };
-                frame.fill_text(text);
-                frame.into_geometry()
+                });
+            }
+        }
+        if let Some((start, end)) = self.drag {
+            frame.fill_rectangle(
+                Point::new(start.x.min(end.x), start.y.min(end.y)),
+                Size::new((end.x - start.x).abs(), (end.y - start.y).abs()),
+                Color::from_rgba(1.0, 1.0, 1.0, 0.2),
+            );
+        }
+        if bounds.contains(cursor_position) {
+            let text_position = Point::new(0.0, bounds.height);
+            let text = canvas::Text {
+                position: text_position,
+                content: format!("x: {}\ny: {}", x_unit, y_unit),
+                color: Color::WHITE,
+                vertical_alignment: iced::VerticalAlignment::Bottom,
+                horizontal_alignment: iced::HorizontalAlignment::Left,
+                ..Default::default()
             };
+            frame.fill_text(text);
+        }
 
-            vec![grid, overlay]
-        } else {
-            vec![grid]
+        vec![grid, frame.into_geometry()]
+    }
+
+    fn update(
+        &mut self,
+        event: Event,
+        bounds: Rectangle,
+        cursor: Cursor,
+    ) -> (event::Status, Option<Message>) {
+        match (event, cursor.position_in(&bounds)) {
+            (Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left)), Some(position)) => {
+                self.drag = Some((position, position));
+                (event::Status::Captured, None)
+            }
+            (Event::Mouse(mouse::Event::CursorMoved { .. }), _) if self.drag.is_some() => {
+                if let (Some((start, _)), Some(cursor_position)) = (self.drag, cursor.position()) {
+                    // kept within the grid so the selection never goes past its edges
+                    let end = Point::new(
+                        (cursor_position.x - bounds.x).clamp(0.0, bounds.width),
+                        (cursor_position.y - bounds.y).clamp(0.0, bounds.height),
+                    );
+                    self.drag = Some((start, end));
+                }
+                (event::Status::Captured, None)
+            }
+            // a click places the fundamental, or a text label while labeling, a drag zooms to
+            // the selected frequencies or times, whichever it covers more of
+            (Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left)), _) => {
+                match self.drag.take() {
+                    Some((start, end)) => {
+                        let message = self.drag_message(start, end, bounds.size());
+                        if message.is_some() {
+                            return (event::Status::Captured, message);
+                        }
+                        let normalized_y = 1.0 - start.y / bounds.height;
+                        let frequency = map_normalized(normalized_y, &self.y);
+                        if self.labeling {
+                            let label = TextLabel {
+                                time: map_normalized(start.x / bounds.width, &self.x),
+                                frequency,
+                                text: String::new(),
+                            };
+                            return (
+                                event::Status::Captured,
+                                Some(Message::TextLabelPlaced(label)),
+                            );
+                        }
+                        self.fundamental = Some(frequency);
+                        (event::Status::Captured, None)
+                    }
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            (
+                Event::Keyboard(keyboard::Event::KeyPressed {
+                    key_code: keyboard::KeyCode::Delete,
+                    ..
+                }),
+                Some(position),
+            ) => {
+                let bounds = Rectangle::new(Point::ORIGIN, bounds.size());
+                match text_overlay::label_at(&self.labels, position, &self.x, &self.y, bounds) {
+                    Some(i) => (event::Status::Captured, Some(Message::TextLabelRemoved(i))),
+                    None => (event::Status::Ignored, None),
+                }
+            }
+            // zooms the frequency axis around the cursor, or the time axis when
+            // scrolling sideways
+            (Event::Mouse(mouse::Event::WheelScrolled { delta }), Some(position)) => {
+                let (scrolled_x, scrolled_y) = match delta {
+                    mouse::ScrollDelta::Lines { x, y } => (x, y),
+                    mouse::ScrollDelta::Pixels { x, y } => (x, y),
+                };
+                if scrolled_x.abs() > scrolled_y.abs() {
+                    let factor = if scrolled_x > 0.0 { 0.8 } else { 1.25 };
+                    let anchor = map_normalized(position.x / bounds.width, &self.x);
+                    let message =
+                        self.x
+                            .zoom(anchor, factor)
+                            .ok()
+                            .map(|range| Message::TimeRangeChanged {
+                                start_sec: range.min().max(0.0),
+                                end_sec: range.max(),
+                            });
+                    return (event::Status::Captured, message);
+                }
+                let factor = if scrolled_y > 0.0 { 0.8 } else { 1.25 };
+                let anchor = map_normalized(1.0 - position.y / bounds.height, &self.y);
+                let nyquist = self.sample_rate as f32 / 2.0;
+                let message =
+                    self.y
+                        .zoom(anchor, factor)
+                        .ok()
+                        .map(|range| Message::FrequencyRangeChanged {
+                            min: range.min().max(0.0),
+                            max: range.max().min(nyquist),
+                        });
+                (event::Status::Captured, message)
+            }
+            _ => (event::Status::Ignored, None),
+        }
+    }
+}
+
+// ten seconds across, every frequency up to nyquist
+#[cfg(test)]
+fn test_grid(width: u32, sample_rate: u32) -> Grid {
+    let x = Scale::default()
+        .with_unit(crate::units::Unit::Second)
+        .with_range(ScaleRange::new(0.0, 10.0).unwrap());
+    let y = Scale::default().with_range(ScaleRange::new(0.0, sample_rate as f32 / 2.0).unwrap());
+    Grid::new(width, sample_rate, 1, x, y, Backend::default())
+}
+
+#[cfg(test)]
+mod test_thread_safety {
+    use super::Grid;
+
+    fn assert_send<T: Send>() {}
+
+    #[cfg(feature = "headless")]
+    fn assert_sync<T: Sync>() {}
+
+    #[test]
+    fn grid_is_send() {
+        assert_send::<Grid>();
+    }
+
+    // only without a window, iced's Cache is never Sync
+    #[cfg(feature = "headless")]
+    #[test]
+    fn headless_grid_is_sync() {
+        assert_sync::<Grid>();
+    }
+}
+
+#[cfg(test)]
+mod test_hsv_to_rgb {
+    use super::hsv_to_rgb;
+    use iced::Color;
+
+    #[test]
+    fn primaries() {
+        assert_eq!(Color::from_rgb(1.0, 0.0, 0.0), hsv_to_rgb(0.0, 1.0, 1.0));
+        assert_eq!(
+            Color::from_rgb(0.0, 1.0, 0.0),
+            hsv_to_rgb(1.0 / 3.0, 1.0, 1.0)
+        );
+        assert_eq!(
+            Color::from_rgb(0.0, 0.0, 1.0),
+            hsv_to_rgb(2.0 / 3.0, 1.0, 1.0)
+        );
+    }
+
+    #[test]
+    fn wraps_around() {
+        assert_eq!(hsv_to_rgb(0.0, 1.0, 1.0), hsv_to_rgb(1.0, 1.0, 1.0))
+    }
+}
+
+#[cfg(test)]
+mod test_set_resolution {
+    use super::test_grid;
+    use crate::transform::Backend;
+
+    #[test]
+    fn recalculates_frequencies() {
+        let samples: Vec<i16> = (0..4096).map(|x| (x % 64) as i16).collect();
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = 64;
+        grid.set_resolution((8, 32), &samples);
+        assert_eq!(33, grid.average_spectrum().len());
+        assert_eq!(8, grid.onset_function().len());
+        assert_eq!(33, grid.latest_magnitudes().len());
+    }
+
+    #[test]
+    fn naive_backend() {
+        let samples: Vec<i16> = (0..4096).map(|x| (x % 64) as i16 - 32).collect();
+        let mut expected = test_grid(4, 8000);
+        let mut naive = test_grid(4, 8000);
+        naive.backend = Backend::Naive;
+        for grid in [&mut expected, &mut naive].iter_mut() {
+            grid.window_size = 64;
+            grid.set_resolution((8, 32), &samples);
+        }
+        for (e, a) in expected
+            .average_spectrum()
+            .iter()
+            .zip(naive.average_spectrum())
+        {
+            assert!((e - a).abs() < 1e-6)
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_set_samples {
+    use super::test_grid;
+
+    #[test]
+    fn keeps_resolution() {
+        let first: Vec<i16> = (0..4096).map(|x| (x % 64) as i16 - 32).collect();
+        let second: Vec<i16> = (0..4096).map(|x| (x % 16) as i16 - 8).collect();
+        let mut switched = test_grid(4, 8000);
+        switched.window_size = 64;
+        switched.set_resolution((8, 32), &first);
+        switched.set_samples(&second);
+        let mut expected = test_grid(4, 8000);
+        expected.window_size = 64;
+        expected.set_resolution((8, 32), &second);
+        assert_eq!(expected.average_spectrum(), switched.average_spectrum());
+        assert_eq!(8, switched.onset_function().len());
+    }
+
+    #[test]
+    fn onsets() {
+        // silent for the first four columns of 64 samples
+        let samples: Vec<i16> = (0..512)
+            .map(|x| {
+                if x < 256 {
+                    0
+                } else {
+                    ((x % 64) * 100 - 3150) as i16
+                }
+            })
+            .collect();
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = 64;
+        grid.set_resolution((8, 32), &samples);
+        assert_eq!(vec![256], grid.onsets(0.5));
+        assert!(grid.onsets(1.0).is_empty());
+    }
+
+    #[test]
+    fn envelope() {
+        let samples: Vec<i16> = (0..4096).map(|x| (x % 64) as i16 - 32).collect();
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = 64;
+        grid.set_samples(&samples);
+        assert_eq!(samples.len(), grid.envelope.len());
+        assert!(grid
+            .envelope
+            .iter()
+            .all(|level| *level >= 1.0 && *level <= 32.0));
+    }
+}
+
+#[cfg(test)]
+mod test_set_frequency_range {
+    use super::test_grid;
+    use crate::units::ScaleRange;
+
+    #[test]
+    fn keeps_frequencies() {
+        let samples: Vec<i16> = (0..4096).map(|x| (x % 64) as i16 - 32).collect();
+        let mut grid = test_grid(8, 8000);
+        grid.window_size = 64;
+        grid.set_samples(&samples);
+        let spectrum = grid.average_spectrum();
+
+        let range = grid.set_frequency_range(500.0, 1500.0).unwrap();
+        assert_eq!(ScaleRange::new(500.0, 1500.0).unwrap(), range);
+        assert_eq!(range, grid.y.range);
+        assert_eq!(spectrum, grid.average_spectrum());
+
+        assert!(grid.set_frequency_range(1500.0, 500.0).is_err());
+        assert_eq!(range, grid.y.range);
+    }
+}
+
+#[cfg(test)]
+mod test_highlights {
+    use super::test_grid;
+    use iced::Color;
+
+    #[test]
+    fn highlight_and_clear() {
+        let mut grid = test_grid(4, 8000);
+        grid.highlight_frequency(60.0, Color::WHITE);
+        grid.highlight_frequency(440.0, Color::BLACK);
+        assert_eq!(
+            vec![(60.0, Color::WHITE), (440.0, Color::BLACK)],
+            grid.highlighted_frequencies
+        );
+        grid.clear_highlights();
+        assert!(grid.highlighted_frequencies.is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_harmonics {
+    use super::test_grid;
+
+    #[test]
+    fn multiples() {
+        let mut grid = test_grid(4, 8000);
+        grid.fundamental = Some(100.0);
+        assert_eq!(
+            vec![200.0, 300.0, 400.0, 500.0, 600.0, 700.0, 800.0],
+            grid.harmonics()
+        )
+    }
+
+    #[test]
+    fn stops_at_nyquist() {
+        let mut grid = test_grid(4, 8000);
+        grid.fundamental = Some(1500.0);
+        assert_eq!(vec![3000.0], grid.harmonics())
+    }
+
+    #[test]
+    fn no_fundamental() {
+        assert!(test_grid(4, 8000).harmonics().is_empty())
+    }
+}
+
+#[cfg(test)]
+mod test_window_size {
+    use super::{test_grid, Grid};
+
+    // a 125 Hz sawtooth
+    fn sawtooth(window_size: usize) -> Grid {
+        let samples: Vec<i16> = (0..4096).map(|x| ((x % 64) * 100 - 3150) as i16).collect();
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = window_size;
+        grid.set_resolution((8, 128), &samples);
+        grid
+    }
+
+    #[test]
+    fn independent_of_resolution() {
+        assert_eq!(513, sawtooth(1024).average_spectrum().len());
+        assert_eq!(129, sawtooth(256).average_spectrum().len());
+    }
+
+    #[test]
+    fn zero_padded_peak() {
+        // the sawtooth repeats every 64 samples, 125 Hz at 8 kHz
+        let peaks = &sawtooth(1024).peaks;
+        assert!(
+            peaks
+                .iter()
+                .all(|peak| (peak - 125.0).abs() < 8000.0 / 1024.0),
+            "{:?}",
+            peaks
+        );
+    }
+
+    #[test]
+    fn longer_than_window() {
+        // silent for the first window of the column, only the samples after it make a sound
+        let samples: Vec<i16> = (0..1024)
+            .map(|x| {
+                if x < 512 {
+                    0
+                } else {
+                    ((x % 64) * 100 - 3150) as i16
+                }
+            })
+            .collect();
+        let mut grid = test_grid(1, 8000);
+        grid.window_size = 64;
+        grid.set_resolution((1, 512), &samples);
+        assert!(grid.average_spectrum().iter().any(|x| *x > 0.0));
+        assert!((grid.peaks[0] - 125.0).abs() < 8000.0 / 64.0);
+    }
+}
+
+#[cfg(test)]
+mod test_matrix {
+    use super::{test_grid, Grid};
+    use crate::units::Scale;
+
+    #[test]
+    fn there_and_back_again() {
+        let samples: Vec<i16> = (0..4096).map(|x| ((x % 64) * 100 - 3150) as i16).collect();
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = 256;
+        grid.set_resolution((8, 128), &samples);
+
+        let matrix = grid.to_matrix();
+        assert_eq!((8, 129), (matrix.len(), matrix[0].len()));
+
+        let loaded =
+            Grid::from_matrix(matrix.clone(), 8000, 1, Scale::default(), Scale::default()).unwrap();
+        assert_eq!(256, loaded.window_size);
+        assert_eq!(matrix, loaded.to_matrix());
+        assert_eq!(grid.peaks, loaded.peaks);
+    }
+
+    #[test]
+    fn snapshot_is_transposed() {
+        let matrix = vec![vec![0.0, 0.25, 1.0], vec![0.5, 0.75, 0.0]];
+        let grid = Grid::from_matrix(matrix, 8000, 1, Scale::default(), Scale::default()).unwrap();
+        // highest bin on top
+        assert_eq!(
+            vec![vec![1.0, 0.0], vec![0.25, 0.75], vec![0.0, 0.5]],
+            grid.snapshot()
+        );
+    }
+
+    #[test]
+    fn snapshot_png() {
+        let matrix = vec![vec![0.0, 0.25, 1.0], vec![0.5, 0.75, 0.0]];
+        let grid = Grid::from_matrix(matrix, 8000, 1, Scale::default(), Scale::default()).unwrap();
+        let png = grid.snapshot_png().unwrap();
+        assert_eq!(b"\x89PNG", &png[..4]);
+        // a column per time column and a row per bin
+        assert_eq!(&[0, 0, 0, 2, 0, 0, 0, 3], &png[16..24]);
+    }
+
+    #[test]
+    fn ragged() {
+        let matrix = vec![vec![0.0; 5], vec![0.0; 4]];
+        assert!(Grid::from_matrix(matrix, 8000, 1, Scale::default(), Scale::default()).is_err())
+    }
+
+    #[test]
+    fn empty() {
+        assert!(Grid::from_matrix(vec![], 8000, 1, Scale::default(), Scale::default()).is_err())
+    }
+}
+
+#[cfg(test)]
+mod test_bin_edges {
+    use super::bin_edges;
+    use crate::units::{Scale, ScaleRange};
+
+    #[test]
+    fn full_range() {
+        let scale = Scale::default().with_range(ScaleRange::new(0.0, 4000.0).unwrap());
+        assert_eq!(
+            vec![0.0, 25.0, 50.0, 75.0, 100.0],
+            bin_edges(4, 8000, &scale, 100.0)
+        )
+    }
+
+    #[test]
+    fn zoomed_range_is_clamped() {
+        let scale = Scale::default().with_range(ScaleRange::new(1000.0, 2000.0).unwrap());
+        assert_eq!(
+            vec![0.0, 0.0, 100.0, 100.0, 100.0],
+            bin_edges(4, 8000, &scale, 100.0)
+        )
+    }
+}
+
+#[cfg(test)]
+mod test_gate {
+    use super::test_grid;
+
+    #[test]
+    fn original_is_untouched() {
+        let samples: Vec<i16> = (0..4096)
+            .map(|x| ((x * 7919) % 2000 - 1000) as i16)
+            .collect();
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = 64;
+        grid.set_resolution((8, 32), &samples);
+        let original = grid.to_matrix();
+
+        grid.gate = true;
+        grid.update_cleaned();
+        assert_eq!(original, grid.to_matrix());
+        assert_eq!(original.len() * original[0].len(), grid.cleaned.len());
+        assert!(grid.cleaned.contains(&0.0));
+
+        grid.gate = false;
+        grid.update_cleaned();
+        assert!(grid.cleaned.is_empty());
+    }
+
+    #[test]
+    fn silence() {
+        // eg. the start of live mode, every column is silent
+        let mut grid = test_grid(4, 8000);
+        grid.window_size = 64;
+        grid.gate = true;
+        grid.set_resolution((8, 32), &[0; 4096]);
+        assert!(!grid.cleaned.is_empty());
+        assert!(grid.cleaned.iter().all(|x| *x == 0.0));
+    }
+
+    #[test]
+    fn denoise_removes_leading_noise() {
+        // steady noise throughout, with a tone only after the first half second
+        let samples: Vec<i16> = (0..8000)
+            .map(|x| {
+                let noise = ((x * 7919) % 200 - 100) as f64;
+                let tone = if x >= 4000 {
+                    8000.0 * (x as f64 * std::f64::consts::TAU / 8.0).sin()
+                } else {
+                    0.0
+                };
+                (noise + tone) as i16
+            })
+            .collect();
+        let mut grid = test_grid(8, 8000);
+        grid.window_size = 64;
+        // eight columns of an eighth of a second each
+        grid.set_resolution((8, 500), &samples);
+        grid.denoise = true;
+        grid.update_cleaned();
+
+        let n_bins = grid.n_bins();
+        let columns: Vec<&[f64]> = grid.cleaned.chunks_exact(n_bins).collect();
+        // the noise estimate is the mean of the first half second, which is all noise
+        assert!(columns[..4].iter().any(|column| column.contains(&0.0)));
+        // the tone at an eighth of the sample rate survives
+        let tone_bin = n_bins / 4;
+        assert!(columns[4..].iter().all(|column| column[tone_bin] > 0.5));
+    }
+}
+
+#[cfg(test)]
+mod test_reference {
+    use super::test_grid;
+
+    #[test]
+    fn identical_reference() {
+        let samples: Vec<i16> = (0..512).map(|x| (x % 64) as i16 - 32).collect();
+        let mut grid = test_grid(8, 8000);
+        grid.window_size = 64;
+        grid.reference = Some(samples.clone());
+        grid.set_resolution((8, 32), &samples);
+        assert!(grid.comparing());
+        assert!(grid
+            .average_spectrum()
+            .iter()
+            .all(|x| (x - 0.5).abs() < 1e-9))
+    }
+
+    #[test]
+    fn louder_than_reference() {
+        let samples: Vec<i16> = (0..512).map(|x| (x % 64) as i16 - 32).collect();
+        let reference: Vec<i16> = (0..512).map(|x| (x % 16) as i16 - 8).collect();
+        let mut grid = test_grid(8, 8000);
+        grid.window_size = 64;
+        grid.reference = Some(reference);
+        grid.set_resolution((8, 32), &samples);
+        // the 125 Hz fundamental of samples is absent from the reference
+        assert!(grid.average_spectrum()[1] > 0.5)
+    }
+}
+
+#[cfg(test)]
+mod test_normalize {
+    use super::{normalize_columns, test_grid, Normalize};
+
+    // the loudest bin of each column
+    fn column_peaks(normalize: Normalize) -> Vec<f64> {
+        // a sawtooth over the first four columns, the same at a tenth of the level over the last
+        let samples: Vec<i16> = (0..512)
+            .map(|x| ((x % 64) as i16 - 32) * if x < 256 { 100 } else { 10 })
+            .collect();
+        let mut grid = test_grid(8, 8000);
+        grid.window_size = 64;
+        grid.normalize = normalize;
+        grid.set_resolution((8, 32), &samples);
+        grid.to_matrix()
+            .iter()
+            .map(|column| column.iter().cloned().fold(0.0, f64::max))
+            .collect()
+    }
+
+    #[test]
+    fn per_column() {
+        for peak in column_peaks(Normalize::PerColumn) {
+            assert!((peak - 1.0).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn global_max() {
+        let peaks = column_peaks(Normalize::GlobalMax);
+        for peak in &peaks[..4] {
+            assert!((peak - 1.0).abs() < 1e-9);
+        }
+        for peak in &peaks[4..] {
+            assert!((peak - 0.1).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn silence() {
+        let columns = vec![vec![0.0, 0.0], vec![0.0, 0.0]];
+        assert_eq!(columns, normalize_columns(&columns, Normalize::GlobalMax));
+        assert_eq!(columns, normalize_columns(&columns, Normalize::PerColumn));
+    }
+
+    #[test]
+    fn quiet_column() {
+        // peaks below 1.0 are still scaled up to it
+        let columns = vec![vec![0.25, 0.5]];
+        assert_eq!(
+            vec![vec![0.5, 1.0]],
+            normalize_columns(&columns, Normalize::PerColumn)
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_weighting {
+    use super::test_grid;
+    use crate::analysis::weighting::Weighting;
+
+    fn average_spectrum(weighting: Weighting) -> Vec<f64> {
+        let samples: Vec<i16> = (0..512).map(|x| (x % 64) as i16 - 32).collect();
+        let mut grid = test_grid(8, 8000);
+        grid.window_size = 64;
+        grid.weighting = weighting;
+        grid.set_resolution((8, 32), &samples);
+        grid.average_spectrum()
+    }
+
+    #[test]
+    fn a_weighting_cuts_the_fundamental() {
+        // the 125 Hz fundamental is the loudest bin until weighted by about -16 dB
+        let unweighted = average_spectrum(Weighting::None);
+        let weighted = average_spectrum(Weighting::AWeighting);
+        assert_eq!(1.0, unweighted[1]);
+        assert!(weighted[1] < 0.2);
+        assert_eq!(0.0, weighted[0]);
+    }
+}
+
+#[cfg(test)]
+mod test_bark {
+    use super::{test_grid, DisplayMode};
+    use crate::test_signals::sine;
+
+    #[test]
+    fn bins_share_their_band() {
+        let samples = sine(1000.0, 16000, 8192);
+        let mut grid = test_grid(4, 16000);
+        grid.window_size = 1024;
+        grid.display_mode = DisplayMode::Bark;
+        grid.set_resolution((4, 512), &samples);
+        let column = &grid.to_matrix()[0];
+        // 1 kHz is bin 64, in a band about 160 Hz wide
+        assert!((column[64] - 1.0).abs() < 1e-6);
+        assert_eq!(column[64], column[66]);
+        assert!(column[100] < 0.1);
+        // below 20 Hz nothing is filtered
+        assert_eq!(0.0, column[1]);
+    }
+}
+
+#[cfg(test)]
+mod test_drag_message {
+    use super::test_grid;
+    use crate::messages::Message;
+    use iced::{Point, Size};
+
+    const SIZE: Size = Size {
+        width: 100.0,
+        height: 100.0,
+    };
+
+    #[test]
+    fn click() {
+        let point = Point::new(50.0, 50.0);
+        assert!(test_grid(8, 8000)
+            .drag_message(point, Point::new(52.0, 51.0), SIZE)
+            .is_none())
+    }
+
+    #[test]
+    fn vertical_drag() {
+        // dragged upwards, from 1 kHz to 3 kHz
+        match test_grid(8, 8000).drag_message(Point::new(50.0, 75.0), Point::new(55.0, 25.0), SIZE)
+        {
+            Some(Message::FrequencyRangeChanged { min, max }) => {
+                assert_eq!((1000.0, 3000.0), (min, max))
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+
+    #[test]
+    fn horizontal_drag() {
+        match test_grid(8, 8000).drag_message(Point::new(80.0, 50.0), Point::new(20.0, 45.0), SIZE)
+        {
+            Some(Message::TimeRangeChanged { start_sec, end_sec }) => {
+                assert_eq!((2.0, 8.0), (start_sec, end_sec))
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+
+    #[test]
+    fn annotating_drag() {
+        let mut grid = test_grid(8, 8000);
+        grid.annotating = true;
+        match grid.drag_message(Point::new(80.0, 25.0), Point::new(20.0, 75.0), SIZE) {
+            Some(Message::AnnotationRegionSelected(annotation)) => {
+                assert_eq!((2.0, 8.0), (annotation.time_start, annotation.time_end));
+                assert_eq!((1000.0, 3000.0), (annotation.freq_min, annotation.freq_max));
+                assert!(annotation.label.is_empty());
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+    }
+}
+
+#[cfg(test)]
+mod test_text_labels {
+    use super::{test_grid, Grid};
+    use crate::analysis::annotation::TextLabel;
+    use crate::messages::Message;
+    use iced::canvas::{Cursor, Event, Program};
+    use iced::{keyboard, mouse, Point, Rectangle, Size};
+
+    fn bounds() -> Rectangle {
+        Rectangle::new(Point::ORIGIN, Size::new(100.0, 100.0))
+    }
+
+    fn click(grid: &mut Grid, position: Point) -> Option<Message> {
+        let cursor = Cursor::Available(position);
+        let pressed = Event::Mouse(mouse::Event::ButtonPressed(mouse::Button::Left));
+        grid.update(pressed, bounds(), cursor);
+        let released = Event::Mouse(mouse::Event::ButtonReleased(mouse::Button::Left));
+        grid.update(released, bounds(), cursor).1
+    }
+
+    fn delete(grid: &mut Grid, position: Point) -> Option<Message> {
+        let pressed = Event::Keyboard(keyboard::Event::KeyPressed {
+            key_code: keyboard::KeyCode::Delete,
+            modifiers: keyboard::Modifiers::default(),
+        });
+        grid.update(pressed, bounds(), Cursor::Available(position))
+            .1
+    }
+
+    #[test]
+    fn click_places_label() {
+        let mut grid = test_grid(8, 8000);
+        grid.labeling = true;
+        match click(&mut grid, Point::new(20.0, 75.0)) {
+            Some(Message::TextLabelPlaced(label)) => {
+                assert_eq!((2.0, 1000.0), (label.time, label.frequency));
+                assert!(label.text.is_empty());
+            }
+            message => panic!("unexpected {:?}", message),
+        }
+        assert_eq!(None, grid.fundamental);
+    }
+
+    #[test]
+    fn click_places_fundamental() {
+        let mut grid = test_grid(8, 8000);
+        assert!(click(&mut grid, Point::new(20.0, 75.0)).is_none());
+        assert_eq!(Some(1000.0), grid.fundamental);
+    }
+
+    #[test]
+    fn delete_over_label() {
+        let mut grid = test_grid(8, 8000);
+        grid.labels.push(TextLabel {
+            time: 5.0,
+            frequency: 2000.0,
+            text: "engine".to_string(),
+        });
+        match delete(&mut grid, Point::new(55.0, 50.0)) {
+            Some(Message::TextLabelRemoved(0)) => (),
+            message => panic!("unexpected {:?}", message),
         }
+        assert!(delete(&mut grid, Point::new(20.0, 20.0)).is_none());
     }
 }
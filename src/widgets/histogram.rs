@@ -0,0 +1,113 @@
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry, Path, Stroke},
+    Color, Element, Length, Point, Rectangle, Size,
+};
+
+use crate::messages::Message;
+
+pub const N_BUCKETS: usize = 256;
+const DASH_LENGTH: f32 = 6.0;
+const DASH_GAP: f32 = 4.0;
+
+// number of samples in each of N_BUCKETS equal width buckets spanning i16::MIN..=i16::MAX
+pub fn bucket(samples: &[i16]) -> Vec<u64> {
+    let mut counts = vec![0; N_BUCKETS];
+    let bucket_width = (1 << 16) / N_BUCKETS as i32;
+    for sample in samples {
+        counts[((*sample as i32 - i16::MIN as i32) / bucket_width) as usize] += 1;
+    }
+    counts
+}
+
+// vertical bar chart of sample amplitudes, quiet samples dominate so heights are log scaled
+pub struct Histogram {
+    counts: Vec<u64>,
+    // dashed lines marking the outermost buckets, anything past them is at full scale
+    pub show_full_scale: bool,
+    fill_proportion: u16,
+    pub cache: Cache,
+}
+
+impl Histogram {
+    pub fn new(samples: &[i16], fill_proportion: u16) -> Histogram {
+        Histogram {
+            counts: bucket(samples),
+            show_full_scale: true,
+            fill_proportion,
+            cache: Cache::new(),
+        }
+    }
+
+    pub fn update_samples(&mut self, samples: &[i16]) {
+        self.counts = bucket(samples);
+        self.cache.clear();
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let fill_proportion = self.fill_proportion;
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::FillPortion(fill_proportion))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for Histogram {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let histogram = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+            let bar_width = bounds.width / N_BUCKETS as f32;
+            let max = self.counts.iter().cloned().max().unwrap_or(0);
+            if max > 0 {
+                let log_max = (max as f32).ln_1p();
+                for (bucket, count) in self.counts.iter().enumerate() {
+                    let height = (*count as f32).ln_1p() / log_max * bounds.height;
+                    let point = Point::new(bucket as f32 * bar_width, bounds.height);
+                    let bar = Size::new(bar_width, -height);
+                    frame.fill(&Path::rectangle(point, bar), Color::from_rgb(0.0, 1.0, 1.0));
+                }
+            }
+
+            if self.show_full_scale {
+                let stroke = Stroke::default().with_color(Color::WHITE).with_width(1.0);
+                for x in [bar_width, bounds.width - bar_width].iter() {
+                    let dashes = Path::new(|builder| {
+                        let mut y = 0.0;
+                        while y < bounds.height {
+                            builder.move_to(Point::new(*x, y));
+                            builder.line_to(Point::new(*x, (y + DASH_LENGTH).min(bounds.height)));
+                            y += DASH_LENGTH + DASH_GAP;
+                        }
+                    });
+                    frame.stroke(&dashes, stroke);
+                }
+            }
+        });
+        vec![histogram]
+    }
+}
+
+#[cfg(test)]
+mod test_bucket {
+    use super::{bucket, N_BUCKETS};
+
+    #[test]
+    fn extremes() {
+        let counts = bucket(&[i16::MIN, i16::MAX]);
+        assert_eq!(N_BUCKETS, counts.len());
+        assert_eq!((1, 1), (counts[0], counts[N_BUCKETS - 1]))
+    }
+
+    #[test]
+    fn silence_is_centered() {
+        let counts = bucket(&[0, 0, 0, -1]);
+        assert_eq!((3, 1), (counts[N_BUCKETS / 2], counts[N_BUCKETS / 2 - 1]))
+    }
+
+    #[test]
+    fn every_sample_counted() {
+        let samples: Vec<i16> = (0..1000).map(|x| (x * 97) as i16).collect();
+        assert_eq!(1000, bucket(&samples).iter().sum::<u64>())
+    }
+}
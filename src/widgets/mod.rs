@@ -1,2 +1,37 @@
+pub mod average_spectrum;
 pub mod axis;
+pub mod cepstrum;
+pub mod colormap;
+pub mod colormap_picker;
+pub mod frame_counter;
+pub mod goniometer;
 pub mod grid;
+pub mod histogram;
+pub mod stereo_width;
+pub mod swatch;
+pub mod text_overlay;
+pub mod waveform;
+
+use iced::Length;
+
+// exactly pixels when given, eg. on a fixed size kiosk display, otherwise a share of the
+// window which follows its size
+pub fn length(fill_proportion: u16, pixels: Option<u16>) -> Length {
+    pixels.map_or(Length::FillPortion(fill_proportion), Length::Units)
+}
+
+#[cfg(test)]
+mod test_length {
+    use super::length;
+    use iced::Length;
+
+    #[test]
+    fn fill_portion() {
+        assert_eq!(Length::FillPortion(20), length(20, None));
+    }
+
+    #[test]
+    fn fixed() {
+        assert_eq!(Length::Units(480), length(20, Some(480)));
+    }
+}
@@ -0,0 +1,100 @@
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry, Path, Stroke},
+    Color, Element, Length, Point, Rectangle, Size,
+};
+
+use crate::analysis::stereo::stereo_correlation;
+use crate::messages::Message;
+
+const HEIGHT: u16 = 24;
+const TEXT_SIZE: f32 = 14.0;
+
+// green for a wide image at 0.0, turning red towards mono at 1.0 and blue towards
+// phase inversion at -1.0
+pub fn correlation_color(correlation: f32) -> Color {
+    let c = correlation.clamp(-1.0, 1.0);
+    if c >= 0.0 {
+        Color::from_rgb(c, 1.0 - c, 0.0)
+    } else {
+        Color::from_rgb(0.0, 1.0 + c, -c)
+    }
+}
+
+// horizontal bar from -1.0 on the left to 1.0 on the right, filled from the center out to the
+// correlation of the samples shown in the goniometer
+#[derive(Debug, Default)]
+pub struct StereoWidthMeter {
+    pub correlation: f32,
+    cache: Cache,
+}
+
+impl StereoWidthMeter {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn update_samples(&mut self, left: &[i16], right: &[i16]) {
+        self.correlation = stereo_correlation(left, right);
+        self.cache.clear();
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::Units(HEIGHT))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for StereoWidthMeter {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let meter = self.cache.draw(bounds.size(), |frame| {
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+            let center = bounds.width / 2.0;
+            let x = center + self.correlation.clamp(-1.0, 1.0) * center;
+            frame.fill_rectangle(
+                Point::new(center.min(x), 0.0),
+                Size::new((x - center).abs(), bounds.height),
+                correlation_color(self.correlation),
+            );
+
+            let guide = Stroke::default()
+                .with_color(Color::from_rgb(0.3, 0.3, 0.3))
+                .with_width(1.0);
+            frame.stroke(
+                &Path::line(Point::new(center, 0.0), Point::new(center, bounds.height)),
+                guide,
+            );
+            frame.fill_text(canvas::Text {
+                content: format!("correlation {:.2}", self.correlation),
+                position: Point::new(4.0, (bounds.height - TEXT_SIZE) / 2.0),
+                color: Color::WHITE,
+                size: TEXT_SIZE,
+                ..Default::default()
+            });
+        });
+        vec![meter]
+    }
+}
+
+#[cfg(test)]
+mod test_correlation_color {
+    use super::{correlation_color, StereoWidthMeter};
+    use iced::Color;
+
+    #[test]
+    fn endpoints() {
+        assert_eq!(Color::from_rgb(0.0, 1.0, 0.0), correlation_color(0.0));
+        assert_eq!(Color::from_rgb(1.0, 0.0, 0.0), correlation_color(1.0));
+        assert_eq!(Color::from_rgb(0.0, 0.0, 1.0), correlation_color(-1.0));
+        assert_eq!(correlation_color(1.0), correlation_color(2.0));
+    }
+
+    #[test]
+    fn update_samples() {
+        let mut meter = StereoWidthMeter::new();
+        meter.update_samples(&[1, -2, 3], &[-1, 2, -3]);
+        assert!((meter.correlation + 1.0).abs() < 1e-6);
+    }
+}
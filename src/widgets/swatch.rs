@@ -0,0 +1,46 @@
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry},
+    Color, Element, Length, Point, Rectangle, Size,
+};
+
+use crate::messages::Message;
+use crate::widgets::colormap::{Colormap, PREVIEW_SIZE};
+
+// a colormap's gradient, shown next to its name
+pub struct Swatch {
+    colors: Vec<Color>,
+    cache: Cache,
+}
+
+impl Swatch {
+    pub fn new(colormap: &Colormap) -> Swatch {
+        Swatch {
+            colors: colormap.gradient(PREVIEW_SIZE.0),
+            cache: Cache::new(),
+        }
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        Canvas::new(self)
+            .width(Length::Units(PREVIEW_SIZE.0 as u16 / 2))
+            .height(Length::Units(PREVIEW_SIZE.1 as u16))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for Swatch {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let swatch = self.cache.draw(bounds.size(), |frame| {
+            let column_width = bounds.width / self.colors.len() as f32;
+            for (i, color) in self.colors.iter().enumerate() {
+                frame.fill_rectangle(
+                    Point::new(i as f32 * column_width, 0.0),
+                    // overlapping the next column so no gaps show between them
+                    Size::new(column_width + 1.0, bounds.height),
+                    *color,
+                );
+            }
+        });
+        vec![swatch]
+    }
+}
@@ -0,0 +1,234 @@
+use iced::{
+    canvas::{self, Frame},
+    text_input, Align, Color, Element, Point, Rectangle, Row, Size, Text, TextInput,
+};
+
+use crate::analysis::annotation::TextLabel;
+use crate::messages::Message;
+use crate::units::{normalize, Scale};
+
+const TEXT_SIZE: f32 = 16.0;
+const MARKER_SIZE: f32 = 4.0;
+const COLOR: Color = Color::WHITE;
+
+// the label being typed after a click on the grid, canvases can not hold widgets so the text
+// input is shown below the grid rather than over it
+#[derive(Default)]
+pub struct TextOverlay {
+    pub pending: Option<TextLabel>,
+    input: text_input::State,
+}
+
+impl TextOverlay {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    // replaces any label which was still being typed
+    pub fn edit(&mut self, label: TextLabel) {
+        self.pending = Some(label);
+        self.input = text_input::State::focused();
+    }
+
+    pub fn set_text(&mut self, text: String) {
+        if let Some(label) = &mut self.pending {
+            label.text = text;
+        }
+    }
+
+    // None when there is nothing to keep, eg. the text was left empty
+    pub fn confirm(&mut self) -> Option<TextLabel> {
+        self.pending
+            .take()
+            .filter(|label| !label.text.trim().is_empty())
+    }
+
+    pub fn editing(&self) -> bool {
+        self.pending.is_some()
+    }
+
+    pub fn view<'a>(&'a mut self) -> Option<Element<'a, Message>> {
+        let label = self.pending.as_ref()?;
+        Some(
+            Row::new()
+                .align_items(Align::Center)
+                .spacing(20)
+                .push(Text::new("Text label:"))
+                .push(
+                    TextInput::new(
+                        &mut self.input,
+                        "enter to add",
+                        &label.text,
+                        Message::TextLabelChanged,
+                    )
+                    .padding(5)
+                    .on_submit(Message::TextLabelConfirmed),
+                )
+                .into(),
+        )
+    }
+}
+
+// where the label's point is drawn within bounds, None when it is outside of the scales
+fn anchor(label: &TextLabel, x: &Scale, y: &Scale, bounds: Rectangle) -> Option<Point> {
+    let (normalized_x, normalized_y) = (normalize(label.time, x), normalize(label.frequency, y));
+    if !(0.0..=1.0).contains(&normalized_x) || !(0.0..=1.0).contains(&normalized_y) {
+        return None;
+    }
+    Some(Point::new(
+        normalized_x * bounds.width,
+        bounds.height - normalized_y * bounds.height,
+    ))
+}
+
+// the text is drawn to the right of the point it was placed at, its width estimated from the
+// number of characters as canvas text can not be measured
+fn text_bounds(label: &TextLabel, anchor: Point) -> Rectangle {
+    let width = label.text.chars().count() as f32 * TEXT_SIZE * 0.6;
+    Rectangle::new(
+        Point::new(anchor.x - MARKER_SIZE, anchor.y - TEXT_SIZE / 2.0),
+        Size::new(width + 2.0 * MARKER_SIZE, TEXT_SIZE),
+    )
+}
+
+pub fn draw_labels(
+    frame: &mut Frame,
+    labels: &[TextLabel],
+    x: &Scale,
+    y: &Scale,
+    bounds: Rectangle,
+) {
+    for label in labels {
+        let anchor = match anchor(label, x, y, bounds) {
+            Some(anchor) => anchor,
+            None => continue,
+        };
+        frame.fill_rectangle(
+            Point::new(anchor.x - MARKER_SIZE / 2.0, anchor.y - MARKER_SIZE / 2.0),
+            Size::new(MARKER_SIZE, MARKER_SIZE),
+            COLOR,
+        );
+        frame.fill_text(canvas::Text {
+            content: label.text.clone(),
+            position: Point::new(anchor.x + MARKER_SIZE, anchor.y),
+            color: COLOR,
+            size: TEXT_SIZE,
+            vertical_alignment: iced::VerticalAlignment::Center,
+            ..Default::default()
+        });
+    }
+}
+
+// index of the label drawn under position, the last drawn when they overlap as it is on top
+pub fn label_at(
+    labels: &[TextLabel],
+    position: Point,
+    x: &Scale,
+    y: &Scale,
+    bounds: Rectangle,
+) -> Option<usize> {
+    labels.iter().rposition(|label| {
+        anchor(label, x, y, bounds)
+            .map(|anchor| text_bounds(label, anchor).contains(position))
+            .unwrap_or(false)
+    })
+}
+
+#[cfg(test)]
+mod test_label_at {
+    use super::label_at;
+    use crate::analysis::annotation::TextLabel;
+    use crate::units::{Scale, ScaleRange};
+    use iced::{Point, Rectangle, Size};
+
+    fn label(time: f32, frequency: f32, text: &str) -> TextLabel {
+        TextLabel {
+            time,
+            frequency,
+            text: text.to_string(),
+        }
+    }
+
+    fn scales() -> (Scale, Scale) {
+        (
+            Scale::default().with_range(ScaleRange::new(0.0, 10.0).unwrap()),
+            Scale::default().with_range(ScaleRange::new(0.0, 1000.0).unwrap()),
+        )
+    }
+
+    fn bounds() -> Rectangle {
+        Rectangle::new(Point::ORIGIN, Size::new(100.0, 100.0))
+    }
+
+    #[test]
+    fn over_text() {
+        let (x, y) = scales();
+        // drawn from (50, 50) rightwards
+        let labels = [label(5.0, 500.0, "siren")];
+        assert_eq!(
+            Some(0),
+            label_at(&labels, Point::new(60.0, 52.0), &x, &y, bounds())
+        );
+        assert_eq!(
+            None,
+            label_at(&labels, Point::new(40.0, 50.0), &x, &y, bounds())
+        );
+        assert_eq!(
+            None,
+            label_at(&labels, Point::new(60.0, 70.0), &x, &y, bounds())
+        );
+    }
+
+    #[test]
+    fn topmost() {
+        let (x, y) = scales();
+        let labels = [label(5.0, 500.0, "first"), label(5.5, 500.0, "second")];
+        assert_eq!(
+            Some(1),
+            label_at(&labels, Point::new(60.0, 50.0), &x, &y, bounds())
+        );
+    }
+
+    #[test]
+    fn outside_of_scales() {
+        let (x, y) = scales();
+        let labels = [label(20.0, 500.0, "later")];
+        assert_eq!(
+            None,
+            label_at(&labels, Point::new(99.0, 50.0), &x, &y, bounds())
+        );
+    }
+}
+
+#[cfg(test)]
+mod test_text_overlay {
+    use super::TextOverlay;
+    use crate::analysis::annotation::TextLabel;
+
+    fn placed() -> TextLabel {
+        TextLabel {
+            time: 1.0,
+            frequency: 100.0,
+            text: String::new(),
+        }
+    }
+
+    #[test]
+    fn confirm() {
+        let mut overlay = TextOverlay::new();
+        overlay.edit(placed());
+        overlay.set_text("bird".to_string());
+        assert!(overlay.editing());
+        assert_eq!("bird", overlay.confirm().unwrap().text);
+        assert!(!overlay.editing());
+    }
+
+    #[test]
+    fn empty_text() {
+        let mut overlay = TextOverlay::new();
+        overlay.edit(placed());
+        overlay.set_text("  ".to_string());
+        assert_eq!(None, overlay.confirm());
+        assert!(!overlay.editing());
+    }
+}
@@ -0,0 +1,309 @@
+use std::ops::Range;
+
+use iced::{
+    canvas::{self, Cache, Canvas, Cursor, Geometry, Path, Stroke},
+    Color, Element, Length, Point, Rectangle,
+};
+
+use crate::io::microphone::find_trigger;
+use crate::messages::Message;
+
+// how far below zero the samples must fall before a rising zero crossing triggers
+const TRIGGER_HYSTERESIS: i16 = 256;
+
+// (min, max) of each of n_buckets consecutive runs of samples, together covering all of them
+//
+// drawing a line between the two keeps peaks visible when there are more samples than
+// pixels, where plotting every nth sample would skip most of them
+pub fn min_max_buckets(samples: &[i16], n_buckets: usize) -> Vec<(i16, i16)> {
+    if samples.is_empty() || n_buckets == 0 {
+        return vec![];
+    }
+    let n_buckets = n_buckets.min(samples.len());
+    (0..n_buckets)
+        .map(|bucket| {
+            // proportional edges so the remainder is spread over the buckets rather than dropped
+            let start = bucket * samples.len() / n_buckets;
+            let end = (bucket + 1) * samples.len() / n_buckets;
+            samples[start..end]
+                .iter()
+                .fold((i16::MAX, i16::MIN), |(min, max), sample| {
+                    (min.min(*sample), max.max(*sample))
+                })
+        })
+        .collect()
+}
+
+// largest of each of n_buckets consecutive runs of an envelope, edges as in min_max_buckets
+pub fn max_buckets(envelope: &[f32], n_buckets: usize) -> Vec<f32> {
+    if envelope.is_empty() || n_buckets == 0 {
+        return vec![];
+    }
+    let n_buckets = n_buckets.min(envelope.len());
+    (0..n_buckets)
+        .map(|bucket| {
+            let start = bucket * envelope.len() / n_buckets;
+            let end = (bucket + 1) * envelope.len() / n_buckets;
+            envelope[start..end].iter().cloned().fold(0.0, f32::max)
+        })
+        .collect()
+}
+
+// amplitude over time, full scale at the top and bottom edges
+pub struct Waveform {
+    samples: Vec<i16>,
+    // peak level of each sample, drawn above and below the samples, empty to leave it out.
+    // lines up with the end of the samples when it is shorter
+    envelope: Vec<f32>,
+    // the first trigger_search samples are searched for a rising zero crossing to start
+    // drawing from, oscilloscope style, and every sample but that many is drawn
+    trigger_search: usize,
+    // samples where an onset was detected, marked with a vertical line. counted from the end
+    // of the trigger search, like the samples the grid is calculated from while live
+    onsets: Vec<usize>,
+    fill_proportion: u16,
+    pub cache: Cache,
+}
+
+impl Waveform {
+    pub fn new(samples: &[i16], fill_proportion: u16) -> Waveform {
+        Waveform {
+            samples: samples.to_vec(),
+            envelope: vec![],
+            trigger_search: 0,
+            onsets: vec![],
+            fill_proportion,
+            cache: Cache::new(),
+        }
+    }
+
+    pub fn update_samples(&mut self, samples: &[i16]) {
+        self.update_triggered(samples, 0);
+    }
+
+    // eg. successive updates from a microphone, which line up at the trigger instead of
+    // drifting in phase
+    pub fn update_triggered(&mut self, samples: &[i16], trigger_search: usize) {
+        self.samples = samples.to_vec();
+        self.trigger_search = trigger_search;
+        self.cache.clear();
+    }
+
+    // from the trigger, or the end of the search when there is none
+    fn drawn_range(&self) -> Range<usize> {
+        let search = self.trigger_search.min(self.samples.len());
+        if search == 0 {
+            return 0..self.samples.len();
+        }
+        let start = find_trigger(&self.samples[..search], 0, TRIGGER_HYSTERESIS).unwrap_or(search);
+        start..start + self.samples.len() - search
+    }
+
+    // the envelope's level at each sample in range, held at its first level before it starts
+    fn envelope_under(&self, range: Range<usize>) -> Vec<f32> {
+        let last = match self.envelope.len().checked_sub(1) {
+            Some(last) => last,
+            None => return vec![],
+        };
+        let start = self.samples.len().saturating_sub(self.envelope.len());
+        range
+            .map(|i| self.envelope[i.saturating_sub(start).min(last)])
+            .collect()
+    }
+
+    pub fn update_envelope(&mut self, envelope: &[f32]) {
+        self.envelope = envelope.to_vec();
+        self.cache.clear();
+    }
+
+    pub fn update_onsets(&mut self, onsets: &[usize]) {
+        self.onsets = onsets.to_vec();
+        self.cache.clear();
+    }
+
+    // horizontal position of each onset within the drawn range, as a proportion of the width
+    fn onset_positions(&self, range: Range<usize>) -> Vec<f32> {
+        let search = self.trigger_search.min(self.samples.len());
+        let length = range.len().max(1) as f32;
+        self.onsets
+            .iter()
+            .map(|onset| onset + search)
+            .filter(|onset| range.contains(onset))
+            .map(|onset| (onset - range.start) as f32 / length)
+            .collect()
+    }
+
+    pub fn view<'a>(&'a mut self) -> Element<'a, Message> {
+        let fill_proportion = self.fill_proportion;
+        Canvas::new(self)
+            .width(Length::Fill)
+            .height(Length::FillPortion(fill_proportion))
+            .into()
+    }
+}
+
+impl canvas::Program<Message> for Waveform {
+    fn draw(&self, bounds: Rectangle, _cursor: Cursor) -> Vec<Geometry> {
+        let waveform = self.cache.draw(bounds.size(), |frame| {
+            let range = self.drawn_range();
+            let samples = &self.samples[range.clone()];
+            frame.fill_rectangle(Point::ORIGIN, bounds.size(), Color::from_rgb(0.0, 0.0, 0.0));
+
+            let full_scale = -(i16::MIN as f32);
+            let to_y = |sample: i16| bounds.height / 2.0 * (1.0 - sample as f32 / full_scale);
+            let stroke = Stroke::default()
+                .with_color(Color::from_rgb(0.0, 1.0, 0.5))
+                .with_width(1.0);
+
+            let n_pixels = bounds.width as usize;
+            if samples.len() >= n_pixels.max(1) {
+                // a vertical line per pixel column from the quietest to the loudest sample
+                let envelope = Path::new(|builder| {
+                    for (x, (min, max)) in min_max_buckets(samples, n_pixels).iter().enumerate() {
+                        let x = x as f32 + 0.5;
+                        builder.move_to(Point::new(x, to_y(*max)));
+                        // at least a pixel tall so silence still draws a line
+                        builder.line_to(Point::new(x, to_y(*min).max(to_y(*max) + 1.0)));
+                    }
+                });
+                frame.stroke(&envelope, stroke);
+            } else if samples.len() > 1 {
+                // zoomed in past one sample per pixel, the samples are joined by lines
+                let step = bounds.width / (samples.len() - 1) as f32;
+                let line = Path::new(|builder| {
+                    builder.move_to(Point::new(0.0, to_y(samples[0])));
+                    for (i, sample) in samples.iter().enumerate().skip(1) {
+                        builder.line_to(Point::new(i as f32 * step, to_y(*sample)));
+                    }
+                });
+                frame.stroke(&line, stroke);
+            }
+
+            let onsets = Path::new(|builder| {
+                for position in self.onset_positions(range.clone()) {
+                    let x = position * bounds.width;
+                    builder.move_to(Point::new(x, 0.0));
+                    builder.line_to(Point::new(x, bounds.height));
+                }
+            });
+            frame.stroke(
+                &onsets,
+                Stroke::default()
+                    .with_color(Color::from_rgb(1.0, 0.2, 0.2))
+                    .with_width(1.0),
+            );
+
+            let to_y = |level: f32| bounds.height / 2.0 * (1.0 - level / full_scale);
+            let levels = max_buckets(&self.envelope_under(range), n_pixels);
+            if levels.len() > 1 {
+                let step = bounds.width / (levels.len() - 1) as f32;
+                let outline = Path::new(|builder| {
+                    for sign in [1.0, -1.0].iter() {
+                        builder.move_to(Point::new(0.0, to_y(sign * levels[0])));
+                        for (i, level) in levels.iter().enumerate().skip(1) {
+                            builder.line_to(Point::new(i as f32 * step, to_y(sign * level)));
+                        }
+                    }
+                });
+                frame.stroke(
+                    &outline,
+                    Stroke::default()
+                        .with_color(Color::from_rgb(1.0, 0.6, 0.0))
+                        .with_width(1.0),
+                );
+            }
+        });
+        vec![waveform]
+    }
+}
+
+#[cfg(test)]
+mod test_trigger {
+    use super::Waveform;
+
+    #[test]
+    fn untriggered() {
+        let waveform = Waveform::new(&[5, -300, 40], 1);
+        assert_eq!(0..3, waveform.drawn_range());
+    }
+
+    #[test]
+    fn starts_at_the_rising_edge() {
+        let samples = [100, -300, -50, 20, 200, 7, 8, 9];
+        let mut waveform = Waveform::new(&[], 1);
+        waveform.update_triggered(&samples, 5);
+        // every sample but the 5 searched, from the crossing at 3
+        assert_eq!(3..6, waveform.drawn_range());
+        waveform.update_samples(&samples);
+        assert_eq!(0..8, waveform.drawn_range());
+    }
+
+    #[test]
+    fn no_trigger_found() {
+        let mut waveform = Waveform::new(&[], 1);
+        waveform.update_triggered(&[1, 2, 3, 4, 5, 6], 4);
+        assert_eq!(4..6, waveform.drawn_range());
+    }
+
+    #[test]
+    fn envelope_lines_up_with_the_end() {
+        let mut waveform = Waveform::new(&[], 1);
+        waveform.update_triggered(&[0; 6], 2);
+        waveform.update_envelope(&[1.0, 2.0, 3.0, 4.0]);
+        assert_eq!(vec![1.0, 1.0, 2.0, 3.0], waveform.envelope_under(1..5));
+        assert_eq!(vec![3.0, 4.0], waveform.envelope_under(4..6));
+    }
+
+    #[test]
+    fn onsets_follow_the_trigger() {
+        let mut waveform = Waveform::new(&[0; 10], 1);
+        waveform.update_onsets(&[1, 4, 6]);
+        assert_eq!(vec![0.1, 0.4, 0.6], waveform.onset_positions(0..10));
+        // counted from the end of the search, those past the drawn samples are left out
+        waveform.update_triggered(&[0; 10], 2);
+        assert_eq!(vec![0.375, 0.75], waveform.onset_positions(0..8));
+    }
+}
+
+#[cfg(test)]
+mod test_max_buckets {
+    use super::max_buckets;
+
+    #[test]
+    fn keeps_peaks() {
+        let mut envelope = vec![0.5; 100];
+        envelope[42] = 3.0;
+        assert_eq!(vec![0.5, 0.5, 3.0, 0.5, 0.5], max_buckets(&envelope, 5));
+        assert_eq!(vec![1.0], max_buckets(&[1.0], 4));
+        assert!(max_buckets(&[], 4).is_empty());
+    }
+}
+
+#[cfg(test)]
+mod test_min_max_buckets {
+    use super::min_max_buckets;
+
+    #[test]
+    fn keeps_peaks() {
+        // a single loud sample between quiet ones is never skipped
+        let mut samples = vec![0; 1000];
+        samples[501] = i16::MAX;
+        samples[502] = i16::MIN;
+        let buckets = min_max_buckets(&samples, 10);
+        assert_eq!(10, buckets.len());
+        assert_eq!((i16::MIN, i16::MAX), buckets[5]);
+        assert_eq!((0, 0), buckets[4]);
+    }
+
+    #[test]
+    fn uneven() {
+        let samples: Vec<i16> = (0..10).collect();
+        assert_eq!(vec![(0, 2), (3, 5), (6, 9)], min_max_buckets(&samples, 3));
+    }
+
+    #[test]
+    fn more_buckets_than_samples() {
+        assert_eq!(vec![(1, 1), (-1, -1)], min_max_buckets(&[1, -1], 5));
+        assert!(min_max_buckets(&[], 5).is_empty());
+    }
+}
@@ -0,0 +1,48 @@
+use std::convert::TryFrom;
+
+use spectrogram::io::wav::WAV;
+use spectrogram::transform::window::WindowFunction;
+use spectrogram::transform::Backend;
+use spectrogram::units::Scale;
+use spectrogram::widgets::grid::Grid;
+
+const DEMO: &[u8] = include_bytes!("../src/demo.wav");
+
+// demo.wav through the whole pipeline, from parsing to the intensities which are drawn
+fn demo_snapshot() -> Vec<Vec<f32>> {
+    let wav = WAV::try_from(DEMO).expect("demo.wav to parse");
+    let samples = wav.channel(0).expect("demo.wav to have a channel").to_vec();
+    let mut grid = Grid::new(
+        50,
+        wav.fmt_header.sample_rate,
+        20,
+        Scale::default(),
+        Scale::default(),
+        Backend::default(),
+    );
+    // already the default, set so a change of default does not change what is checked
+    grid.window = WindowFunction::Hann;
+    grid.update_frequencies((50, 256), &samples);
+    grid.snapshot()
+}
+
+#[test]
+fn finite() {
+    for pixel in demo_snapshot().iter().flatten() {
+        assert!(pixel.is_finite(), "{} is not finite", pixel);
+    }
+}
+
+#[test]
+fn signal_present() {
+    let snapshot = demo_snapshot();
+    assert!(snapshot.iter().flatten().any(|pixel| *pixel > 0.5));
+}
+
+#[test]
+fn sparse() {
+    let snapshot = demo_snapshot();
+    let pixels: Vec<f32> = snapshot.into_iter().flatten().collect();
+    let mean = pixels.iter().sum::<f32>() / pixels.len() as f32;
+    assert!(mean < 0.3, "mean intensity {}", mean);
+}